@@ -0,0 +1,387 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{Column, Dataset, Schema};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::ops::RangeInclusive;
+use std::str::FromStr;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const CATALOGUE: &str = "./data/emission-factors.csv";
+const DAYS_PER_YEAR: f64 = 365.25;
+const MONTHS_PER_YEAR: f64 = 12.0;
+
+//////////////////////////////////////// frequencies ////////////////////////////////////////
+
+/// How often a behavior happens; each frequency asks for its duration in its own unit,
+/// e.g. hours per day for Daily but days per week for Weekly.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Frequency {
+    Daily,    // Hours per day
+    Weekly,   // Days per week
+    Biweekly, // Days per two weeks
+    Monthly,  // Days per month
+    Annually, // Months per year
+}
+
+impl Frequency {
+    pub const ALL: [Frequency; 5] = [Frequency::Daily, Frequency::Weekly, Frequency::Biweekly, Frequency::Monthly, Frequency::Annually];
+
+    /// The form value, as in `frequency=daily`.
+    pub fn name(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "daily",
+            Frequency::Weekly => "weekly",
+            Frequency::Biweekly => "biweekly",
+            Frequency::Monthly => "monthly",
+            Frequency::Annually => "annually",
+        }
+    }
+
+    pub fn label(&self) -> &'static str {
+        match self {
+            Frequency::Daily => "Daily",
+            Frequency::Weekly => "Weekly",
+            Frequency::Biweekly => "Bi-Weekly",
+            Frequency::Monthly => "Monthly",
+            Frequency::Annually => "Annually",
+        }
+    }
+
+    /// The durations that can be chosen for this frequency.
+    pub fn durations(&self) -> RangeInclusive<u32> {
+        match self {
+            Frequency::Daily => 1..=24,
+            Frequency::Weekly => 1..=7,
+            Frequency::Biweekly => 1..=14,
+            Frequency::Monthly => 1..=31,
+            Frequency::Annually => 1..=12,
+        }
+    }
+
+    /// How a duration reads in the form, e.g. "3 day(s) per week".
+    pub fn describe(&self, duration: u32) -> String {
+        let per = match self {
+            Frequency::Daily => "hour(s) per day",
+            Frequency::Weekly => "day(s) per week",
+            Frequency::Biweekly => "day(s) per two weeks",
+            Frequency::Monthly => "day(s) per month",
+            Frequency::Annually => "month(s) per year",
+        };
+        format!("{} {}", duration, per)
+    }
+
+    /// Days a year on which a behavior happens at all; every day of the year for Daily.
+    fn days_per_year(&self, duration: u32) -> f64 {
+        let duration = duration as f64;
+        match self {
+            Frequency::Daily => DAYS_PER_YEAR,
+            Frequency::Weekly => duration * DAYS_PER_YEAR / 7.0,
+            Frequency::Biweekly => duration * DAYS_PER_YEAR / 14.0,
+            Frequency::Monthly => (duration * MONTHS_PER_YEAR).min(DAYS_PER_YEAR),
+            Frequency::Annually => duration * DAYS_PER_YEAR / MONTHS_PER_YEAR,
+        }
+    }
+
+    /// Hours a year spent on a behavior, counting `hours_per_day` for every day of it.
+    fn hours_per_year(&self, duration: u32, hours_per_day: f64) -> f64 {
+        let duration = duration as f64;
+        match self {
+            Frequency::Daily => duration * DAYS_PER_YEAR,
+            Frequency::Weekly => duration * hours_per_day * DAYS_PER_YEAR / 7.0,
+            Frequency::Biweekly => duration * hours_per_day * DAYS_PER_YEAR / 14.0,
+            Frequency::Monthly => duration * hours_per_day * MONTHS_PER_YEAR,
+            Frequency::Annually => duration * hours_per_day * DAYS_PER_YEAR / MONTHS_PER_YEAR,
+        }
+    }
+}
+
+impl FromStr for Frequency {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        Frequency::ALL.into_iter().find(|frequency| frequency.name() == name).ok_or_else(|| {
+            let names: Vec<&str> = Frequency::ALL.iter().map(Frequency::name).collect();
+            format!("unknown frequency '{}' (expected one of: {})", name, names.join(", "))
+        })
+    }
+}
+
+impl fmt::Display for Frequency {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+//////////////////////////////////////// catalogue ////////////////////////////////////////
+
+/// What an emission factor is counted per.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Unit {
+    Hour,
+    Day,
+    Km,
+}
+
+impl Unit {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Unit::Hour => "hour",
+            Unit::Day => "day",
+            Unit::Km => "km",
+        }
+    }
+}
+
+impl FromStr for Unit {
+    type Err = String;
+
+    fn from_str(name: &str) -> Result<Self, Self::Err> {
+        [Unit::Hour, Unit::Day, Unit::Km].into_iter().find(|unit| unit.name() == name)
+            .ok_or_else(|| format!("unknown unit '{}' (expected hour, day or km)", name))
+    }
+}
+
+/// One row of the catalogue: a behavior and its emission factor.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Behavior {
+    pub name: String,             // Form value, as in `behavior=driving`
+    pub label: String,            // Shown in the form
+    pub factor: f64,              // kg CO₂ per unit
+    pub unit: Unit,
+    pub hours_per_day: f64,       // Hours of the behavior on a day it happens
+    pub km_per_hour: Option<f64>, // Average speed, to turn time into distance for per-km factors
+    pub source: String,           // Where the factor comes from
+}
+
+/// A frequency × duration answer turned into yearly emissions, step by step.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Breakdown {
+    pub behavior: String,
+    pub label: String,
+    pub frequency: Frequency,
+    pub duration: u32,
+    pub schedule: String,      // The answer as the form reads it
+    pub hours: f64,            // Hours a year spent on the behavior
+    pub quantity: f64,         // The hours converted to the unit of the factor
+    pub unit: Unit,
+    pub factor: f64,           // kg CO₂ per unit
+    pub annual_emissions: f64, // kg CO₂ a year
+    pub source: String,
+}
+
+impl Behavior {
+    /// Annualizes an answer to the form into yearly emissions.
+    ///
+    /// Args:
+    ///     frequency: How often the behavior happens.
+    ///     duration: How long each time, in the unit of the frequency (see `Frequency::durations`).
+    ///
+    /// Returns:
+    ///     The hours a year, their amount in the unit of the factor and the resulting kg CO₂.
+    pub fn annualize(&self, frequency: Frequency, duration: u32) -> Result<Breakdown, Box<dyn Error>> {
+        let durations = frequency.durations();
+        if !durations.contains(&duration) {
+            return Err(format!("a {} duration must be between {} and {}", frequency, durations.start(), durations.end()).into());
+        }
+
+        let hours = frequency.hours_per_year(duration, self.hours_per_day);
+        let quantity = match self.unit {
+            Unit::Hour => hours,
+            // Counted at most once a day: heating all 24 hours of a day is still one heating day
+            Unit::Day => (hours / self.hours_per_day).min(frequency.days_per_year(duration)),
+            Unit::Km => hours * self.km_per_hour.unwrap_or_default(),
+        };
+        Ok(Breakdown {
+            behavior: self.name.clone(),
+            label: self.label.clone(),
+            frequency,
+            duration,
+            schedule: frequency.describe(duration),
+            hours,
+            quantity,
+            unit: self.unit,
+            factor: self.factor,
+            annual_emissions: quantity * self.factor,
+            source: self.source.clone(),
+        })
+    }
+}
+
+impl fmt::Display for Breakdown {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{}, {}: {:.0} hours a year", self.label, self.schedule, self.hours)?;
+        match self.unit {
+            Unit::Hour => {}
+            Unit::Day => write!(f, ", or {:.1} days", self.quantity)?,
+            Unit::Km => write!(f, ", or about {:.0} km", self.quantity)?,
+        }
+        write!(f, " at {} kg CO₂/{}, emitting {:.0} kg CO₂ a year", self.factor, self.unit.name(), self.annual_emissions)
+    }
+}
+
+/// Schema of `emission-factors.csv`.
+pub fn catalogue_schema() -> Schema {
+    Schema::new(vec![
+        Column::text("Behavior"),
+        Column::text("Label"),
+        Column::float("Factor").unit("kg CO₂"),
+        Column::text("Unit"),
+        Column::float("Hours Per Day"),
+        Column::float("Km Per Hour").optional(),
+        Column::text("Source").optional(),
+    ])
+}
+
+/// The behaviors the form offers, with their emission factors.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Catalogue {
+    pub behaviors: Vec<Behavior>, // In file order
+}
+
+impl Catalogue {
+    /// Reads and checks a catalogue; per-km behaviors need a speed.
+    pub fn load(path: &str) -> Result<Self, Box<dyn Error>> {
+        let dataset = Dataset::load(path, catalogue_schema())?;
+        let names = dataset.texts("Behavior")?;
+        let labels = dataset.texts("Label")?;
+        let factors = dataset.floats("Factor")?;
+        let units = dataset.texts("Unit")?;
+        let hours = dataset.floats("Hours Per Day")?;
+        let speeds = dataset.optional_floats("Km Per Hour")?;
+        let sources = dataset.texts("Source")?;
+
+        let mut behaviors: Vec<Behavior> = Vec::with_capacity(dataset.len());
+        for row in 0..dataset.len() {
+            let invalid = |message: String| format!("{}, line {}: {}", path, dataset.line(row), message);
+            let unit: Unit = units[row].parse().map_err(invalid)?;
+            if behaviors.iter().any(|behavior| behavior.name == names[row]) {
+                return Err(invalid(format!("duplicate behavior '{}'", names[row])).into());
+            }
+            if !(factors[row].is_finite() && factors[row] >= 0.0) {
+                return Err(invalid("the factor must be zero or more".to_string()).into());
+            }
+            if !(hours[row] > 0.0 && hours[row] <= 24.0) {
+                return Err(invalid("hours per day must be more than 0 and at most 24".to_string()).into());
+            }
+            if unit == Unit::Km && !speeds[row].is_some_and(|speed| speed > 0.0) {
+                return Err(invalid("a per-km factor needs a positive speed in km per hour".to_string()).into());
+            }
+
+            behaviors.push(Behavior {
+                name: names[row].to_string(),
+                label: labels[row].to_string(),
+                factor: factors[row],
+                unit,
+                hours_per_day: hours[row],
+                km_per_hour: speeds[row],
+                source: sources[row].to_string(),
+            });
+        }
+        Ok(Self { behaviors })
+    }
+
+    pub fn get(&self, name: &str) -> Option<&Behavior> {
+        self.behaviors.iter().find(|behavior| behavior.name == name)
+    }
+
+    /// Names of every behavior, for error messages.
+    pub fn names(&self) -> Vec<&str> {
+        self.behaviors.iter().map(|behavior| behavior.name.as_str()).collect()
+    }
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use std::fs;
+
+    const HEADER: &str = "Behavior,Label,Factor(kg CO₂),Unit,Hours Per Day,Km Per Hour,Source\n";
+
+    fn behavior(unit: Unit, hours_per_day: f64, km_per_hour: Option<f64>) -> Behavior {
+        Behavior {
+            name: "test".to_string(), label: "Testing".to_string(), factor: 0.5, unit, hours_per_day, km_per_hour, source: "fixture".to_string(),
+        }
+    }
+
+    // Writes `rows` under the catalogue header and loads it
+    fn load(name: &str, rows: &str) -> Result<Catalogue, Box<dyn Error>> {
+        let path = std::env::temp_dir().join(format!("climate-predict-{}-{}.csv", std::process::id(), name));
+        fs::write(&path, format!("{}{}", HEADER, rows)).unwrap();
+        let catalogue = Catalogue::load(path.to_str().unwrap());
+        fs::remove_file(&path).unwrap();
+        catalogue
+    }
+
+    #[test]
+    fn every_frequency_counts_its_days_and_hours_a_year() {
+        let (duration, hours_per_day) = (3, 2.0);
+        let expected = [
+            (Frequency::Daily, DAYS_PER_YEAR, 3.0 * DAYS_PER_YEAR), // The duration is the hours of each day
+            (Frequency::Weekly, 3.0 * DAYS_PER_YEAR / 7.0, 6.0 * DAYS_PER_YEAR / 7.0),
+            (Frequency::Biweekly, 3.0 * DAYS_PER_YEAR / 14.0, 6.0 * DAYS_PER_YEAR / 14.0),
+            (Frequency::Monthly, 36.0, 72.0),
+            (Frequency::Annually, 3.0 * DAYS_PER_YEAR / 12.0, 6.0 * DAYS_PER_YEAR / 12.0),
+        ];
+        for (frequency, days, hours) in expected {
+            assert!((frequency.days_per_year(duration) - days).abs() < 1e-9, "{} days", frequency);
+            assert!((frequency.hours_per_year(duration, hours_per_day) - hours).abs() < 1e-9, "{} hours", frequency);
+            assert!(frequency.durations().contains(&duration));
+            assert_eq!(frequency.name().parse::<Frequency>(), Ok(frequency));
+        }
+        // 31 days a month would be 372 days a year
+        assert_eq!(Frequency::Monthly.days_per_year(31), DAYS_PER_YEAR);
+    }
+
+    #[test]
+    fn annualize_converts_the_hours_to_the_unit_of_the_factor() {
+        let hourly = behavior(Unit::Hour, 2.0, None).annualize(Frequency::Weekly, 3).unwrap();
+        assert!((hourly.hours - 6.0 * DAYS_PER_YEAR / 7.0).abs() < 1e-9);
+        assert_eq!((hourly.quantity, hourly.annual_emissions), (hourly.hours, hourly.hours * 0.5));
+        assert_eq!(hourly.schedule, "3 day(s) per week");
+
+        let driving = behavior(Unit::Km, 1.0, Some(40.0)).annualize(Frequency::Daily, 2).unwrap();
+        assert!((driving.quantity - 2.0 * DAYS_PER_YEAR * 40.0).abs() < 1e-9);
+        assert!((driving.annual_emissions - driving.quantity * 0.5).abs() < 1e-9);
+
+        for (frequency, duration) in [(Frequency::Daily, 0), (Frequency::Daily, 25), (Frequency::Weekly, 8), (Frequency::Annually, 13)] {
+            assert!(behavior(Unit::Hour, 1.0, None).annualize(frequency, duration).is_err(), "{} {}", frequency, duration);
+        }
+    }
+
+    #[test]
+    fn per_day_behaviors_count_each_day_once() {
+        let heating = behavior(Unit::Day, 8.0, None);
+        // Half the usual hours on every day is half a day's factor each day
+        let short = heating.annualize(Frequency::Daily, 4).unwrap();
+        assert!((short.quantity - DAYS_PER_YEAR / 2.0).abs() < 1e-9);
+        // All 24 hours of every day is still one heating day each
+        let all_day = heating.annualize(Frequency::Daily, 24).unwrap();
+        assert_eq!(all_day.quantity, DAYS_PER_YEAR);
+        assert!(all_day.hours > all_day.quantity * heating.hours_per_day);
+        assert_eq!(heating.annualize(Frequency::Monthly, 31).unwrap().quantity, DAYS_PER_YEAR);
+        assert_eq!(heating.annualize(Frequency::Weekly, 2).unwrap().quantity, 2.0 * DAYS_PER_YEAR / 7.0);
+    }
+
+    #[test]
+    fn catalogues_are_checked_row_by_row() {
+        let catalogue = load("catalogue", "driving,Driving,0.17,km,1,40,DESNZ\nheating,Heating,6.5,day,8,,\n").unwrap();
+        assert_eq!(catalogue.names(), ["driving", "heating"]);
+        assert_eq!(catalogue.get("heating").unwrap().km_per_hour, None);
+        assert!(catalogue.get("flying").is_none());
+
+        let duplicate = load("duplicate", "driving,Driving,0.17,km,1,40,\ndriving,Driving again,0.2,km,1,40,\n").unwrap_err().to_string();
+        assert!(duplicate.ends_with("line 3: duplicate behavior 'driving'"), "{}", duplicate);
+        let no_speed = load("speed", "driving,Driving,0.17,km,1,,\n").unwrap_err().to_string();
+        assert!(no_speed.ends_with("line 2: a per-km factor needs a positive speed in km per hour"), "{}", no_speed);
+        assert!(load("unit", "driving,Driving,0.17,mile,1,40,\n").unwrap_err().to_string().contains("unknown unit 'mile'"));
+
+        assert!(Catalogue::load(CATALOGUE).unwrap().behaviors.len() > 1);
+    }
+}
@@ -0,0 +1,82 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use climate_predict::interpolate::Method;
+use climate_predict::merge::{self, Manifest};
+use std::env;
+use std::error::Error;
+use std::fs;
+use std::process;
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+const USAGE: &str = "usage: build-dataset [--write [--method linear|step|spline]]";
+
+/// Command-line options: `--write` regenerates the files, otherwise the checked-in copy is verified.
+struct Options {
+    write: bool,
+    method: Option<Method>,
+}
+
+fn parse_args() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options { write: false, method: None };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--write" => options.write = true,
+            "--method" => options.method = Some(args.next().ok_or(USAGE)?.parse()?),
+            _ => return Err(format!("unexpected argument '{}'\n{}", arg, USAGE).into()),
+        }
+    }
+    if !options.write && options.method.is_some() {
+        return Err(format!("--method only applies with --write; the check rebuilds with the interpolation \
+            recorded in {}\n{}", merge::MANIFEST, USAGE).into());
+    }
+    Ok(options)
+}
+
+/// Prints the first line where a checked-in file differs from its rebuild; returns whether they match.
+fn compare(path: &str, checked_in: &str, rebuilt: &str, method: Method) -> bool {
+    match merge::first_difference(checked_in, rebuilt) {
+        None => true,
+        Some((line, expected, actual)) => {
+            eprintln!("{} differs from a rebuild with {} interpolation", path, method);
+            eprintln!("  line {}: checked in '{}', rebuilt '{}'", line, expected, actual);
+            false
+        }
+    }
+}
+
+/// Exits with an error if the CSV or its manifest differs from a rebuild with the manifest's interpolation.
+fn check() -> Result<(), Box<dyn Error>> {
+    let manifest_text = fs::read_to_string(merge::MANIFEST)?;
+    let manifest: Manifest = serde_json::from_str(&manifest_text)?;
+    let method: Method = manifest.interpolation.parse()?;
+    let build = merge::build(method)?;
+
+    let csv_matches = compare(merge::OUTPUT, &fs::read_to_string(merge::OUTPUT)?, &build.csv, method);
+    let manifest_matches = compare(merge::MANIFEST, &manifest_text, &serde_json::to_string_pretty(&build.manifest)?, method);
+    if !(csv_matches && manifest_matches) {
+        eprintln!("The checked-in dataset does not match the raw sources. Run with --write to regenerate it.");
+        process::exit(1);
+    }
+    println!("{} and {} match the raw sources ({} rows, {}–{})",
+        merge::OUTPUT, merge::MANIFEST, build.manifest.rows, build.manifest.first_year, build.manifest.last_year);
+    Ok(())
+}
+
+//////////////////////////////////////// main ////////////////////////////////////////
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_args()?;
+    if !options.write {
+        return check();
+    }
+
+    let build = merge::build(options.method.unwrap_or(Method::Linear))?;
+    fs::write(merge::OUTPUT, &build.csv)?;
+    fs::write(merge::MANIFEST, serde_json::to_string_pretty(&build.manifest)? + "\n")?;
+    println!("Wrote {} ({} rows) and {}", merge::OUTPUT, build.manifest.rows, merge::MANIFEST);
+    Ok(())
+}
+
+// cargo run --bin build-dataset
@@ -0,0 +1,114 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use climate_predict::dataset::{self, Dataset};
+use climate_predict::impute::Strategy;
+use climate_predict::regional::{self, Baseline};
+use climate_predict::regression::{mean_squared_error, LinearModel};
+use climate_predict::sources::AnnualSeries;
+use std::env;
+use std::error::Error;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const DATA: &str = "./data/emission_temp_data.csv";
+const DEGREE: usize = 2; // Degree of the per-city polynomial model
+const REPORT_EVERY: usize = 20; // Print every n-th year of each city's anomalies
+const Z_95: f64 = 1.96; // Standard deviations covering ~95 % of a normal distribution
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+const USAGE: &str = "usage: city [--weighted] [--impute drop|forward-fill|linear|spline|seasonal-mean] [BASELINE_START BASELINE_END]";
+
+/// Global emissions and temperatures by year from the merged dataset.
+fn read_global(path: &str) -> Result<(AnnualSeries, AnnualSeries), Box<dyn Error>> {
+    let dataset = Dataset::load(path, dataset::emission_temperature())?;
+    let years: Vec<i32> = dataset.integers("Year")?.into_iter().map(|year| year as i32).collect();
+    let emissions = years.iter().copied().zip(dataset.floats("Emissions")?).collect();
+    let temps = years.iter().copied().zip(dataset.floats("Lowess")?).collect();
+
+    Ok((emissions, temps))
+}
+
+/// Command-line options.
+struct Options {
+    baseline: Baseline,         // From `START END` arguments, or the GISTEMP default
+    impute: Option<Strategy>,   // Fill missing months before aggregating
+    weighted: bool,             // Weight years by their measurement uncertainty
+}
+
+fn parse_args() -> Result<Options, Box<dyn Error>> {
+    let mut args: Vec<String> = env::args().skip(1).collect();
+    let mut impute = None;
+    if let Some(i) = args.iter().position(|arg| arg == "--impute") {
+        let name = args.get(i + 1).ok_or(USAGE)?;
+        impute = Some(name.parse()?);
+        args.drain(i..i + 2);
+    }
+    let weighted = args.iter().any(|arg| arg == "--weighted");
+    args.retain(|arg| arg != "--weighted");
+
+    let baseline = match args.as_slice() {
+        [] => Baseline::default(),
+        [start, end] => Baseline { start: start.parse()?, end: end.parse()? },
+        _ => return Err(USAGE.into()),
+    };
+    Ok(Options { baseline, impute, weighted })
+}
+
+//////////////////////////////////////// main ////////////////////////////////////////
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_args()?;
+    let baseline = options.baseline;
+    let (emissions, temps) = read_global(DATA)?;
+    let global_emissions: Vec<f64> = emissions.iter().map(|&(_, e)| e).collect();
+    let global_temps: Vec<f64> = temps.iter().map(|&(_, t)| t).collect();
+    let global = LinearModel::fit(&global_emissions, &global_temps)?;
+    println!("global: {:.4} °C per GtCO₂", global.slope);
+
+    for mut city in regional::load_cities(regional::CITY_TEMPERATURES)? {
+        if let Some(strategy) = options.impute {
+            let (filled, report) = city.imputed(strategy)?;
+            println!("\n{}: missing months, {} imputation\n  {}", city.name, strategy, report);
+            city = filled;
+        }
+        let models = city.fit(&emissions, baseline, DEGREE, options.weighted)?;
+        let samples = &models.samples;
+        println!("\n{}, {} ({}): {} months, anomalies vs {}–{}",
+            city.name, city.country, city.coordinates, city.temperatures.len(), baseline.start, baseline.end);
+
+        let uncertainty = city.annual_uncertainty()?.annual_series()?;
+        for (year, anomaly) in city.annual_anomalies(baseline)?.annual_series()?.into_iter().step_by(REPORT_EVERY) {
+            match uncertainty.iter().find(|&&(y, _)| y == year) {
+                Some(&(_, sigma)) => println!("  {}: {:+.2} ± {:.2} °C", year, anomaly, sigma),
+                None => println!("  {}: {:+.2} °C (no published uncertainty)", year, anomaly),
+            }
+        }
+
+        let linear: Vec<f64> = samples.emissions.iter().map(|&e| models.linear.predict(e)).collect();
+        let polynomial: Vec<f64> = samples.emissions.iter().map(|&e| models.polynomial.predict(e)).collect();
+        println!("  {} years overlap the emissions record", samples.years.len());
+        println!("  linear: {:.4} °C per GtCO₂ ({:.1}x global), MSE {:.4}",
+            models.linear.slope, models.linear.slope / global.slope, mean_squared_error(&linear, &samples.anomalies));
+        println!("  polynomial (degree {}): MSE {:.4}", DEGREE, mean_squared_error(&polynomial, &samples.anomalies));
+
+        // Interval for the latest year, at that year's measurement precision when weighted
+        if let (Some(&level), Some(&sigma)) = (samples.emissions.last(), samples.uncertainties.last()) {
+            let weight = match sigma {
+                Some(sigma) if options.weighted => 1.0 / (sigma * sigma),
+                _ => 1.0,
+            };
+            for (name, prediction) in [
+                ("linear", models.linear.predict_with_weight(level, weight)),
+                ("polynomial", models.polynomial.predict_with_weight(level, weight)),
+            ] {
+                let (low, high) = prediction.interval(Z_95);
+                println!("  {} at {:.1} GtCO₂: {:+.2} °C (95 %: {:+.2} to {:+.2})", name, level, prediction.mean, low, high);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// cargo run --bin city
@@ -0,0 +1,282 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::footprint::{FootprintModel, Record, DIETS, RECYCLABLES, SHOWER_FREQUENCIES, TRANSPORTS};
+use std::collections::BTreeMap;
+use std::error::Error;
+
+//////////////////////////////////////// types ////////////////////////////////////////
+
+/// A single actionable change to one answer of a footprint record.
+#[derive(Debug, Clone)]
+pub struct Change {
+    pub question: &'static str,
+    pub from: String,
+    pub to: String,
+}
+
+/// A set of changes together with the emissions the model predicts after applying them.
+#[derive(Debug, Clone)]
+pub struct Recommendation {
+    pub changes: Vec<Change>,
+    pub predicted: f64, // Monthly kg CO2 after the changes
+    pub savings: f64,   // Monthly kg CO2 saved relative to the unchanged record
+}
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+/// Lists the lower-emission alternatives for each actionable question, one list per question.
+///
+/// Only moves towards the low-emission end of each answer are offered: a greener diet, less
+/// private transport, fewer showers and recycling every material.
+fn alternatives(record: &Record) -> Vec<Vec<Change>> {
+    let change = |question, from: &str, to: &str| Change { question, from: from.to_string(), to: to.to_string() };
+    let later = |levels: &[&'static str], current: &str| -> Vec<&'static str> {
+        levels.iter().position(|level| *level == current)
+            .map_or(Vec::new(), |index| levels[index + 1..].to_vec())
+    };
+    let earlier = |levels: &[&'static str], current: &str| -> Vec<&'static str> {
+        levels.iter().position(|level| *level == current)
+            .map_or(Vec::new(), |index| levels[..index].to_vec())
+    };
+
+    let mut questions = Vec::new();
+    questions.push(later(&DIETS, &record.diet).into_iter()
+        .map(|to| change("Diet", &record.diet, to))
+        .collect());
+    questions.push(later(&TRANSPORTS, &record.transport).into_iter()
+        .map(|to| change("Transport", &record.transport, to))
+        .collect());
+    questions.push(earlier(&SHOWER_FREQUENCIES, &record.how_often_shower).into_iter()
+        .map(|to| change("How Often Shower", &record.how_often_shower, to))
+        .collect());
+    if record.recycling.len() < RECYCLABLES.len() {
+        questions.push(vec![change("Recycling", &record.recycling.join(", "), &RECYCLABLES.join(", "))]);
+    }
+    questions
+}
+
+/// Median monthly vehicle distance of the records using each transport mode.
+///
+/// A changed transport takes its mode's typical distance, so a driver who switches to walking is
+/// not scored as walking thousands of kilometres a month, far outside anything the model has seen.
+pub fn typical_distances(records: &[Record]) -> Result<BTreeMap<String, f64>, Box<dyn Error>> {
+    TRANSPORTS.iter()
+        .map(|&transport| {
+            let mut distances: Vec<f64> = records.iter()
+                .filter(|record| record.transport == transport)
+                .map(|record| record.vehicle_monthly_distance_km)
+                .collect();
+            if distances.is_empty() {
+                return Err(format!("no records use {} transport", transport).into());
+            }
+            distances.sort_by(f64::total_cmp);
+            let mid = distances.len() / 2;
+            let median = if distances.len().is_multiple_of(2) { (distances[mid - 1] + distances[mid]) / 2.0 } else { distances[mid] };
+            Ok((transport.to_string(), median))
+        })
+        .collect()
+}
+
+/// Returns a copy of the record with the given changes applied.
+fn apply(record: &Record, changes: &[Change], distances: &BTreeMap<String, f64>) -> Record {
+    let mut changed = record.clone();
+    for change in changes {
+        match change.question {
+            "Diet" => changed.diet = change.to.clone(),
+            "How Often Shower" => changed.how_often_shower = change.to.clone(),
+            "Recycling" => changed.recycling = RECYCLABLES.iter().map(|m| m.to_string()).collect(),
+            "Transport" => {
+                // Giving up the private car means there is no vehicle left to describe
+                changed.transport = change.to.clone();
+                changed.vehicle_type = String::new();
+                changed.vehicle_monthly_distance_km = distances[&change.to];
+            }
+            _ => unreachable!("no actionable question named {}", change.question),
+        }
+    }
+    changed
+}
+
+/// Every combination that picks at most one alternative per question (the empty set included).
+fn combinations(questions: &[Vec<Change>]) -> Vec<Vec<Change>> {
+    let mut sets = vec![Vec::new()];
+    for options in questions {
+        let mut extended = Vec::with_capacity(sets.len() * (options.len() + 1));
+        for set in &sets {
+            extended.push(set.clone());
+            for option in options {
+                let mut with_option = set.clone();
+                with_option.push(option.clone());
+                extended.push(with_option);
+            }
+        }
+        sets = extended;
+    }
+    sets
+}
+
+//////////////////////////////////////// recommendations ////////////////////////////////////////
+
+/// Finds the smallest sets of actionable changes that cut predicted emissions by at least `target`.
+///
+/// The search space is small (at most one alternative per question), so every combination is
+/// scored with the model and the ones with the fewest changes that reach the target are kept.
+///
+/// Args:
+///     model: Trained footprint model.
+///     training: Records the model was trained on, for the typical distance of each transport mode.
+///     record: The person's current answers.
+///     target: Required reduction in monthly kg CO2.
+///
+/// Returns:
+///     The qualifying change sets ranked by estimated savings, largest first. Empty if no
+///     combination of actionable changes reaches the target.
+pub fn recommend(model: &FootprintModel, training: &[Record], record: &Record, target: f64) -> Result<Vec<Recommendation>, Box<dyn Error>> {
+    let current = model.predict(record)?;
+    let candidates = combinations(&alternatives(record));
+    let distances = typical_distances(training)?;
+
+    let rows = candidates.iter()
+        .map(|changes| apply(record, changes, &distances).features())
+        .collect::<Result<Vec<_>, _>>()?;
+    let predictions = model.predict_rows(&rows)?;
+
+    let reaching: Vec<Recommendation> = candidates.into_iter().zip(predictions)
+        .map(|(changes, predicted)| Recommendation { changes, predicted, savings: current - predicted })
+        .filter(|recommendation| !recommendation.changes.is_empty() && recommendation.savings >= target)
+        .collect();
+
+    let fewest = match reaching.iter().map(|recommendation| recommendation.changes.len()).min() {
+        Some(fewest) => fewest,
+        None => return Ok(Vec::new()),
+    };
+    let mut smallest: Vec<Recommendation> = reaching.into_iter()
+        .filter(|recommendation| recommendation.changes.len() == fewest)
+        .collect();
+    smallest.sort_by(|a, b| b.savings.total_cmp(&a.savings));
+    Ok(smallest)
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::footprint;
+
+    const TRANSPORT_EMISSIONS: [f64; 3] = [400.0, 50.0, 0.0]; // By TRANSPORTS
+    const TOLERANCE: f64 = 1e-3;                               // kg CO2; the rank-deficient SVD solve is not exact
+
+    fn person(diet: &str, transport: &str, shower: &str, recycling: &[&str]) -> Record {
+        let mut record = Record {
+            body_type: "normal".to_string(),
+            sex: "female".to_string(),
+            diet: diet.to_string(),
+            how_often_shower: shower.to_string(),
+            heating_energy_source: "electricity".to_string(),
+            transport: transport.to_string(),
+            vehicle_type: if transport == "private" { "petrol".to_string() } else { String::new() },
+            social_activity: "sometimes".to_string(),
+            monthly_grocery_bill: 150.0,
+            frequency_of_traveling_by_air: "rarely".to_string(),
+            vehicle_monthly_distance_km: 0.0,
+            waste_bag_size: "medium".to_string(),
+            waste_bag_weekly_count: 2,
+            how_long_tv_pc_daily_hour: 4,
+            how_many_new_clothes_monthly: 5,
+            how_long_internet_daily_hour: 6,
+            energy_efficiency: "Sometimes".to_string(),
+            recycling: recycling.iter().map(|material| material.to_string()).collect(),
+            cooking_with: vec!["Stove".to_string()],
+            carbon_emission: 0.0,
+        };
+        // Distances spread around each mode's own level; the emissions do not depend on them
+        let spread = SHOWER_FREQUENCIES.iter().position(|level| *level == shower).unwrap() as f64;
+        record.vehicle_monthly_distance_km = match transport {
+            "private" => 3000.0 + 500.0 * spread,
+            "public" => 800.0 + 100.0 * spread,
+            _ => 20.0 + 10.0 * spread,
+        };
+        record.carbon_emission = emissions(&record);
+        record
+    }
+
+    // Exactly linear in the encoded answers, so fit_linear recovers it
+    fn emissions(record: &Record) -> f64 {
+        let index = |levels: &[&str], value: &str| levels.iter().position(|level| *level == value).unwrap() as f64;
+        1500.0 + 100.0 * (3.0 - index(&DIETS, &record.diet))
+            + TRANSPORT_EMISSIONS[index(&TRANSPORTS, &record.transport) as usize]
+            + 60.0 * index(&SHOWER_FREQUENCIES, &record.how_often_shower)
+            + 25.0 * (RECYCLABLES.len() - record.recycling.len()) as f64
+    }
+
+    fn records() -> Vec<Record> {
+        let recycling: [&[&str]; 5] = [&[], &["Paper"], &["Plastic", "Glass"], &["Metal"], &RECYCLABLES];
+        let mut records = Vec::new();
+        for diet in DIETS {
+            for transport in TRANSPORTS {
+                for shower in SHOWER_FREQUENCIES {
+                    records.extend(recycling.iter().map(|materials| person(diet, transport, shower, materials)));
+                }
+            }
+        }
+        records
+    }
+
+    fn destinations(recommendation: &Recommendation) -> Vec<&str> {
+        recommendation.changes.iter().map(|change| change.to.as_str()).collect()
+    }
+
+    #[test]
+    fn recommendations_reach_the_target_with_the_fewest_changes_largest_savings_first() {
+        let records = records();
+        let model = FootprintModel::fit_linear(&records).unwrap();
+        let distances = typical_distances(&records).unwrap();
+        let record = person("omnivore", "private", "twice a day", &["Paper"]);
+        assert!((model.predict(&record).unwrap() - 2455.0).abs() < TOLERANCE);
+
+        // Savings of single changes: walking 400, public transport 350, vegan 300, fewer showers up to 180, recycling 75
+        let single = recommend(&model, &records, &record, 290.0).unwrap();
+        assert_eq!(single.iter().map(destinations).collect::<Vec<_>>(), vec![vec!["walk/bicycle"], vec!["public"], vec!["vegan"]]);
+        for recommendation in &single {
+            let expected = emissions(&record) - emissions(&apply(&record, &recommendation.changes, &distances));
+            assert!((recommendation.savings - expected).abs() < TOLERANCE, "{:?}", recommendation);
+            assert!((recommendation.predicted + recommendation.savings - 2455.0).abs() < TOLERANCE);
+        }
+
+        // No single change saves 450, so every recommendation takes two, each reaching the target
+        let pairs = recommend(&model, &records, &record, 450.0).unwrap();
+        assert!(!pairs.is_empty());
+        assert!(pairs.iter().all(|recommendation| recommendation.changes.len() == 2 && recommendation.savings >= 450.0 - TOLERANCE));
+        assert!(pairs.windows(2).all(|pair| pair[0].savings >= pair[1].savings));
+        assert_eq!(destinations(&pairs[0]), vec!["vegan", "walk/bicycle"]);
+
+        // Every change together saves 300 + 400 + 180 + 75
+        let all = recommend(&model, &records, &record, 955.0 - TOLERANCE).unwrap();
+        assert_eq!(all.len(), 1);
+        assert_eq!(all[0].changes.len(), 4);
+        assert!(recommend(&model, &records, &record, 956.0).unwrap().is_empty());
+    }
+
+    #[test]
+    fn a_new_transport_takes_the_typical_distance_of_its_mode() {
+        let records = footprint::load_records(footprint::DATA).unwrap();
+        let distances = typical_distances(&records).unwrap();
+        let driver = records.iter().find(|record| record.transport == "private" && record.vehicle_monthly_distance_km > 4000.0).unwrap();
+
+        for transport in ["public", "walk/bicycle"] {
+            let change = Change { question: "Transport", from: "private".to_string(), to: transport.to_string() };
+            let switched = apply(driver, &[change], &distances);
+            let range = records.iter()
+                .filter(|record| record.transport == transport)
+                .map(|record| record.vehicle_monthly_distance_km)
+                .fold((f64::MAX, f64::MIN), |(low, high), distance| (low.min(distance), high.max(distance)));
+            let distance = switched.vehicle_monthly_distance_km;
+            assert!(distance >= range.0 && distance <= range.1, "{} km is outside {:?} for {}", distance, range, transport);
+            assert_eq!(distance, distances[transport]);
+            assert_eq!(switched.vehicle_type, "");
+        }
+        assert_eq!(distances["walk/bicycle"], 50.0);
+        assert!(typical_distances(&records[..0]).is_err());
+    }
+}
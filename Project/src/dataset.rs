@@ -0,0 +1,432 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use csv::{ReaderBuilder, StringRecord};
+use std::error::Error;
+use std::fmt;
+use std::fs::{self, File};
+use std::io::Read;
+
+//////////////////////////////////////// schema ////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum ColumnType {
+    Float,
+    Integer,
+    Text,
+}
+
+/// A column the dataset is expected to contain.
+#[derive(Debug, Clone)]
+pub struct Column {
+    pub name: String,
+    pub kind: ColumnType,
+    pub unit: Option<String>,
+    pub required: bool, // Required columns reject empty cells; optional ones load them as Value::Missing
+}
+
+impl Column {
+    pub fn float(name: &str) -> Self {
+        Self::new(name, ColumnType::Float)
+    }
+
+    pub fn integer(name: &str) -> Self {
+        Self::new(name, ColumnType::Integer)
+    }
+
+    pub fn text(name: &str) -> Self {
+        Self::new(name, ColumnType::Text)
+    }
+
+    pub fn unit(mut self, unit: &str) -> Self {
+        self.unit = Some(unit.to_string());
+        self
+    }
+
+    pub fn optional(mut self) -> Self {
+        self.required = false;
+        self
+    }
+
+    fn new(name: &str, kind: ColumnType) -> Self {
+        Self { name: name.to_string(), kind, unit: None, required: true }
+    }
+
+    /// Whether a CSV header refers to this column: either the bare name or the name followed by
+    /// the unit in parentheses, as in `Emissions(GtCO₂)`.
+    fn matches(&self, header: &str) -> bool {
+        let header = header.trim();
+        header == self.name || self.unit.as_ref().is_some_and(|unit| {
+            header == format!("{}({})", self.name, unit) || header == format!("{} ({})", self.name, unit)
+        })
+    }
+}
+
+/// The columns to load from a file, in the order they are stored in the Dataset.
+#[derive(Debug, Clone)]
+pub struct Schema {
+    pub columns: Vec<Column>,
+}
+
+impl Schema {
+    pub fn new(columns: Vec<Column>) -> Self {
+        Self { columns }
+    }
+
+    pub fn column(&self, name: &str) -> Option<&Column> {
+        self.columns.iter().find(|column| column.name == name)
+    }
+
+    /// Guesses a schema from CSV data with a header row, for files nobody has described yet.
+    ///
+    /// A column is Integer if every non-empty cell parses as an integer, Float if every one parses
+    /// as a number, and Text otherwise. All inferred columns are optional, named by their full header.
+    pub fn infer<R: Read>(reader: R) -> Result<Self, Box<dyn Error>> {
+        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(reader);
+        let headers = rdr.headers()?.clone();
+        let mut kinds = vec![ColumnType::Integer; headers.len()];
+
+        for record in rdr.records() {
+            // Malformed rows are reported with their line number when the data is read
+            let Ok(record) = record else { continue };
+            for (kind, raw) in kinds.iter_mut().zip(record.iter()) {
+                let raw = raw.trim();
+                if raw.is_empty() {
+                    continue;
+                }
+                if *kind == ColumnType::Integer && raw.parse::<i64>().is_err() {
+                    *kind = ColumnType::Float;
+                }
+                if *kind == ColumnType::Float && raw.parse::<f64>().is_err() {
+                    *kind = ColumnType::Text;
+                }
+            }
+        }
+
+        Ok(Self::new(headers.iter().zip(kinds).map(|(header, kind)| Column::new(header.trim(), kind).optional()).collect()))
+    }
+}
+
+/// `emission_temp_data.csv`: year, global emissions and smoothed temperature anomaly.
+pub fn emission_temperature() -> Schema {
+    Schema::new(vec![
+        Column::integer("Year"),
+        Column::float("Emissions").unit("GtCO₂"),
+        Column::float("Lowess").unit("°C"),
+    ])
+}
+
+//////////////////////////////////////// values ////////////////////////////////////////
+
+#[derive(Debug, Clone, PartialEq)]
+pub enum Value {
+    Float(f64),
+    Integer(i64),
+    Text(String),
+    Missing,
+}
+
+impl Value {
+    /// Numeric value of a Float or Integer cell.
+    pub fn as_f64(&self) -> Option<f64> {
+        match self {
+            Value::Float(value) => Some(*value),
+            Value::Integer(value) => Some(*value as f64),
+            _ => None,
+        }
+    }
+
+    fn parse(raw: &str, column: &Column) -> Result<Self, String> {
+        let raw = raw.trim();
+        if raw.is_empty() {
+            return if column.required { Err("missing value".to_string()) } else { Ok(Value::Missing) };
+        }
+        match column.kind {
+            ColumnType::Float => raw.parse().map(Value::Float).map_err(|_| format!("'{}' is not a number", raw)),
+            ColumnType::Integer => raw.parse().map(Value::Integer).map_err(|_| format!("'{}' is not an integer", raw)),
+            ColumnType::Text => Ok(Value::Text(raw.to_string())),
+        }
+    }
+}
+
+//////////////////////////////////////// errors ////////////////////////////////////////
+
+/// A single cell that does not match the schema, or a whole row the CSV reader could not split (no column).
+#[derive(Debug, Clone)]
+pub struct RowError {
+    pub line: u64,
+    pub column: Option<String>,
+    pub message: String,
+}
+
+/// Every problem found while loading a file, so they can be fixed in one pass.
+#[derive(Debug)]
+pub struct DatasetError {
+    pub file: String,
+    pub errors: Vec<RowError>,
+}
+
+impl fmt::Display for DatasetError {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} has {} invalid value(s)", self.file, self.errors.len())?;
+        for error in &self.errors {
+            match &error.column {
+                Some(column) => write!(f, "\n  line {}, column {}: {}", error.line, column, error.message)?,
+                None => write!(f, "\n  line {}: {}", error.line, error.message)?,
+            }
+        }
+        Ok(())
+    }
+}
+
+impl Error for DatasetError {}
+
+//////////////////////////////////////// dataset ////////////////////////////////////////
+
+/// A CSV file loaded by column name and validated against a schema.
+#[derive(Debug, Clone)]
+pub struct Dataset {
+    pub schema: Schema,
+    columns: Vec<Vec<Value>>, // One vector per schema column
+    lines: Vec<u64>,          // Line number of each row in the source file
+}
+
+impl Dataset {
+    /// Loads a CSV file with a header row.
+    ///
+    /// Args:
+    ///     path: Path to the CSV file.
+    ///     schema: Columns to load; other columns in the file are ignored.
+    ///
+    /// Returns:
+    ///     The dataset, or a DatasetError listing the line and column of every invalid value.
+    pub fn load(path: &str, schema: Schema) -> Result<Self, Box<dyn Error>> {
+        Self::from_reader(File::open(path)?, schema, path)
+    }
+
+    /// Loads a CSV file whose header row is preceded by free-form text (titles, separator lines).
+    ///
+    /// Everything before the first line starting with `header_start` is skipped; line numbers in
+    /// the dataset and in errors still refer to the original file.
+    pub fn load_after_preamble(path: &str, schema: Schema, header_start: &str) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        let (offset, skipped) = preamble(&contents, path, header_start)?;
+        Self::read(&contents.as_bytes()[offset..], schema, path, skipped)
+    }
+
+    /// Loads any CSV file with a schema guessed by Schema::infer.
+    ///
+    /// Args:
+    ///     path: Path to the CSV file.
+    ///     header_start: Start of the header line, if free-form text precedes it (see load_after_preamble).
+    pub fn load_inferred(path: &str, header_start: Option<&str>) -> Result<Self, Box<dyn Error>> {
+        let contents = fs::read_to_string(path)?;
+        let (offset, skipped) = match header_start {
+            Some(header_start) => preamble(&contents, path, header_start)?,
+            None => (0, 0),
+        };
+        let data = &contents.as_bytes()[offset..];
+        Self::read(data, Schema::infer(data)?, path, skipped)
+    }
+
+    /// Loads CSV data from any reader; `source` names it in error messages.
+    pub fn from_reader<R: Read>(reader: R, schema: Schema, source: &str) -> Result<Self, Box<dyn Error>> {
+        Self::read(reader, schema, source, 0)
+    }
+
+    // `skipped_lines` is added to every line number so they match the original file
+    fn read<R: Read>(mut reader: R, schema: Schema, source: &str, skipped_lines: u64) -> Result<Self, Box<dyn Error>> {
+        // Line numbers are counted from the raw bytes: the csv crate's own count runs one short on CRLF files
+        let mut data = Vec::new();
+        reader.read_to_end(&mut data)?;
+        let mut counter = LineCounter::new(&data, skipped_lines);
+        let mut rdr = ReaderBuilder::new().has_headers(true).from_reader(data.as_slice());
+        let headers = rdr.headers()?.clone();
+
+        // Resolve every schema column to its position in the file
+        let mut positions = Vec::with_capacity(schema.columns.len());
+        for column in &schema.columns {
+            let position = headers.iter().position(|header| column.matches(header)).ok_or_else(|| {
+                format!("{} has no column '{}' (found: {})", source, column.name, headers.iter().collect::<Vec<_>>().join(", "))
+            })?;
+            positions.push(position);
+        }
+
+        let mut columns = vec![Vec::new(); schema.columns.len()];
+        let mut lines = Vec::new();
+        let mut errors = Vec::new();
+        let mut record = StringRecord::new();
+
+        loop {
+            match rdr.read_record(&mut record) {
+                Ok(true) => {}
+                Ok(false) => break,
+                Err(error) => match error.position() {
+                    Some(position) => {
+                        let line = counter.line_at(position.byte());
+                        errors.push(RowError { line, column: None, message: malformed_row(&error) });
+                        continue;
+                    }
+                    None => return Err(error.into()),
+                },
+            }
+            let line = record.position().map_or(0, |position| counter.line_at(position.byte()));
+            for (i, column) in schema.columns.iter().enumerate() {
+                match Value::parse(record.get(positions[i]).unwrap_or(""), column) {
+                    Ok(value) => columns[i].push(value),
+                    Err(message) => errors.push(RowError { line, column: Some(column.name.clone()), message }),
+                }
+            }
+            lines.push(line);
+        }
+
+        if !errors.is_empty() {
+            return Err(Box::new(DatasetError { file: source.to_string(), errors }));
+        }
+        Ok(Self { schema, columns, lines })
+    }
+
+    pub fn len(&self) -> usize {
+        self.lines.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.lines.is_empty()
+    }
+
+    /// Line number of a row in the source file.
+    pub fn line(&self, row: usize) -> u64 {
+        self.lines[row]
+    }
+
+    /// Raw values of a column.
+    pub fn values(&self, name: &str) -> Result<&[Value], Box<dyn Error>> {
+        let index = self.schema.columns.iter().position(|column| column.name == name)
+            .ok_or_else(|| format!("dataset has no column '{}'", name))?;
+        Ok(&self.columns[index])
+    }
+
+    /// A numeric column as floats; fails if any value is missing.
+    pub fn floats(&self, name: &str) -> Result<Vec<f64>, Box<dyn Error>> {
+        self.values(name)?.iter().enumerate()
+            .map(|(row, value)| value.as_f64().ok_or_else(|| {
+                format!("line {}, column {}: expected a number, found {:?}", self.lines[row], name, value).into()
+            }))
+            .collect()
+    }
+
+    /// A numeric column with missing values kept as None.
+    pub fn optional_floats(&self, name: &str) -> Result<Vec<Option<f64>>, Box<dyn Error>> {
+        Ok(self.values(name)?.iter().map(Value::as_f64).collect())
+    }
+
+    /// An integer column; fails if any value is missing.
+    pub fn integers(&self, name: &str) -> Result<Vec<i64>, Box<dyn Error>> {
+        self.values(name)?.iter().enumerate()
+            .map(|(row, value)| match value {
+                Value::Integer(value) => Ok(*value),
+                _ => Err(format!("line {}, column {}: expected an integer, found {:?}", self.lines[row], name, value).into()),
+            })
+            .collect()
+    }
+
+    /// A text column, with missing values as empty strings.
+    pub fn texts(&self, name: &str) -> Result<Vec<&str>, Box<dyn Error>> {
+        Ok(self.values(name)?.iter()
+            .map(|value| match value {
+                Value::Text(text) => text.as_str(),
+                _ => "",
+            })
+            .collect())
+    }
+}
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+// Maps byte offsets to 1-based line numbers, counting '\n' so LF and CRLF files agree; offsets must not decrease
+struct LineCounter<'a> {
+    data: &'a [u8],
+    offset: usize,
+    line: u64,
+}
+
+impl<'a> LineCounter<'a> {
+    fn new(data: &'a [u8], skipped_lines: u64) -> Self {
+        Self { data, offset: 0, line: skipped_lines + 1 }
+    }
+
+    // Line of the first character at or after `byte`: the csv crate puts a record's start before the
+    // terminator of the row above it
+    fn line_at(&mut self, byte: u64) -> u64 {
+        let mut byte = (byte as usize).min(self.data.len());
+        while byte < self.data.len() && matches!(self.data[byte], b'\r' | b'\n') {
+            byte += 1;
+        }
+        if byte > self.offset {
+            self.line += self.data[self.offset..byte].iter().filter(|&&b| b == b'\n').count() as u64;
+            self.offset = byte;
+        }
+        self.line
+    }
+}
+
+// Describes a row the CSV reader rejected, without the reader's own (CRLF-miscounted) position
+fn malformed_row(error: &csv::Error) -> String {
+    match error.kind() {
+        csv::ErrorKind::UnequalLengths { expected_len, len, .. } => format!("row has {} field(s), expected {}", len, expected_len),
+        csv::ErrorKind::Utf8 { .. } => "row is not valid UTF-8".to_string(),
+        _ => error.to_string(),
+    }
+}
+
+// Byte offset of the first line starting with `header_start`, and how many lines come before it
+fn preamble(contents: &str, source: &str, header_start: &str) -> Result<(usize, u64), Box<dyn Error>> {
+    let mut offset = 0;
+    for (skipped, line) in contents.split_inclusive('\n').enumerate() {
+        if line.starts_with(header_start) {
+            return Ok((offset, skipped as u64));
+        }
+        offset += line.len();
+    }
+    Err(format!("{} has no header line starting with '{}'", source, header_start).into())
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const FIXTURE: &str = "Year,Emissions\n2000,20.64\n2010,28.65\n2020,oops\n2022,37.15\n";
+
+    fn schema() -> Schema {
+        Schema::new(vec![Column::integer("Year"), Column::float("Emissions").unit("GtCO₂")])
+    }
+
+    fn invalid_lines(data: &str) -> Vec<u64> {
+        let error = Dataset::from_reader(data.as_bytes(), schema(), "fixture").unwrap_err();
+        error.downcast_ref::<DatasetError>().unwrap().errors.iter().map(|error| error.line).collect()
+    }
+
+    #[test]
+    fn crlf_and_lf_files_report_the_same_lines() {
+        let crlf = FIXTURE.replace('\n', "\r\n");
+        assert_eq!(invalid_lines(FIXTURE), vec![4]);
+        assert_eq!(invalid_lines(&crlf), vec![4]);
+
+        let valid = crlf.replace("oops", "33.4");
+        let dataset = Dataset::from_reader(valid.as_bytes(), schema(), "fixture").unwrap();
+        assert_eq!((0..dataset.len()).map(|row| dataset.line(row)).collect::<Vec<_>>(), vec![2, 3, 4, 5]);
+    }
+
+    #[test]
+    fn malformed_rows_are_reported_with_their_line() {
+        let data = "Year,Emissions\r\n2000,20.64\r\n2010,28.65,extra\r\n2020,oops\r\n";
+        let error = Dataset::from_reader(data.as_bytes(), schema(), "fixture").unwrap_err();
+        let errors = &error.downcast_ref::<DatasetError>().unwrap().errors;
+
+        assert_eq!(errors.len(), 2);
+        assert_eq!((errors[0].line, errors[0].column.as_deref()), (3, None));
+        assert_eq!((errors[1].line, errors[1].column.as_deref()), (4, Some("Emissions")));
+        assert!(error.to_string().contains("line 3: row has 3 field(s), expected 2"), "{}", error);
+    }
+}
@@ -0,0 +1,260 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{self, Dataset};
+use crate::regression::Prediction;
+use crate::scenario::{Pathway, TemperatureModel};
+use std::error::Error;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const PREINDUSTRIAL_PPM: f64 = 278.0; // C0 in the forcing formula
+const GTCO2_PER_PPM: f64 = 7.81; // Mass of CO2 that raises the atmospheric concentration by 1 ppm
+const FORCING_COEFFICIENT: f64 = 5.35; // ΔF = 5.35 · ln(C/C0) W/m² (Myhre et al. 1998)
+const UPPER_HEAT_CAPACITY: f64 = 8.0; // Mixed layer + atmosphere, W·yr/m²/K
+const DEEP_HEAT_CAPACITY: f64 = 100.0; // Deep ocean, W·yr/m²/K
+const OCEAN_EXCHANGE: f64 = 0.7; // γ, heat exchange between the boxes, W/m²/K
+pub const CO2_PPM: (f64, f64) = (290.7, 419.3); // Observed CO2 in CO2_YEARS: 1880 from ice cores, 2023 at Mauna Loa
+pub const CO2_YEARS: (i32, i32) = (1880, 2023); // First and last year of the emissions/temperature dataset
+
+// Calibration grid for the climate feedback λ (W/m²/K)
+const FEEDBACK_RANGE: (f64, f64) = (0.3, 3.0);
+const GRID_STEPS: usize = 270;
+
+//////////////////////////////////////// model ////////////////////////////////////////
+
+/// Tunable parameters of the two-box model.
+#[derive(Debug, Clone, Copy)]
+pub struct Parameters {
+    pub feedback: f64,              // λ: extra outgoing radiation per degree of warming (W/m²/K)
+    pub airborne_fraction: f64,     // Share of each year's emissions that stays in the atmosphere
+    pub initial_concentration: f64, // CO2 (ppm) in the first simulated year
+    pub offset: f64,                // Shifts model warming onto the dataset's anomaly baseline (°C)
+}
+
+impl Parameters {
+    /// Equilibrium warming for a doubling of CO2: ECS = 5.35·ln 2 / λ.
+    pub fn climate_sensitivity(&self) -> f64 {
+        FORCING_COEFFICIENT * 2f64.ln() / self.feedback
+    }
+}
+
+/// Two-box energy-balance model: emissions → CO2 concentration → radiative forcing → temperature.
+///
+/// Each year the emissions raise the concentration by airborne_fraction · E / 7.81 ppm, the
+/// forcing F = 5.35·ln(C/C0) warms the upper box, which loses heat to space (λ·T) and to the deep
+/// ocean (γ·(T - T_deep)). Unlike the regressions the model has memory, so a pathway is always
+/// simulated after replaying the historical emissions it was calibrated on.
+///
+/// CO2 is the only forcing, so the calibrated sensitivity also soaks up the (net warming) effect of
+/// the other greenhouse gases and aerosols and comes out above the IPCC range.
+#[derive(Debug, Clone)]
+pub struct EnergyBalanceModel {
+    pub parameters: Parameters,
+    pub residual_variance: f64, // Mean squared difference between the observed and the calibrated temperatures (°C²)
+    history_years: Vec<i32>,
+    history_emissions: Vec<f64>,
+}
+
+impl EnergyBalanceModel {
+    /// Calibrates the model against the observed concentrations and temperatures.
+    ///
+    /// The airborne fraction is fixed by the carbon budget: it is the share of the recorded
+    /// emissions needed to move CO2 from the first to the last observed concentration. The
+    /// climate feedback λ is then found by grid search; for every grid point the anomaly offset
+    /// has a closed form (the mean residual), so only λ is searched.
+    ///
+    /// Args:
+    ///     years: Consecutive years of the historical record.
+    ///     emissions: Emissions for each year (GtCO₂).
+    ///     temps: Observed temperature anomaly for each year (°C).
+    ///     concentrations: Observed CO2 (ppm) in the first and the last year.
+    ///
+    /// Returns:
+    ///     The model with the parameters that minimise the mean squared error.
+    pub fn calibrate(years: &[i32], emissions: &[f64], temps: &[f64], concentrations: (f64, f64)) -> Result<Self, Box<dyn Error>> {
+        if years.len() < 2 || years.len() != emissions.len() || years.len() != temps.len() {
+            return Err("energy balance calibration needs at least two years of matching emissions and temps".into());
+        }
+        if years.windows(2).any(|pair| pair[1] != pair[0] + 1) {
+            return Err("energy balance calibration needs consecutive years".into());
+        }
+
+        // The first year's emissions are already part of the initial concentration
+        let (initial_concentration, final_concentration) = concentrations;
+        let cumulative: f64 = emissions[1..].iter().sum();
+        if cumulative <= 0.0 {
+            return Err("energy balance calibration needs positive cumulative emissions".into());
+        }
+        let airborne_fraction = (final_concentration - initial_concentration) * GTCO2_PER_PPM / cumulative;
+
+        let mut best: Option<(f64, Parameters)> = None;
+        for step in 0..=GRID_STEPS {
+            let mut parameters = Parameters {
+                feedback: lerp(FEEDBACK_RANGE, step),
+                airborne_fraction,
+                initial_concentration,
+                offset: 0.0,
+            };
+            let warming = simulate(&parameters, emissions);
+            parameters.offset = temps.iter().zip(&warming).map(|(t, w)| t - w).sum::<f64>() / temps.len() as f64;
+
+            let mse = temps.iter().zip(&warming)
+                .map(|(t, w)| (t - w - parameters.offset).powi(2))
+                .sum::<f64>() / temps.len() as f64;
+            if best.is_none_or(|(best_mse, _)| mse < best_mse) {
+                best = Some((mse, parameters));
+            }
+        }
+
+        let (residual_variance, parameters) = best.ok_or("energy balance calibration grid is empty")?;
+        Ok(Self { parameters, residual_variance, history_years: years.to_vec(), history_emissions: emissions.to_vec() })
+    }
+
+    /// Calibrates the model on a file laid out like the merged emissions/temperature dataset
+    /// (see dataset::emission_temperature); it must cover CO2_YEARS, see observed_concentrations.
+    pub fn calibrate_on(path: &str) -> Result<Self, Box<dyn Error>> {
+        let dataset = Dataset::load(path, dataset::emission_temperature())?;
+        let years: Vec<i32> = dataset.integers("Year")?.into_iter().map(|year| year as i32).collect();
+        let concentrations = observed_concentrations(&years).map_err(|error| format!("{}: {}", path, error))?;
+        Self::calibrate(&years, &dataset.floats("Emissions")?, &dataset.floats("Lowess")?, concentrations)
+    }
+
+    /// Temperature anomaly (°C) for each year of the given emissions, starting from the first history year.
+    pub fn temperatures(&self, emissions: &[f64]) -> Vec<f64> {
+        simulate(&self.parameters, emissions).into_iter().map(|w| w + self.parameters.offset).collect()
+    }
+}
+
+impl TemperatureModel for EnergyBalanceModel {
+    fn name(&self) -> String {
+        "energy balance".to_string()
+    }
+
+    fn project(&self, pathway: &Pathway) -> Vec<f64> {
+        let start = match pathway.years.first() {
+            Some(&start) => start,
+            None => return Vec::new(),
+        };
+
+        // Replay the recorded history up to the start of the pathway, then continue along it; a pathway
+        // starting after the record is reached by a straight line from the last recorded emissions
+        let mut spin_up: Vec<f64> = self.history_years.iter().zip(&self.history_emissions)
+            .filter(|(&year, _)| year < start)
+            .map(|(_, &emission)| emission)
+            .collect();
+        if let (Some(&last_year), Some(&last), Some(&first)) = (self.history_years.last(), self.history_emissions.last(), pathway.emissions.first()) {
+            let gap = start - last_year;
+            spin_up.extend((1..gap).map(|step| last + (first - last) * step as f64 / gap as f64));
+        }
+        let mut emissions = spin_up.clone();
+        emissions.extend_from_slice(&pathway.emissions);
+
+        self.temperatures(&emissions).split_off(spin_up.len())
+    }
+}
+
+impl EnergyBalanceModel {
+    /// Projects the pathway with a constant spread: the scatter of the observed record around the
+    /// calibrated model. It does not cover the uncertainty of the parameters themselves.
+    pub fn project_with_uncertainty(&self, pathway: &Pathway) -> Vec<Prediction> {
+        let std = self.residual_variance.sqrt();
+        self.project(pathway).into_iter().map(|mean| Prediction { mean, std }).collect()
+    }
+}
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+/// CO2_PPM, if the record runs from the first to the last of CO2_YEARS.
+///
+/// Only those two concentrations are built in; a record over other years would otherwise be
+/// calibrated with the wrong forcing without any sign of it. Use calibrate with that record's own
+/// concentrations instead.
+pub fn observed_concentrations(years: &[i32]) -> Result<(f64, f64), Box<dyn Error>> {
+    match (years.first(), years.last()) {
+        (Some(&first), Some(&last)) if (first, last) == CO2_YEARS => Ok(CO2_PPM),
+        (Some(&first), Some(&last)) => Err(format!(
+            "the record covers {}–{}, but the built-in CO2 concentrations are for {}–{}", first, last, CO2_YEARS.0, CO2_YEARS.1).into()),
+        _ => Err("the record has no years".into()),
+    }
+}
+
+/// Steps the two boxes forward one year at a time and returns the upper-box warming.
+///
+/// Both boxes start in equilibrium with the initial concentration, so the run does not open
+/// with a spurious warming transient.
+fn simulate(parameters: &Parameters, emissions: &[f64]) -> Vec<f64> {
+    let mut concentration = parameters.initial_concentration;
+    let equilibrium = forcing(concentration) / parameters.feedback;
+    let (mut upper, mut deep) = (equilibrium, equilibrium);
+    let mut warming = Vec::with_capacity(emissions.len());
+
+    for (i, emission) in emissions.iter().enumerate() {
+        if i > 0 {
+            concentration += parameters.airborne_fraction * emission / GTCO2_PER_PPM;
+        }
+        let exchange = OCEAN_EXCHANGE * (upper - deep);
+        upper += (forcing(concentration) - parameters.feedback * upper - exchange) / UPPER_HEAT_CAPACITY;
+        deep += exchange / DEEP_HEAT_CAPACITY;
+        warming.push(upper);
+    }
+
+    warming
+}
+
+/// Radiative forcing (W/m²) of a CO2 concentration relative to preindustrial.
+fn forcing(concentration: f64) -> f64 {
+    FORCING_COEFFICIENT * (concentration.max(1.0) / PREINDUSTRIAL_PPM).ln()
+}
+
+fn lerp((low, high): (f64, f64), step: usize) -> f64 {
+    low + (high - low) * step as f64 / GRID_STEPS as f64
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn parameters(feedback: f64) -> Parameters {
+        Parameters { feedback, airborne_fraction: 1.0, initial_concentration: PREINDUSTRIAL_PPM, offset: 0.0 }
+    }
+
+    // One pulse in the second year that doubles preindustrial CO2, then nothing: constant forcing of 5.35·ln 2
+    fn doubling(years: usize) -> Vec<f64> {
+        let mut emissions = vec![0.0; years];
+        emissions[1] = PREINDUSTRIAL_PPM * GTCO2_PER_PPM;
+        emissions
+    }
+
+    #[test]
+    fn constant_forcing_approaches_the_climate_sensitivity() {
+        for feedback in [FEEDBACK_RANGE.0, 1.2, FEEDBACK_RANGE.1] {
+            let parameters = parameters(feedback);
+            let warming = simulate(&parameters, &doubling(5000));
+            let sensitivity = parameters.climate_sensitivity();
+
+            assert!(warming.windows(2).all(|pair| pair[1] >= pair[0] - 1e-12), "λ = {} overshoots and comes back", feedback);
+            assert!(warming.iter().all(|&w| w <= sensitivity + 1e-9), "λ = {} warms past its ECS", feedback);
+            assert!((warming[warming.len() - 1] - sensitivity).abs() < 1e-3, "λ = {} ends at {}, ECS {}", feedback, warming[warming.len() - 1], sensitivity);
+        }
+    }
+
+    #[test]
+    fn simulate_stays_in_equilibrium_without_emissions() {
+        for feedback in [FEEDBACK_RANGE.0, FEEDBACK_RANGE.1] {
+            let parameters = Parameters { initial_concentration: CO2_PPM.1, ..parameters(feedback) };
+            let equilibrium = forcing(CO2_PPM.1) / feedback;
+            assert!(simulate(&parameters, &[0.0; 1000]).iter().all(|w| (w - equilibrium).abs() < 1e-9));
+        }
+    }
+
+    #[test]
+    fn concentrations_are_only_built_in_for_the_dataset_years() {
+        let years: Vec<i32> = (CO2_YEARS.0..=CO2_YEARS.1).collect();
+        assert_eq!(observed_concentrations(&years).unwrap(), CO2_PPM);
+        assert!(observed_concentrations(&years[..years.len() - 1]).is_err());
+        assert!(observed_concentrations(&years[1..]).is_err());
+        assert!(observed_concentrations(&[]).is_err());
+    }
+}
@@ -0,0 +1,227 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::ops::Range;
+
+//////////////////////////////////////// types ////////////////////////////////////////
+
+/// A named block of columns in an encoded feature vector.
+///
+/// One answer on the footprint form (e.g. "Diet") can expand into several one-hot columns,
+/// so explanations are computed per group rather than per raw column.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct FeatureGroup {
+    pub name: String,
+    pub columns: Range<usize>,
+}
+
+/// How much a single feature group pushed the prediction away from the baseline.
+#[derive(Debug, Clone)]
+pub struct Contribution {
+    pub feature: String,
+    pub value: f64,
+}
+
+/// A local explanation of one prediction.
+///
+/// The contributions always sum to `prediction - baseline`.
+#[derive(Debug, Clone)]
+pub struct Explanation {
+    pub baseline: f64,
+    pub prediction: f64,
+    pub contributions: Vec<Contribution>,
+}
+
+impl Explanation {
+    /// Returns the contributions ordered by absolute size, largest first.
+    pub fn ranked(&self) -> Vec<&Contribution> {
+        let mut ranked: Vec<&Contribution> = self.contributions.iter().collect();
+        ranked.sort_by(|a, b| b.value.abs().total_cmp(&a.value.abs()));
+        ranked
+    }
+}
+
+//////////////////////////////////////// explainers ////////////////////////////////////////
+
+/// Exact contributions for a linear model y = intercept + Σ w_j x_j.
+///
+/// Args:
+///     weights: Model coefficients, one per encoded column.
+///     intercept: Model intercept.
+///     x: Encoded feature vector being explained.
+///     background: Encoded rows whose mean prediction is the baseline (usually the training set).
+///     groups: How encoded columns are grouped into reported features.
+///
+/// Returns:
+///     Explanation where each group contributes Σ_{j ∈ group} w_j (x_j - mean_j), or an error if
+///     the weights, background rows or groups do not fit `x`.
+pub fn linear(weights: &[f64], intercept: f64, x: &[f64], background: &[Vec<f64>], groups: &[FeatureGroup]) -> Result<Explanation, Box<dyn Error>> {
+    if background.is_empty() {
+        return Err("explain::linear needs at least one background row".into());
+    }
+    if weights.len() != x.len() {
+        return Err(format!("explain::linear has {} weights for {} columns", weights.len(), x.len()).into());
+    }
+    check_shapes(x, background, groups)?;
+
+    // Column means of the background data: the linear model's expected input
+    let mut means = vec![0.0; x.len()];
+    for row in background {
+        for (mean, value) in means.iter_mut().zip(row.iter()) {
+            *mean += value / background.len() as f64;
+        }
+    }
+
+    let predict = |row: &[f64]| intercept + weights.iter().zip(row.iter()).map(|(w, v)| w * v).sum::<f64>();
+    let contributions = groups.iter()
+        .map(|group| Contribution {
+            feature: group.name.clone(),
+            value: group.columns.clone().map(|j| weights[j] * (x[j] - means[j])).sum(),
+        })
+        .collect();
+
+    Ok(Explanation { baseline: predict(&means), prediction: predict(x), contributions })
+}
+
+/// Model-agnostic Shapley values estimated by sampling feature orderings.
+///
+/// Each sample draws a background row and a random ordering of the groups, then switches the
+/// groups from the background value to the explained value one at a time, crediting each group
+/// with the change in prediction it caused. The reversed ordering is evaluated with the same
+/// background row to reduce variance. Because every sample telescopes from f(background) to f(x),
+/// the contributions sum exactly to the prediction minus the baseline.
+///
+/// Args:
+///     predict: Batch prediction function over encoded rows.
+///     x: Encoded feature vector being explained.
+///     background: Encoded rows to draw reference values from.
+///     groups: How encoded columns are grouped into reported features.
+///     samples: Number of orderings to draw (each is evaluated forwards and backwards).
+///     seed: Seed for the random number generator, so explanations are reproducible.
+///
+/// Returns:
+///     Explanation whose baseline is the mean prediction over the sampled background rows.
+pub fn sampling_shapley<F>(predict: F, x: &[f64], background: &[Vec<f64>], groups: &[FeatureGroup], samples: usize, seed: u64) -> Result<Explanation, Box<dyn Error>>
+where
+    F: Fn(&[Vec<f64>]) -> Result<Vec<f64>, Box<dyn Error>>,
+{
+    if background.is_empty() || samples == 0 {
+        return Err("explain::sampling_shapley needs background rows and at least one sample".into());
+    }
+    check_shapes(x, background, groups)?;
+
+    let mut rng = StdRng::seed_from_u64(seed);
+    let mut totals = vec![0.0; groups.len()];
+    let mut baseline = 0.0;
+    let mut prediction = 0.0;
+    let mut evaluated = 0;
+
+    for _ in 0..samples {
+        let reference = &background[rng.gen_range(0..background.len())];
+        let mut order: Vec<usize> = (0..groups.len()).collect();
+        order.shuffle(&mut rng);
+
+        for order in [order.clone(), order.into_iter().rev().collect()] {
+            // Build the path of rows from the reference to x, switching one group per step
+            let mut rows = Vec::with_capacity(order.len() + 1);
+            let mut row = reference.clone();
+            rows.push(row.clone());
+            for &g in &order {
+                for j in groups[g].columns.clone() {
+                    row[j] = x[j];
+                }
+                rows.push(row.clone());
+            }
+
+            let outputs = predict(&rows)?;
+            for (step, &g) in order.iter().enumerate() {
+                totals[g] += outputs[step + 1] - outputs[step];
+            }
+            baseline += outputs[0];
+            prediction += outputs[order.len()];
+            evaluated += 1;
+        }
+    }
+
+    let n = evaluated as f64;
+    let contributions = groups.iter().zip(totals)
+        .map(|(group, total)| Contribution { feature: group.name.clone(), value: total / n })
+        .collect();
+
+    Ok(Explanation { baseline: baseline / n, prediction: prediction / n, contributions })
+}
+
+// Every background row has the columns of `x`, and every group lies within them
+fn check_shapes(x: &[f64], background: &[Vec<f64>], groups: &[FeatureGroup]) -> Result<(), Box<dyn Error>> {
+    if let Some((i, row)) = background.iter().enumerate().find(|(_, row)| row.len() != x.len()) {
+        return Err(format!("background row {} has {} columns, expected {}", i, row.len(), x.len()).into());
+    }
+    if let Some(group) = groups.iter().find(|group| group.columns.start > group.columns.end || group.columns.end > x.len()) {
+        return Err(format!("feature group '{}' covers columns {:?} of {}", group.name, group.columns, x.len()).into());
+    }
+    Ok(())
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn groups() -> Vec<FeatureGroup> {
+        vec![
+            FeatureGroup { name: "a".to_string(), columns: 0..1 },
+            FeatureGroup { name: "b".to_string(), columns: 1..3 },
+            FeatureGroup { name: "c".to_string(), columns: 3..4 },
+        ]
+    }
+
+    fn background() -> Vec<Vec<f64>> {
+        vec![vec![0.0, 1.0, 0.0, 2.0], vec![1.0, 0.0, 1.0, -1.0], vec![2.0, 0.0, 0.0, 0.5]]
+    }
+
+    // Contributions sum to the prediction minus the baseline (Shapley efficiency)
+    fn assert_efficient(explanation: &Explanation) {
+        let total: f64 = explanation.contributions.iter().map(|contribution| contribution.value).sum();
+        assert!((total - (explanation.prediction - explanation.baseline)).abs() < 1e-9, "{:?}", explanation);
+    }
+
+    #[test]
+    fn linear_contributions_are_efficient() {
+        let explanation = linear(&[2.0, -1.0, 0.5, 3.0], 1.0, &[1.5, 1.0, 1.0, 0.0], &background(), &groups()).unwrap();
+        assert_efficient(&explanation);
+        assert!((explanation.contributions[0].value - 2.0 * 0.5).abs() < 1e-9);
+    }
+
+    #[test]
+    fn sampled_contributions_are_efficient_with_interactions() {
+        let predict = |rows: &[Vec<f64>]| -> Result<Vec<f64>, Box<dyn Error>> {
+            Ok(rows.iter().map(|row| row[0] * row[3] + (row[1] - row[2]).powi(2) + row[0]).collect())
+        };
+        let explanation = sampling_shapley(predict, &[1.5, 1.0, 1.0, 0.0], &background(), &groups(), 25, 7).unwrap();
+        assert_efficient(&explanation);
+        assert_eq!(explanation.contributions.len(), 3);
+    }
+
+    #[test]
+    fn inputs_that_do_not_fit_x_are_rejected() {
+        let (weights, x) = ([2.0, -1.0, 0.5, 3.0], [1.5, 1.0, 1.0, 0.0]);
+        let error = |result: Result<Explanation, Box<dyn Error>>| result.unwrap_err().to_string();
+
+        assert_eq!(error(linear(&weights[..3], 1.0, &x, &background(), &groups())), "explain::linear has 3 weights for 4 columns");
+        let mut short = background();
+        short[1].pop();
+        assert_eq!(error(linear(&weights, 1.0, &x, &short, &groups())), "background row 1 has 3 columns, expected 4");
+        let mut wide = groups();
+        wide[2].columns = 3..5;
+        assert_eq!(error(linear(&weights, 1.0, &x, &background(), &wide)), "feature group 'c' covers columns 3..5 of 4");
+
+        let predict = |rows: &[Vec<f64>]| -> Result<Vec<f64>, Box<dyn Error>> { Ok(rows.iter().map(|row| row[0]).collect()) };
+        assert!(sampling_shapley(predict, &x, &short, &groups(), 5, 7).is_err());
+        assert!(sampling_shapley(predict, &x, &background(), &wide, 5, 7).is_err());
+    }
+}
@@ -0,0 +1,390 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::explain::{self, Explanation, FeatureGroup};
+use crate::regression::mean_squared_error;
+use csv::ReaderBuilder;
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Deserializer, Serialize};
+use smartcore::ensemble::random_forest_regressor::{RandomForestRegressor, RandomForestRegressorParameters};
+use smartcore::linalg::basic::matrix::DenseMatrix;
+use std::error::Error;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const DATA: &str = "./data/Carbon Emission.csv";
+const BACKGROUND_SIZE: usize = 100; // Training rows kept with the model as the reference for explanations
+const SHAPLEY_SAMPLES: usize = 200; // Orderings drawn per explanation for models without exact contributions
+const SHAPLEY_SEED: u64 = 42;
+
+// Category levels, in increasing order wherever the answer has a natural order
+const BODY_TYPES: [&str; 4] = ["underweight", "normal", "overweight", "obese"];
+const SEXES: [&str; 2] = ["female", "male"];
+pub(crate) const DIETS: [&str; 4] = ["omnivore", "pescatarian", "vegetarian", "vegan"];
+pub(crate) const SHOWER_FREQUENCIES: [&str; 4] = ["less frequently", "daily", "more frequently", "twice a day"];
+const HEATING_SOURCES: [&str; 4] = ["coal", "natural gas", "wood", "electricity"];
+pub(crate) const TRANSPORTS: [&str; 3] = ["private", "public", "walk/bicycle"];
+const VEHICLE_TYPES: [&str; 5] = ["petrol", "diesel", "hybrid", "lpg", "electric"];
+const SOCIAL_ACTIVITIES: [&str; 3] = ["never", "sometimes", "often"];
+const AIR_TRAVEL: [&str; 4] = ["never", "rarely", "frequently", "very frequently"];
+const WASTE_BAG_SIZES: [&str; 4] = ["small", "medium", "large", "extra large"];
+const ENERGY_EFFICIENCY: [&str; 3] = ["No", "Sometimes", "Yes"];
+pub(crate) const RECYCLABLES: [&str; 4] = ["Paper", "Plastic", "Glass", "Metal"];
+const COOKING_METHODS: [&str; 5] = ["Stove", "Oven", "Microwave", "Grill", "Airfryer"];
+
+//////////////////////////////////////// record ////////////////////////////////////////
+
+/// One row of `Carbon Emission.csv`: a person's answers and their monthly emissions (kg CO2).
+#[derive(Debug, Clone, Deserialize)]
+pub struct Record {
+    #[serde(rename = "Body Type")]
+    pub body_type: String,
+    #[serde(rename = "Sex")]
+    pub sex: String,
+    #[serde(rename = "Diet")]
+    pub diet: String,
+    #[serde(rename = "How Often Shower")]
+    pub how_often_shower: String,
+    #[serde(rename = "Heating Energy Source")]
+    pub heating_energy_source: String,
+    #[serde(rename = "Transport")]
+    pub transport: String,
+    #[serde(rename = "Vehicle Type")]
+    pub vehicle_type: String,
+    #[serde(rename = "Social Activity")]
+    pub social_activity: String,
+    #[serde(rename = "Monthly Grocery Bill")]
+    pub monthly_grocery_bill: f64,
+    #[serde(rename = "Frequency of Traveling by Air")]
+    pub frequency_of_traveling_by_air: String,
+    #[serde(rename = "Vehicle Monthly Distance Km")]
+    pub vehicle_monthly_distance_km: f64,
+    #[serde(rename = "Waste Bag Size")]
+    pub waste_bag_size: String,
+    #[serde(rename = "Waste Bag Weekly Count")]
+    pub waste_bag_weekly_count: i32,
+    #[serde(rename = "How Long TV PC Daily Hour")]
+    pub how_long_tv_pc_daily_hour: i32,
+    #[serde(rename = "How Many New Clothes Monthly")]
+    pub how_many_new_clothes_monthly: i32,
+    #[serde(rename = "How Long Internet Daily Hour")]
+    pub how_long_internet_daily_hour: i32,
+    #[serde(rename = "Energy efficiency")]
+    pub energy_efficiency: String,
+    #[serde(rename = "Recycling", deserialize_with = "parse_list")]
+    pub recycling: Vec<String>,
+    #[serde(rename = "Cooking_With", deserialize_with = "parse_list")]
+    pub cooking_with: Vec<String>,
+    #[serde(rename = "CarbonEmission")]
+    pub carbon_emission: f64,
+}
+
+/// Parses the Python-style list cells used by the dataset, e.g. `['Paper', 'Metal']`.
+fn parse_list<'de, D: Deserializer<'de>>(deserializer: D) -> Result<Vec<String>, D::Error> {
+    let raw = String::deserialize(deserializer)?;
+    Ok(raw.trim().trim_start_matches('[').trim_end_matches(']')
+        .split(',')
+        .map(|item| item.trim().trim_matches('\'').to_string())
+        .filter(|item| !item.is_empty())
+        .collect())
+}
+
+impl Record {
+    /// Encodes the answers as a numeric feature vector.
+    ///
+    /// Ordered answers become a single ordinal column, unordered answers are one-hot encoded and
+    /// multi-select answers become one flag per option.
+    ///
+    /// Returns:
+    ///     The feature vector and the groups mapping each question to its columns.
+    pub fn encode(&self) -> Result<(Vec<f64>, Vec<FeatureGroup>), Box<dyn Error>> {
+        let mut encoder = Encoder::default();
+        encoder.ordinal("Body Type", &self.body_type, &BODY_TYPES)?;
+        encoder.ordinal("Sex", &self.sex, &SEXES)?;
+        encoder.one_hot("Diet", &self.diet, &DIETS)?;
+        encoder.ordinal("How Often Shower", &self.how_often_shower, &SHOWER_FREQUENCIES)?;
+        encoder.one_hot("Heating Energy Source", &self.heating_energy_source, &HEATING_SOURCES)?;
+        encoder.one_hot("Transport", &self.transport, &TRANSPORTS)?;
+        encoder.one_hot("Vehicle Type", &self.vehicle_type, &VEHICLE_TYPES)?;
+        encoder.ordinal("Social Activity", &self.social_activity, &SOCIAL_ACTIVITIES)?;
+        encoder.number("Monthly Grocery Bill", self.monthly_grocery_bill);
+        encoder.ordinal("Frequency of Traveling by Air", &self.frequency_of_traveling_by_air, &AIR_TRAVEL)?;
+        encoder.number("Vehicle Monthly Distance Km", self.vehicle_monthly_distance_km);
+        encoder.ordinal("Waste Bag Size", &self.waste_bag_size, &WASTE_BAG_SIZES)?;
+        encoder.number("Waste Bag Weekly Count", self.waste_bag_weekly_count as f64);
+        encoder.number("How Long TV PC Daily Hour", self.how_long_tv_pc_daily_hour as f64);
+        encoder.number("How Many New Clothes Monthly", self.how_many_new_clothes_monthly as f64);
+        encoder.number("How Long Internet Daily Hour", self.how_long_internet_daily_hour as f64);
+        encoder.ordinal("Energy efficiency", &self.energy_efficiency, &ENERGY_EFFICIENCY)?;
+        encoder.flags("Recycling", &self.recycling, &RECYCLABLES)?;
+        encoder.flags("Cooking_With", &self.cooking_with, &COOKING_METHODS)?;
+        Ok((encoder.values, encoder.groups))
+    }
+
+    /// Encodes the answers as a numeric feature vector (see `encode`).
+    pub fn features(&self) -> Result<Vec<f64>, Box<dyn Error>> {
+        Ok(self.encode()?.0)
+    }
+}
+
+/// Builds a feature vector one question at a time, remembering which columns each question owns.
+#[derive(Default)]
+struct Encoder {
+    values: Vec<f64>,
+    groups: Vec<FeatureGroup>,
+}
+
+impl Encoder {
+    fn push(&mut self, name: &str, values: &[f64]) {
+        let start = self.values.len();
+        self.values.extend_from_slice(values);
+        self.groups.push(FeatureGroup { name: name.to_string(), columns: start..self.values.len() });
+    }
+
+    fn number(&mut self, name: &str, value: f64) {
+        self.push(name, &[value]);
+    }
+
+    fn ordinal(&mut self, name: &str, value: &str, levels: &[&str]) -> Result<(), Box<dyn Error>> {
+        let index = levels.iter().position(|level| *level == value)
+            .ok_or_else(|| format!("unknown {} answer '{}'", name, value))?;
+        self.push(name, &[index as f64]);
+        Ok(())
+    }
+
+    // An empty answer (e.g. no vehicle) encodes as all zeros
+    fn one_hot(&mut self, name: &str, value: &str, levels: &[&str]) -> Result<(), Box<dyn Error>> {
+        if !value.is_empty() && !levels.contains(&value) {
+            return Err(format!("unknown {} answer '{}'", name, value).into());
+        }
+        let columns: Vec<f64> = levels.iter().map(|level| if *level == value { 1.0 } else { 0.0 }).collect();
+        self.push(name, &columns);
+        Ok(())
+    }
+
+    fn flags(&mut self, name: &str, values: &[String], levels: &[&str]) -> Result<(), Box<dyn Error>> {
+        if let Some(unknown) = values.iter().find(|value| !levels.contains(&value.as_str())) {
+            return Err(format!("unknown {} answer '{}'", name, unknown).into());
+        }
+        let columns: Vec<f64> = levels.iter()
+            .map(|level| if values.iter().any(|value| value == level) { 1.0 } else { 0.0 })
+            .collect();
+        self.push(name, &columns);
+        Ok(())
+    }
+}
+
+/// The allowed answers of every multiple-choice question, by CSV header.
+///
+/// Vehicle Type may also be left empty (no vehicle); Recycling and Cooking_With take any subset.
+pub fn choices() -> [(&'static str, &'static [&'static str]); 13] {
+    [
+        ("Body Type", &BODY_TYPES),
+        ("Sex", &SEXES),
+        ("Diet", &DIETS),
+        ("How Often Shower", &SHOWER_FREQUENCIES),
+        ("Heating Energy Source", &HEATING_SOURCES),
+        ("Transport", &TRANSPORTS),
+        ("Vehicle Type", &VEHICLE_TYPES),
+        ("Social Activity", &SOCIAL_ACTIVITIES),
+        ("Frequency of Traveling by Air", &AIR_TRAVEL),
+        ("Waste Bag Size", &WASTE_BAG_SIZES),
+        ("Energy efficiency", &ENERGY_EFFICIENCY),
+        ("Recycling", &RECYCLABLES),
+        ("Cooking_With", &COOKING_METHODS),
+    ]
+}
+
+/// Reads every record of a footprint CSV file.
+pub fn load_records(path: &str) -> Result<Vec<Record>, Box<dyn Error>> {
+    let mut rdr = ReaderBuilder::new().has_headers(true).from_path(path)?;
+    let mut records = Vec::new();
+    for result in rdr.deserialize() {
+        records.push(result?);
+    }
+    Ok(records)
+}
+
+//////////////////////////////////////// model ////////////////////////////////////////
+
+#[derive(Serialize, Deserialize)]
+enum Estimator {
+    RandomForest(RandomForestRegressor<f64, f64, DenseMatrix<f64>, Vec<f64>>),
+    Linear { weights: Vec<f64>, intercept: f64 },
+}
+
+/// Predicts a person's monthly emissions (kg CO2) from their footprint answers.
+#[derive(Serialize, Deserialize)]
+pub struct FootprintModel {
+    estimator: Estimator,
+    groups: Vec<FeatureGroup>,
+    background: Vec<Vec<f64>>, // Sample of encoded training rows, used as the explanation baseline
+}
+
+impl FootprintModel {
+    /// Fits a random forest on the given records.
+    pub fn fit_random_forest(records: &[Record], params: RandomForestRegressorParameters) -> Result<Self, Box<dyn Error>> {
+        let (rows, targets, groups) = encode_all(records)?;
+        let forest = RandomForestRegressor::fit(&DenseMatrix::from_2d_vec(&rows), &targets, params)?;
+        Ok(Self { estimator: Estimator::RandomForest(forest), groups, background: background(rows) })
+    }
+
+    /// Fits an ordinary least squares model on the given records.
+    ///
+    /// One-hot columns make the design matrix rank deficient, so the normal equations are solved
+    /// through the SVD, which picks the minimum-norm solution.
+    pub fn fit_linear(records: &[Record]) -> Result<Self, Box<dyn Error>> {
+        let (rows, targets, groups) = encode_all(records)?;
+        let n_features = rows.first().map_or(0, |row| row.len());
+        let x = DMatrix::from_fn(rows.len(), n_features + 1, |i, j| if j == 0 { 1.0 } else { rows[i][j - 1] });
+        let y = DVector::from_column_slice(&targets);
+        let beta = x.svd(true, true).solve(&y, 1e-9)?;
+
+        let estimator = Estimator::Linear { weights: beta.iter().skip(1).cloned().collect(), intercept: beta[0] };
+        Ok(Self { estimator, groups, background: background(rows) })
+    }
+
+    /// Kind of estimator, for display.
+    pub fn name(&self) -> &'static str {
+        match &self.estimator {
+            Estimator::RandomForest(_) => "random forest",
+            Estimator::Linear { .. } => "linear",
+        }
+    }
+
+    /// Predicts monthly emissions for each encoded row.
+    pub fn predict_rows(&self, rows: &[Vec<f64>]) -> Result<Vec<f64>, Box<dyn Error>> {
+        match &self.estimator {
+            Estimator::RandomForest(forest) => Ok(forest.predict(&DenseMatrix::from_2d_vec(&rows.to_vec()))?),
+            Estimator::Linear { weights, intercept } => Ok(rows.iter()
+                .map(|row| intercept + weights.iter().zip(row.iter()).map(|(w, v)| w * v).sum::<f64>())
+                .collect()),
+        }
+    }
+
+    /// Predicts monthly emissions (kg CO2) for a single record.
+    pub fn predict(&self, record: &Record) -> Result<f64, Box<dyn Error>> {
+        Ok(self.predict_rows(&[record.features()?])?[0])
+    }
+
+    /// In-sample root mean squared error and R² on the given records.
+    pub fn evaluate(&self, records: &[Record]) -> Result<(f64, f64), Box<dyn Error>> {
+        let rows = records.iter().map(|record| record.features()).collect::<Result<Vec<_>, _>>()?;
+        let predictions = self.predict_rows(&rows)?;
+        let targets: Vec<f64> = records.iter().map(|record| record.carbon_emission).collect();
+
+        let mean = targets.iter().sum::<f64>() / targets.len() as f64;
+        let variance = targets.iter().map(|target| (target - mean).powi(2)).sum::<f64>() / targets.len() as f64;
+        let mse = mean_squared_error(&predictions, &targets);
+        Ok((mse.sqrt(), 1.0 - mse / variance))
+    }
+
+    /// Explains a single prediction as per-answer contributions relative to the average person.
+    ///
+    /// Linear models get exact contributions. smartcore does not expose the fitted trees, so
+    /// TreeSHAP is not available for the forest and its Shapley values are sampled instead.
+    pub fn explain(&self, record: &Record) -> Result<Explanation, Box<dyn Error>> {
+        let x = record.features()?;
+        match &self.estimator {
+            Estimator::Linear { weights, intercept } => explain::linear(weights, *intercept, &x, &self.background, &self.groups),
+            Estimator::RandomForest(_) => explain::sampling_shapley(
+                |rows| self.predict_rows(rows), &x, &self.background, &self.groups, SHAPLEY_SAMPLES, SHAPLEY_SEED,
+            ),
+        }
+    }
+}
+
+// Encoded training rows, their targets and the question-to-column groups
+type Encoded = (Vec<Vec<f64>>, Vec<f64>, Vec<FeatureGroup>);
+
+fn encode_all(records: &[Record]) -> Result<Encoded, Box<dyn Error>> {
+    let first = records.first().ok_or("cannot fit a footprint model without records")?;
+    let groups = first.encode()?.1;
+    let rows = records.iter().map(|record| record.features()).collect::<Result<Vec<_>, _>>()?;
+    let targets = records.iter().map(|record| record.carbon_emission).collect();
+    Ok((rows, targets, groups))
+}
+
+// Evenly spaced subset of the training rows, so the baseline does not depend on file order
+fn background(rows: Vec<Vec<f64>>) -> Vec<Vec<f64>> {
+    let step = (rows.len() / BACKGROUND_SIZE).max(1);
+    rows.into_iter().step_by(step).take(BACKGROUND_SIZE).collect()
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn records(n: usize) -> Vec<Record> {
+        let mut records = load_records(DATA).unwrap();
+        records.truncate(n);
+        records
+    }
+
+    // Contributions sum to the prediction minus the baseline, and the prediction is the model's
+    fn assert_explains(model: &FootprintModel, record: &Record) -> Explanation {
+        let explanation = model.explain(record).unwrap();
+        let total: f64 = explanation.contributions.iter().map(|contribution| contribution.value).sum();
+        assert!((total - (explanation.prediction - explanation.baseline)).abs() < 1e-6, "{:?}", explanation);
+        assert!((explanation.prediction - model.predict(record).unwrap()).abs() < 1e-6);
+        explanation
+    }
+
+    #[test]
+    fn records_encode_one_group_per_question() {
+        let record = &records(1)[0];
+        let (values, groups) = record.encode().unwrap();
+        assert_eq!(groups.len(), 19);
+        assert_eq!(groups.last().unwrap().columns.end, values.len());
+        let diet = groups.iter().find(|group| group.name == "Diet").unwrap();
+        assert_eq!(diet.columns.len(), DIETS.len());
+        assert_eq!(values[diet.columns.clone()].iter().sum::<f64>(), 1.0);
+
+        let unknown = Record { diet: "carnivore".to_string(), ..record.clone() };
+        assert!(unknown.encode().is_err());
+    }
+
+    #[test]
+    fn linear_fit_explains_a_change_of_answer_in_its_group_alone() {
+        let records = records(2000);
+        let model = FootprintModel::fit_linear(&records).unwrap();
+        assert_eq!(model.name(), "linear");
+        let (rmse, r2) = model.evaluate(&records).unwrap();
+        assert!(rmse > 0.0 && r2 > 0.8, "RMSE {}, R² {}", rmse, r2);
+
+        let omnivore = Record { diet: "omnivore".to_string(), ..records[0].clone() };
+        let vegan = Record { diet: "vegan".to_string(), ..omnivore.clone() };
+        let (before, after) = (assert_explains(&model, &omnivore), assert_explains(&model, &vegan));
+        assert_eq!(before.baseline, after.baseline);
+        for (old, new) in before.contributions.iter().zip(&after.contributions) {
+            if old.feature == "Diet" {
+                assert!((new.value - old.value - (after.prediction - before.prediction)).abs() < 1e-6);
+            } else {
+                assert!((new.value - old.value).abs() < 1e-9, "{} changed", old.feature);
+            }
+        }
+    }
+
+    #[test]
+    fn forest_fit_has_sampled_but_efficient_explanations() {
+        let records = records(500);
+        let params = RandomForestRegressorParameters::default().with_n_trees(10).with_max_depth(6);
+        let model = FootprintModel::fit_random_forest(&records, params).unwrap();
+        assert_eq!(model.name(), "random forest");
+        let (_, r2) = model.evaluate(&records).unwrap();
+        assert!(r2 > 0.3, "R² {}", r2);
+
+        let explanation = assert_explains(&model, &records[0]);
+        assert_eq!(explanation.contributions.len(), 19);
+        // Seeded, so the same record gets the same explanation
+        let again = model.explain(&records[0]).unwrap();
+        assert!(explanation.contributions.iter().zip(&again.contributions).all(|(a, b)| a.value == b.value));
+    }
+
+    #[test]
+    fn fits_need_records() {
+        assert!(FootprintModel::fit_linear(&[]).is_err());
+        assert!(FootprintModel::fit_random_forest(&[], RandomForestRegressorParameters::default()).is_err());
+    }
+}
@@ -0,0 +1,44 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use climate_predict::persist::ClimateModel;
+use std::error::Error;
+use std::io;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const DATA: &str = "./data/emission_temp_data.csv";
+const Z_95: f64 = 1.96; // Standard deviations covering ~95 % of a normal distribution
+
+//////////////////////////////////////// main ////////////////////////////////////////
+
+fn main() -> Result<(), Box<dyn Error>> {
+    // year → emissions → temperature
+    let pipeline = ClimateModel::train(DATA)?.pipeline();
+
+    loop {
+        println!("Enter a year to forecast or type 'exit' to quit:");
+        let mut year_input = String::new();
+        io::stdin().read_line(&mut year_input)?;
+        if year_input.trim().eq("exit") {
+            break;
+        }
+
+        let year: f64 = match year_input.trim().parse() {
+            Ok(year) => year,
+            Err(_) => {
+                println!("Please enter a valid year.");
+                continue;
+            }
+        };
+
+        for output in pipeline.predict(year) {
+            let (low, high) = output.prediction.interval(Z_95);
+            println!("  {} [{}]: {:.2} ± {:.2} (95 %: {:.2} to {:.2})",
+                output.label, output.model, output.prediction.mean, output.prediction.std, low, high);
+        }
+    }
+
+    Ok(())
+}
+
+// cargo run --bin forecast
@@ -0,0 +1,369 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::Dataset;
+use crate::interpolate::{self, Method};
+use crate::timeseries::{Period, TimeSeries};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+//////////////////////////////////////// strategies ////////////////////////////////////////
+
+/// How to fill a missing value.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Strategy {
+    Drop,         // Leave it missing; the whole row is removed
+    ForwardFill,  // Repeat the last known value
+    Linear,       // Straight line between the known values on either side
+    Spline,       // Natural cubic spline through all known values
+    SeasonalMean, // Mean of the known values for the same calendar month (of all values, for annual data)
+}
+
+impl Strategy {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Strategy::Drop => "drop",
+            Strategy::ForwardFill => "forward-fill",
+            Strategy::Linear => "linear",
+            Strategy::Spline => "spline",
+            Strategy::SeasonalMean => "seasonal-mean",
+        }
+    }
+}
+
+impl fmt::Display for Strategy {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Strategy {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "drop" => Ok(Strategy::Drop),
+            "forward-fill" => Ok(Strategy::ForwardFill),
+            "linear" => Ok(Strategy::Linear),
+            "spline" => Ok(Strategy::Spline),
+            "seasonal-mean" => Ok(Strategy::SeasonalMean),
+            _ => Err(format!("unknown imputation strategy '{}' (expected drop, forward-fill, linear, spline or seasonal-mean)", s)),
+        }
+    }
+}
+
+/// Fills missing values of one column.
+///
+/// Interpolation never extrapolates and forward fill has nothing to repeat before the first known
+/// value, so gaps at the start (and, for interpolation, the end) stay missing.
+///
+/// Args:
+///     periods: Time of every value, in increasing order.
+///     values: The column, None where missing.
+///     strategy: How to fill the gaps.
+///
+/// Returns:
+///     The column with as many gaps filled as the strategy allows.
+pub fn fill(periods: &[Period], values: &[Option<f64>], strategy: Strategy) -> Result<Vec<Option<f64>>, Box<dyn Error>> {
+    if periods.len() != values.len() {
+        return Err("imputation needs one period per value".into());
+    }
+    if let Some(pair) = periods.windows(2).find(|pair| pair[0] >= pair[1]) {
+        return Err(format!("imputation needs values in time order, found {} before {}", pair[0], pair[1]).into());
+    }
+    let missing: Vec<usize> = (0..values.len()).filter(|&i| values[i].is_none()).collect();
+    let mut filled = values.to_vec();
+    if missing.is_empty() {
+        return Ok(filled);
+    }
+
+    match strategy {
+        Strategy::Drop => {}
+        Strategy::ForwardFill => {
+            for i in 1..filled.len() {
+                if filled[i].is_none() {
+                    filled[i] = filled[i - 1];
+                }
+            }
+        }
+        Strategy::Linear | Strategy::Spline => {
+            let known: Vec<(f64, f64)> = periods.iter().zip(values)
+                .filter_map(|(period, value)| value.map(|value| (period.decimal_year(), value)))
+                .collect();
+            if known.len() < 2 {
+                return Err("interpolating a column needs at least two known values".into());
+            }
+            let (first, last) = (known[0].0, known[known.len() - 1].0);
+            let inside: Vec<usize> = missing.into_iter()
+                .filter(|&i| (first..=last).contains(&periods[i].decimal_year()))
+                .collect();
+            let at: Vec<f64> = inside.iter().map(|&i| periods[i].decimal_year()).collect();
+            let method = if strategy == Strategy::Spline { Method::Spline } else { Method::Linear };
+            for (i, value) in inside.into_iter().zip(interpolate::interpolate(&known, &at, method)?) {
+                filled[i] = Some(value);
+            }
+        }
+        Strategy::SeasonalMean => {
+            // Bucket 0 holds annual values, 1–12 the calendar months
+            let bucket = |period: &Period| match *period {
+                Period::Year(_) => 0,
+                Period::Month(_, month) => month as usize,
+            };
+            let mut totals = [(0.0, 0usize); 13];
+            for (period, value) in periods.iter().zip(values) {
+                if let Some(value) = value {
+                    let total = &mut totals[bucket(period)];
+                    *total = (total.0 + value, total.1 + 1);
+                }
+            }
+            for i in missing {
+                let (sum, count) = totals[bucket(&periods[i])];
+                if count > 0 {
+                    filled[i] = Some(sum / count as f64);
+                }
+            }
+        }
+    }
+    Ok(filled)
+}
+
+/// Reinstates the periods missing from a monthly or annual series and fills them.
+///
+/// Returns:
+///     The filled series and how many periods were added; gaps the strategy cannot fill stay absent.
+pub fn fill_gaps(series: &TimeSeries, strategy: Strategy) -> Result<(TimeSeries, usize), Box<dyn Error>> {
+    if series.is_empty() {
+        return Ok((series.clone(), 0));
+    }
+    let (periods, values) = with_gaps(series);
+    let filled = fill(&periods, &values, strategy)?;
+
+    let added = values.iter().zip(&filled).filter(|(before, after)| before.is_none() && after.is_some()).count();
+    let points = periods.into_iter().zip(filled).filter_map(|(period, value)| value.map(|value| (period, value))).collect();
+    Ok((TimeSeries::new(&series.name, points)?, added))
+}
+
+/// Every period from the first to the last point of a series, with None where the series has no value.
+pub fn with_gaps(series: &TimeSeries) -> (Vec<Period>, Vec<Option<f64>>) {
+    let (Some(&(first, _)), Some(&(last, _))) = (series.points.first(), series.points.last()) else {
+        return (Vec::new(), Vec::new());
+    };
+    let mut periods = vec![first];
+    let mut period = first;
+    while period < last {
+        period = period.next();
+        periods.push(period);
+    }
+
+    let mut known = series.points.iter().peekable();
+    let values = periods.iter().map(|period| known.next_if(|(p, _)| p == period).map(|&(_, value)| value)).collect();
+    (periods, values)
+}
+
+//////////////////////////////////////// preprocessing ////////////////////////////////////////
+
+/// Imputes the numeric columns of a dataset ordered by a period key column.
+///
+/// Each column is filled with its own strategy, then every row still missing a value in any of
+/// the columns is dropped.
+#[derive(Debug, Clone)]
+pub struct Preprocessor {
+    key_column: String,
+    columns: Vec<(String, Strategy)>,
+}
+
+impl Preprocessor {
+    /// Starts a preprocessing stage keyed by `key_column` (`YYYYMM`, ISO date or year keys).
+    pub fn new(key_column: &str) -> Self {
+        Self { key_column: key_column.to_string(), columns: Vec::new() }
+    }
+
+    /// Adds a numeric column to keep, with the strategy for its missing values.
+    pub fn column(mut self, name: &str, strategy: Strategy) -> Self {
+        self.columns.push((name.to_string(), strategy));
+        self
+    }
+
+    /// Runs the stage on a dataset whose rows are sorted by the key column.
+    ///
+    /// Returns:
+    ///     The complete rows and a report of what was imputed and dropped.
+    pub fn apply(&self, dataset: &Dataset) -> Result<Imputed, Box<dyn Error>> {
+        let periods = dataset.texts(&self.key_column)?.into_iter().enumerate()
+            .map(|(row, key)| Period::parse(key).map_err(|message| format!("line {}: {}", dataset.line(row), message)))
+            .collect::<Result<Vec<_>, _>>()?;
+        let values = self.columns.iter().map(|(name, _)| dataset.optional_floats(name)).collect::<Result<Vec<_>, _>>()?;
+        self.apply_to(periods, values)
+    }
+
+    /// Runs the stage on columns already in memory, such as a series spread out by with_gaps.
+    ///
+    /// Args:
+    ///     periods: Time of every row, in increasing order.
+    ///     values: One column per added column, in the order they were added, None where missing.
+    pub fn apply_to(&self, periods: Vec<Period>, values: Vec<Vec<Option<f64>>>) -> Result<Imputed, Box<dyn Error>> {
+        if values.len() != self.columns.len() {
+            return Err(format!("preprocessing expects {} column(s), got {}", self.columns.len(), values.len()).into());
+        }
+
+        let mut filled = Vec::with_capacity(self.columns.len());
+        let mut reports = Vec::with_capacity(self.columns.len());
+        for ((name, strategy), values) in self.columns.iter().zip(values) {
+            let column = fill(&periods, &values, *strategy).map_err(|error| format!("column {}: {}", name, error))?;
+            reports.push(ColumnReport {
+                column: name.clone(),
+                strategy: *strategy,
+                missing: values.iter().filter(|value| value.is_none()).count(),
+                imputed: values.iter().zip(&column).filter(|(before, after)| before.is_none() && after.is_some()).count(),
+            });
+            filled.push(column);
+        }
+
+        let rows: Vec<usize> = (0..periods.len()).filter(|&row| filled.iter().all(|column| column[row].is_some())).collect();
+        let columns = filled.iter().map(|column| rows.iter().filter_map(|&row| column[row]).collect()).collect();
+        let report = Report { rows: periods.len(), dropped: periods.len() - rows.len(), columns: reports };
+
+        Ok(Imputed {
+            names: self.columns.iter().map(|(name, _)| name.clone()).collect(),
+            periods: rows.iter().map(|&row| periods[row]).collect(),
+            columns,
+            report,
+        })
+    }
+}
+
+/// Complete rows left after preprocessing.
+#[derive(Debug, Clone)]
+pub struct Imputed {
+    pub periods: Vec<Period>,
+    pub report: Report,
+    names: Vec<String>,
+    columns: Vec<Vec<f64>>,
+}
+
+impl Imputed {
+    /// Values of a preprocessed column, one per remaining row.
+    pub fn column(&self, name: &str) -> Result<&[f64], Box<dyn Error>> {
+        let index = self.names.iter().position(|n| n == name).ok_or_else(|| format!("no preprocessed column '{}'", name))?;
+        Ok(&self.columns[index])
+    }
+
+    /// A preprocessed column as a time series.
+    pub fn series(&self, name: &str) -> Result<TimeSeries, Box<dyn Error>> {
+        TimeSeries::new(name, self.periods.iter().copied().zip(self.column(name)?.iter().copied()).collect())
+    }
+}
+
+//////////////////////////////////////// reports ////////////////////////////////////////
+
+/// Missing and imputed value counts for one column.
+#[derive(Debug, Clone)]
+pub struct ColumnReport {
+    pub column: String,
+    pub strategy: Strategy,
+    pub missing: usize,
+    pub imputed: usize,
+}
+
+/// What a preprocessing stage changed.
+#[derive(Debug, Clone)]
+pub struct Report {
+    pub rows: usize,    // Rows before preprocessing
+    pub dropped: usize, // Rows removed because a value could not be imputed
+    pub columns: Vec<ColumnReport>,
+}
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{} rows, {} dropped", self.rows, self.dropped)?;
+        for column in &self.columns {
+            write!(f, "\n  {}: {} missing, {} imputed ({})", column.column, column.missing, column.imputed, column.strategy)?;
+        }
+        Ok(())
+    }
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    fn years(first: i32, count: i32) -> Vec<Period> {
+        (first..first + count).map(Period::Year).collect()
+    }
+
+    // Gaps at the start, in the middle and at the end of a straight line y = 2·i
+    fn gappy() -> Vec<Option<f64>> {
+        vec![None, Some(2.0), None, Some(6.0), Some(8.0), None, Some(12.0), None]
+    }
+
+    #[test]
+    fn drop_leaves_every_gap() {
+        assert_eq!(fill(&years(2000, 8), &gappy(), Strategy::Drop).unwrap(), gappy());
+    }
+
+    #[test]
+    fn forward_fill_repeats_the_last_value_but_not_before_the_first() {
+        let filled = fill(&years(2000, 8), &gappy(), Strategy::ForwardFill).unwrap();
+        assert_eq!(filled, vec![None, Some(2.0), Some(2.0), Some(6.0), Some(8.0), Some(8.0), Some(12.0), Some(12.0)]);
+    }
+
+    #[test]
+    fn interpolation_fills_inner_gaps_only() {
+        for strategy in [Strategy::Linear, Strategy::Spline] {
+            let filled = fill(&years(2000, 8), &gappy(), strategy).unwrap();
+            assert_eq!((filled[0], filled[7]), (None, None), "{} extrapolated", strategy);
+            for i in [2, 5] {
+                assert!((filled[i].unwrap() - 2.0 * i as f64).abs() < 1e-9, "{} fills {} with {:?}", strategy, i, filled[i]);
+            }
+        }
+        assert!(fill(&years(2000, 3), &[None, Some(1.0), None], Strategy::Linear).is_err());
+    }
+
+    #[test]
+    fn seasonal_mean_uses_the_same_calendar_month() {
+        let periods: Vec<Period> = (0..36).map(|i| Period::Month(2000 + i / 12, i as u32 % 12 + 1)).collect();
+        let mut values: Vec<Option<f64>> = (0..36).map(|i| Some((i % 12) as f64 + (i / 12) as f64)).collect();
+        values[0] = None; // Leading gap: January 2000
+        values[35] = None; // Trailing gap: December 2002
+
+        let filled = fill(&periods, &values, Strategy::SeasonalMean).unwrap();
+        assert_eq!(filled[0], Some((1.0 + 2.0) / 2.0));
+        assert_eq!(filled[35], Some((11.0 + 12.0) / 2.0));
+        assert_eq!(fill(&years(2000, 4), &[Some(1.0), None, Some(3.0), None], Strategy::SeasonalMean).unwrap()[3], Some(2.0));
+    }
+
+    #[test]
+    fn fill_rejects_unordered_periods() {
+        assert!(fill(&[Period::Year(2001), Period::Year(2000)], &[Some(1.0), None], Strategy::Linear).is_err());
+    }
+
+    #[test]
+    fn fill_gaps_reinstates_missing_months() {
+        let points = [(2000, 11, 1.0), (2001, 2, 4.0)].iter().map(|&(year, month, value)| (Period::Month(year, month), value)).collect();
+        let series = TimeSeries::new("test", points).unwrap();
+
+        let (filled, added) = fill_gaps(&series, Strategy::Linear).unwrap();
+        assert_eq!(added, 2);
+        assert_eq!(filled.points.len(), 4);
+        assert_eq!(filled.points[1].0, Period::Month(2000, 12));
+        assert_eq!(fill_gaps(&series, Strategy::Drop).unwrap().1, 0);
+    }
+
+    #[test]
+    fn preprocessor_reports_imputed_and_dropped_rows() {
+        let imputed = Preprocessor::new("Year")
+            .column("a", Strategy::Linear)
+            .column("b", Strategy::ForwardFill)
+            .apply_to(years(2000, 8), vec![gappy(), gappy()])
+            .unwrap();
+
+        // Row 0 has nothing to fill from in either column; row 7 only has a forward fill for b
+        assert_eq!((imputed.report.rows, imputed.report.dropped), (8, 2));
+        assert_eq!((imputed.report.columns[0].missing, imputed.report.columns[0].imputed), (4, 2));
+        assert_eq!((imputed.report.columns[1].missing, imputed.report.columns[1].imputed), (4, 3));
+        assert_eq!(imputed.column("a").unwrap(), &[2.0, 4.0, 6.0, 8.0, 10.0, 12.0]);
+        assert_eq!(imputed.periods.first(), Some(&Period::Year(2001)));
+    }
+}
@@ -0,0 +1,493 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{ColumnType, Dataset, Value};
+use crate::timeseries::{Period, TimeSeries, MONTHS};
+use serde::Serialize;
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const TIME_COLUMNS: [&str; 6] = ["year", "yyyymm", "dt", "date", "month", "time"]; // Header names taken as the time index
+const OUTLIER_WINDOW: usize = 5; // Values in the moving median each value is compared with
+const OUTLIER_SCORE: f64 = 3.5; // Robust z-score above which a value is flagged
+const NOISE_FLOOR: f64 = 0.05; // Minimum residual scale, as a fraction of the column's standard deviation
+const MONTH_NAMES: [&str; MONTHS] = ["Jan", "Feb", "Mar", "Apr", "May", "Jun", "Jul", "Aug", "Sep", "Oct", "Nov", "Dec"];
+
+//////////////////////////////////////// report ////////////////////////////////////////
+
+/// Data quality summary of one dataset, printable as text or serializable as JSON.
+#[derive(Debug, Clone, Serialize)]
+pub struct Report {
+    pub source: String,
+    pub rows: usize,
+    pub columns: Vec<ColumnSummary>,
+    pub time_index: Option<TimeIndex>,
+    pub correlations: Correlations,
+    pub seasonality: Vec<Seasonality>, // Numeric columns of a monthly dataset without gaps
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct ColumnSummary {
+    pub name: String,
+    pub kind: &'static str,
+    pub count: usize,   // Non-missing values
+    pub missing: usize,
+    pub distinct: usize,
+    pub min: Option<f64>,
+    pub max: Option<f64>,
+    pub mean: Option<f64>,
+    pub std: Option<f64>,
+    pub outliers: Vec<Outlier>,
+}
+
+/// A value far from its neighbours, see find_outliers.
+#[derive(Debug, Clone, Serialize)]
+pub struct Outlier {
+    pub line: u64,
+    pub value: f64,
+    pub score: f64,
+}
+
+/// Problems with the column that orders the rows in time.
+#[derive(Debug, Clone, Serialize)]
+pub struct TimeIndex {
+    pub column: String,
+    pub first: Option<String>,
+    pub last: Option<String>,
+    pub unparsed: Vec<u64>,         // Lines whose key is not a YYYY, YYYYMM or ISO date
+    pub duplicates: Vec<Duplicate>,
+    pub out_of_order: Vec<u64>,     // Lines whose key is earlier than the line before
+    pub gaps: usize,                // Periods between first and last with no row
+}
+
+#[derive(Debug, Clone, Serialize)]
+pub struct Duplicate {
+    pub period: String,
+    pub lines: Vec<u64>,
+}
+
+/// Pearson correlations between the numeric columns (time index excluded), over rows where both are present.
+#[derive(Debug, Clone, Serialize)]
+pub struct Correlations {
+    pub columns: Vec<String>,
+    pub matrix: Vec<Vec<Option<f64>>>,
+}
+
+/// Seasonal cycle of a monthly column, from TimeSeries::decompose.
+#[derive(Debug, Clone, Serialize)]
+pub struct Seasonality {
+    pub column: String,
+    pub cycle: [f64; MONTHS], // Average effect of each calendar month, January first
+    pub amplitude: f64,       // Largest minus smallest monthly effect
+    pub residual_std: f64,    // Spread left after removing the trend and the cycle
+}
+
+//////////////////////////////////////// inspection ////////////////////////////////////////
+
+/// Builds the data quality report for a loaded dataset.
+///
+/// Args:
+///     dataset: Any dataset, e.g. from Dataset::load_inferred.
+///     source: Name to show in the report.
+///     time_column: Column ordering the rows; None picks the first column named like a year or date.
+///
+/// Returns:
+///     The report, or an error if the named time column does not exist.
+pub fn inspect(dataset: &Dataset, source: &str, time_column: Option<&str>) -> Result<Report, Box<dyn Error>> {
+    let time_column = match time_column {
+        Some(name) => Some(dataset.schema.column(name).ok_or_else(|| format!("{} has no column '{}'", source, name))?.name.clone()),
+        None => dataset.schema.columns.iter()
+            .find(|column| {
+                let name = column.name.to_lowercase();
+                TIME_COLUMNS.contains(&name.as_str()) || name.contains("year") || name.contains("date")
+            })
+            .map(|column| column.name.clone()),
+    };
+
+    // Monthly rows are only compared with the same calendar month, so the seasonal cycle is not flagged
+    let periods = match &time_column {
+        Some(name) => periods(dataset, name)?,
+        None => vec![None; dataset.len()],
+    };
+    let seasons: Vec<u32> = periods.iter()
+        .map(|period| match period {
+            Some(Period::Month(_, month)) => *month,
+            _ => 0,
+        })
+        .collect();
+
+    let mut columns = Vec::new();
+    let mut numeric = Vec::new();
+    for column in &dataset.schema.columns {
+        let values = dataset.values(&column.name)?;
+        let is_time = time_column.as_deref() == Some(column.name.as_str());
+        columns.push(summarize(dataset, &column.name, column.kind, values, (!is_time).then_some(seasons.as_slice())));
+        if column.kind != ColumnType::Text && !is_time {
+            numeric.push((column.name.clone(), values.iter().map(Value::as_f64).collect::<Vec<_>>()));
+        }
+    }
+
+    let time_index = time_column.as_ref().map(|name| time_index(dataset, name, &periods));
+    let seasonality = numeric.iter().filter_map(|(name, values)| seasonality(name, &periods, values)).collect();
+
+    let matrix = numeric.iter()
+        .map(|(_, a)| numeric.iter().map(|(_, b)| correlation(a, b)).collect())
+        .collect();
+    let correlations = Correlations { columns: numeric.into_iter().map(|(name, _)| name).collect(), matrix };
+
+    Ok(Report { source: source.to_string(), rows: dataset.len(), columns, time_index, correlations, seasonality })
+}
+
+// `seasons` groups the rows for outlier detection; None skips it (for the time index itself)
+fn summarize(dataset: &Dataset, name: &str, kind: ColumnType, values: &[Value], seasons: Option<&[u32]>) -> ColumnSummary {
+    let present: Vec<(usize, &Value)> = values.iter().enumerate().filter(|(_, value)| **value != Value::Missing).collect();
+    let distinct: BTreeSet<String> = present.iter().map(|(_, value)| format!("{:?}", value)).collect();
+    let numbers: Vec<(usize, f64)> = present.iter().filter_map(|&(row, value)| value.as_f64().map(|v| (row, v))).collect();
+
+    let mut summary = ColumnSummary {
+        name: name.to_string(),
+        kind: match kind {
+            ColumnType::Float => "float",
+            ColumnType::Integer => "integer",
+            ColumnType::Text => "text",
+        },
+        count: present.len(),
+        missing: values.len() - present.len(),
+        distinct: distinct.len(),
+        min: None,
+        max: None,
+        mean: None,
+        std: None,
+        outliers: Vec::new(),
+    };
+    if numbers.is_empty() {
+        return summary;
+    }
+
+    let n = numbers.len() as f64;
+    let mean = numbers.iter().map(|&(_, v)| v).sum::<f64>() / n;
+    let std = (numbers.iter().map(|&(_, v)| (v - mean).powi(2)).sum::<f64>() / (n - 1.0).max(1.0)).sqrt();
+    summary.min = numbers.iter().map(|&(_, v)| v).reduce(f64::min);
+    summary.max = numbers.iter().map(|&(_, v)| v).reduce(f64::max);
+    summary.mean = Some(mean);
+    summary.std = Some(std);
+    if let Some(seasons) = seasons {
+        let seasons: Vec<u32> = numbers.iter().map(|&(row, _)| seasons[row]).collect();
+        summary.outliers = find_outliers(&numbers, &seasons, std).into_iter()
+            .map(|(row, value, score)| Outlier { line: dataset.line(row), value, score })
+            .collect();
+    }
+    summary
+}
+
+/// Flags values that stand out from their neighbours.
+///
+/// Each value is compared with the median of the OUTLIER_WINDOW values centred on it (in row order,
+/// narrower near the ends), which follows trends such as rising emissions instead of flagging their
+/// recent end. Monthly values (season 1–12) are compared with a median over a full year instead, and
+/// the median deviation of their calendar month is removed, so summer peaks are not flagged.
+/// Residuals are scored against their median absolute deviation, floored at NOISE_FLOOR of the
+/// standard deviation so that smooth or rounded columns do not flag rounding noise.
+fn find_outliers(numbers: &[(usize, f64)], seasons: &[u32], std: f64) -> Vec<(usize, f64, f64)> {
+    let monthly = seasons.iter().any(|&season| season > 0);
+    let window = if monthly { MONTHS + 1 } else { OUTLIER_WINDOW };
+    if numbers.len() < window {
+        return Vec::new();
+    }
+    let half = window / 2;
+    let mut residuals: Vec<f64> = (0..numbers.len())
+        .map(|i| {
+            // A year-long window is shifted rather than narrowed at the ends, so it always spans every season
+            let range = if monthly {
+                let start = i.saturating_sub(half).min(numbers.len() - window);
+                start..start + window
+            } else {
+                let reach = half.min(i).min(numbers.len() - 1 - i);
+                i - reach..i + reach + 1
+            };
+            numbers[i].1 - median(numbers[range].iter().map(|&(_, v)| v).collect())
+        })
+        .collect();
+    if monthly {
+        let mut by_season: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+        for (&season, &residual) in seasons.iter().zip(&residuals) {
+            by_season.entry(season).or_default().push(residual);
+        }
+        let cycle: BTreeMap<u32, f64> = by_season.into_iter().map(|(season, values)| (season, median(values))).collect();
+        for (residual, season) in residuals.iter_mut().zip(seasons) {
+            *residual -= cycle[season];
+        }
+    }
+    let center = median(residuals.clone());
+    let spread = 1.4826 * median(residuals.iter().map(|r| (r - center).abs()).collect());
+    let scale = spread.max(NOISE_FLOOR * std);
+    if scale == 0.0 {
+        return Vec::new();
+    }
+
+    numbers.iter().zip(residuals)
+        .map(|(&(row, value), residual)| (row, value, (residual - center).abs() / scale))
+        .filter(|&(_, _, score)| score > OUTLIER_SCORE)
+        .collect()
+}
+
+// The parsed key of every row, None where it is missing or not a period
+fn periods(dataset: &Dataset, column: &str) -> Result<Vec<Option<Period>>, Box<dyn Error>> {
+    Ok(dataset.values(column)?.iter()
+        .map(|value| match value {
+            Value::Integer(key) => Period::parse(&key.to_string()).ok(),
+            Value::Text(key) => Period::parse(key).ok(),
+            _ => None,
+        })
+        .collect())
+}
+
+fn time_index(dataset: &Dataset, column: &str, periods: &[Option<Period>]) -> TimeIndex {
+    let mut index = TimeIndex {
+        column: column.to_string(),
+        first: None,
+        last: None,
+        unparsed: Vec::new(),
+        duplicates: Vec::new(),
+        out_of_order: Vec::new(),
+        gaps: 0,
+    };
+
+    let mut seen: BTreeMap<Period, Vec<u64>> = BTreeMap::new();
+    let mut previous: Option<Period> = None;
+    for (row, period) in periods.iter().enumerate() {
+        let line = dataset.line(row);
+        let Some(period) = *period else {
+            index.unparsed.push(line);
+            continue;
+        };
+        if previous.is_some_and(|previous| period < previous) {
+            index.out_of_order.push(line);
+        }
+        previous = Some(period);
+        seen.entry(period).or_default().push(line);
+    }
+
+    index.duplicates = seen.iter()
+        .filter(|(_, lines)| lines.len() > 1)
+        .map(|(period, lines)| Duplicate { period: period.to_string(), lines: lines.clone() })
+        .collect();
+    if let (Some(&first), Some(&last)) = (seen.keys().next(), seen.keys().next_back()) {
+        index.first = Some(first.to_string());
+        index.last = Some(last.to_string());
+        if first.resolution() == last.resolution() {
+            let mut period = first;
+            while period < last {
+                period = period.next();
+                if !seen.contains_key(&period) {
+                    index.gaps += 1;
+                }
+            }
+        }
+    }
+    index
+}
+
+// None unless every row is a month, each month appears once, and no month between first and last is missing
+fn seasonality(name: &str, periods: &[Option<Period>], values: &[Option<f64>]) -> Option<Seasonality> {
+    let points = periods.iter().zip(values)
+        .map(|(period, value)| match period {
+            Some(period @ Period::Month(..)) => Some((*period, (*value)?)),
+            _ => None,
+        })
+        .collect::<Option<Vec<_>>>()?;
+    let decomposition = TimeSeries::new(name, points).ok()?.decompose().ok()?;
+
+    let cycle = decomposition.cycle();
+    let amplitude = cycle.iter().copied().fold(f64::MIN, f64::max) - cycle.iter().copied().fold(f64::MAX, f64::min);
+    let residuals: Vec<f64> = decomposition.residual.iter().flatten().copied().collect();
+    let mean = residuals.iter().sum::<f64>() / residuals.len() as f64;
+    let residual_std = (residuals.iter().map(|r| (r - mean).powi(2)).sum::<f64>() / (residuals.len() as f64 - 1.0).max(1.0)).sqrt();
+    Some(Seasonality { column: name.to_string(), cycle, amplitude, residual_std })
+}
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+fn median(mut values: Vec<f64>) -> f64 {
+    values.sort_by(f64::total_cmp);
+    let mid = values.len() / 2;
+    if values.len().is_multiple_of(2) { (values[mid - 1] + values[mid]) / 2.0 } else { values[mid] }
+}
+
+// Pearson correlation over rows where both columns have a value; None without variance or overlap
+fn correlation(a: &[Option<f64>], b: &[Option<f64>]) -> Option<f64> {
+    let pairs: Vec<(f64, f64)> = a.iter().zip(b).filter_map(|(x, y)| Some(((*x)?, (*y)?))).collect();
+    if pairs.len() < 2 {
+        return None;
+    }
+    let n = pairs.len() as f64;
+    let (mean_a, mean_b) = (pairs.iter().map(|p| p.0).sum::<f64>() / n, pairs.iter().map(|p| p.1).sum::<f64>() / n);
+    let covariance: f64 = pairs.iter().map(|(x, y)| (x - mean_a) * (y - mean_b)).sum();
+    let (var_a, var_b): (f64, f64) = (pairs.iter().map(|p| (p.0 - mean_a).powi(2)).sum(), pairs.iter().map(|p| (p.1 - mean_b).powi(2)).sum());
+    if var_a == 0.0 || var_b == 0.0 {
+        return None;
+    }
+    Some(covariance / (var_a * var_b).sqrt())
+}
+
+fn show(value: Option<f64>) -> String {
+    value.map_or("-".to_string(), |value| format!("{:.4}", value))
+}
+
+//////////////////////////////////////// text output ////////////////////////////////////////
+
+impl fmt::Display for Report {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        writeln!(f, "{}: {} rows, {} columns", self.source, self.rows, self.columns.len())?;
+
+        writeln!(f, "\nColumns:")?;
+        for column in &self.columns {
+            write!(f, "  {} ({}): {} values, {} missing", column.name, column.kind, column.count, column.missing)?;
+            if column.kind == "text" {
+                writeln!(f, ", {} distinct", column.distinct)?;
+                continue;
+            }
+            writeln!(f, ", min {}, max {}, mean {}, std {}", show(column.min), show(column.max), show(column.mean), show(column.std))?;
+            for outlier in &column.outliers {
+                writeln!(f, "    outlier on line {}: {:.3} (score {:.1})", outlier.line, outlier.value, outlier.score)?;
+            }
+        }
+
+        match &self.time_index {
+            None => writeln!(f, "\nNo time index column found")?,
+            Some(index) => {
+                writeln!(f, "\nTime index {}: {} to {}", index.column,
+                    index.first.as_deref().unwrap_or("-"), index.last.as_deref().unwrap_or("-"))?;
+                writeln!(f, "  {} unparsed keys, {} duplicated periods, {} rows out of order, {} gaps",
+                    index.unparsed.len(), index.duplicates.len(), index.out_of_order.len(), index.gaps)?;
+                for duplicate in &index.duplicates {
+                    writeln!(f, "    {} appears on lines {:?}", duplicate.period, duplicate.lines)?;
+                }
+                if !index.out_of_order.is_empty() {
+                    writeln!(f, "    out of order on lines {:?}", index.out_of_order)?;
+                }
+                if !index.unparsed.is_empty() {
+                    writeln!(f, "    unparsed on lines {:?}", index.unparsed)?;
+                }
+            }
+        }
+
+        for seasonality in &self.seasonality {
+            writeln!(f, "\nSeasonal cycle of {}: amplitude {:.4}, residual std {:.4}", seasonality.column, seasonality.amplitude, seasonality.residual_std)?;
+            let cells: Vec<String> = MONTH_NAMES.iter().zip(seasonality.cycle).map(|(month, effect)| format!("{} {:+.3}", month, effect)).collect();
+            writeln!(f, "  {}", cells.join("  "))?;
+        }
+
+        if self.correlations.columns.len() > 1 {
+            let width = self.correlations.columns.iter().map(|name| name.chars().count()).max().unwrap_or(0);
+            writeln!(f, "\nCorrelations:")?;
+            for (name, row) in self.correlations.columns.iter().zip(&self.correlations.matrix) {
+                let cells: Vec<String> = row.iter().map(|r| r.map_or("     -".to_string(), |r| format!("{:+.3}", r))).collect();
+                writeln!(f, "  {:width$}  {}", name, cells.join("  "), width = width)?;
+            }
+        }
+        Ok(())
+    }
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::Schema;
+
+    fn load(data: &str) -> Dataset {
+        let schema = Schema::infer(data.as_bytes()).unwrap();
+        Dataset::from_reader(data.as_bytes(), schema, "fixture").unwrap()
+    }
+
+    // A straight trend has no residuals, so the scale is NOISE_FLOOR · std and a spike of h scores (h - 1) / scale
+    fn spiked_trend(height: f64) -> Vec<(usize, f64)> {
+        (0..20).map(|i| (i, i as f64 + if i == 10 { height } else { 0.0 })).collect()
+    }
+
+    #[test]
+    fn outliers_are_flagged_above_the_threshold_score() {
+        let std = 10.0; // scale 0.5, so the score is 2(h - 1) and the threshold lies at h = 2.75
+        assert!(find_outliers(&spiked_trend(2.7), &[0; 20], std).is_empty());
+        let flagged = find_outliers(&spiked_trend(2.8), &[0; 20], std);
+        assert_eq!(flagged.len(), 1);
+        let (row, value, score) = flagged[0];
+        assert_eq!((row, value), (10, 12.8));
+        assert!((score - 3.6).abs() < 1e-9, "{}", score);
+        assert!(score > OUTLIER_SCORE);
+
+        // A seasonal cycle is not an outlier, but a spike in one month is
+        let mut numbers: Vec<(usize, f64)> = (0..36).map(|i| (i, 5.0 * (i as f64 * std::f64::consts::PI / 6.0).sin())).collect();
+        let seasons: Vec<u32> = (0..36).map(|i| i as u32 % 12 + 1).collect();
+        assert!(find_outliers(&numbers, &seasons, 3.6).is_empty());
+        numbers[20].1 += 4.0;
+        assert_eq!(find_outliers(&numbers, &seasons, 3.6).iter().map(|outlier| outlier.0).collect::<Vec<_>>(), vec![20]);
+    }
+
+    #[test]
+    fn outliers_report_physical_lines_in_crlf_files() {
+        let data: String = std::iter::once("Year,Anomaly\n".to_string())
+            .chain(spiked_trend(40.0).into_iter().map(|(i, value)| format!("{},{}\n", 2000 + i, value)))
+            .collect();
+        for data in [data.clone(), data.replace('\n', "\r\n")] {
+            let report = inspect(&load(&data), "fixture", None).unwrap();
+            let outliers = &report.columns[1].outliers;
+            assert_eq!(outliers.iter().map(|outlier| (outlier.line, outlier.value)).collect::<Vec<_>>(), vec![(12, 50.0)]);
+            assert!(report.to_string().contains("outlier on line 12: 50.000 (score"), "{}", report);
+        }
+    }
+
+    #[test]
+    fn duplicate_and_unordered_years_are_listed_by_line() {
+        let dataset = load("Year,Emissions\n2000,1.0\n2001,2.0\n2001,2.1\n1999,0.5\n2003,3.0\nn/a,3.1\n");
+        let index = inspect(&dataset, "fixture", None).unwrap().time_index.unwrap();
+        assert_eq!(index.column, "Year");
+        assert_eq!((index.first.as_deref(), index.last.as_deref()), (Some("1999"), Some("2003")));
+        assert_eq!(index.duplicates.len(), 1);
+        assert_eq!((index.duplicates[0].period.as_str(), index.duplicates[0].lines.clone()), ("2001", vec![3, 4]));
+        assert_eq!(index.out_of_order, vec![5]);
+        assert_eq!(index.unparsed, vec![7]);
+        assert_eq!(index.gaps, 1); // 2002
+    }
+
+    #[test]
+    fn monthly_columns_report_their_seasonal_cycle() {
+        let cycle = [3.0, 2.0, 1.0, 0.0, -1.0, -2.0, -3.0, -2.0, -1.0, 0.0, 1.0, 2.0];
+        let mut data = "YYYYMM,Value\n".to_string();
+        for i in 0..36 {
+            data += &format!("{}{:02},{}\n", 2000 + i / 12, i % 12 + 1, 50.0 + 0.2 * i as f64 + cycle[i % 12]);
+        }
+        let report = inspect(&load(&data), "fixture", None).unwrap();
+        assert_eq!(report.seasonality.len(), 1);
+        let seasonality = &report.seasonality[0];
+        assert_eq!(seasonality.column, "Value");
+        assert!(seasonality.cycle.iter().zip(cycle).all(|(effect, expected)| (effect - expected).abs() < 1e-9));
+        assert!((seasonality.amplitude - 6.0).abs() < 1e-9);
+        assert!(seasonality.residual_std < 1e-9);
+
+        // A missing month leaves a gap, and annual rows have no cycle
+        let gap: String = data.lines().filter(|line| !line.starts_with("200106")).map(|line| format!("{}\n", line)).collect();
+        assert!(inspect(&load(&gap), "fixture", None).unwrap().seasonality.is_empty());
+        assert!(inspect(&load("Year,Value\n2000,1\n2001,2\n"), "fixture", None).unwrap().seasonality.is_empty());
+    }
+
+    #[test]
+    fn correlations_skip_the_time_index_and_missing_values() {
+        let dataset = load("Year,a,b,c,flat\n2000,1,3,4,7\n2001,2,5,,7\n2002,3,7,1,7\n2003,4,,0,7\n2004,5,11,2,7\n");
+        let correlations = inspect(&dataset, "fixture", None).unwrap().correlations;
+        assert_eq!(correlations.columns, vec!["a", "b", "c", "flat"]);
+        let r = |i: usize, j: usize| correlations.matrix[i][j];
+        assert!((r(0, 0).unwrap() - 1.0).abs() < 1e-12);
+        // b = 2a + 1 wherever both are present
+        assert!((r(0, 1).unwrap() - 1.0).abs() < 1e-12);
+        // Over the rows with a and c: (1, 4), (3, 1), (4, 0), (5, 2)
+        assert!((r(0, 2).unwrap() - -5.75 / 8.75).abs() < 1e-12);
+        assert_eq!(r(0, 2), r(2, 0));
+        // A constant column has no correlation with anything, itself included
+        assert!((0..4).all(|i| r(i, 3).is_none() && r(3, i).is_none()));
+    }
+}
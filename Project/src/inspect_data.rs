@@ -0,0 +1,71 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use climate_predict::dataset::Dataset;
+use climate_predict::inspect;
+use std::env;
+use std::error::Error;
+use std::process;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const DATA: &str = "./data/emission_temp_data.csv";
+const USAGE: &str = "usage: inspect-data [--json] [--time COLUMN] [--header-start PREFIX] [PATH...]";
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+/// Command-line options.
+struct Options {
+    json: bool,
+    time_column: Option<String>,  // Overrides the guessed time index column
+    header_start: Option<String>, // Skip free-form lines before the header, as in the GISTEMP export
+    paths: Vec<String>,
+}
+
+fn parse_args() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options { json: false, time_column: None, header_start: None, paths: Vec::new() };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--json" => options.json = true,
+            "--time" => options.time_column = Some(args.next().ok_or(USAGE)?),
+            "--header-start" => options.header_start = Some(args.next().ok_or(USAGE)?),
+            flag if flag.starts_with("--") => return Err(format!("unexpected option '{}'\n{}", flag, USAGE).into()),
+            path => options.paths.push(path.to_string()),
+        }
+    }
+    if options.paths.is_empty() {
+        options.paths.push(DATA.to_string());
+    }
+    Ok(options)
+}
+
+//////////////////////////////////////// main ////////////////////////////////////////
+
+fn run() -> Result<(), Box<dyn Error>> {
+    let options = parse_args()?;
+
+    let mut reports = Vec::new();
+    for path in &options.paths {
+        let dataset = Dataset::load_inferred(path, options.header_start.as_deref())?;
+        reports.push(inspect::inspect(&dataset, path, options.time_column.as_deref())?);
+    }
+
+    if options.json {
+        println!("{}", serde_json::to_string_pretty(&reports)?);
+    } else {
+        for report in &reports {
+            println!("{}", report);
+        }
+    }
+    Ok(())
+}
+
+// Errors are printed as messages, not as the Debug form main would give them
+fn main() {
+    if let Err(error) = run() {
+        eprintln!("{}", error);
+        process::exit(1);
+    }
+}
+
+// cargo run --bin inspect-data
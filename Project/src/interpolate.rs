@@ -0,0 +1,172 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+//////////////////////////////////////// methods ////////////////////////////////////////
+
+/// How to fill in values between known points.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Method {
+    Linear, // Straight line between neighbouring points
+    Step,   // Hold the previous known value
+    Spline, // Natural cubic spline through every point
+}
+
+impl Method {
+    pub fn name(&self) -> &'static str {
+        match self {
+            Method::Linear => "linear",
+            Method::Step => "step",
+            Method::Spline => "spline",
+        }
+    }
+}
+
+impl fmt::Display for Method {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for Method {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Method::Linear),
+            "step" => Ok(Method::Step),
+            "spline" => Ok(Method::Spline),
+            _ => Err(format!("unknown interpolation method '{}' (expected linear, step or spline)", s)),
+        }
+    }
+}
+
+//////////////////////////////////////// interpolation ////////////////////////////////////////
+
+/// Evaluates the curve through `known` points at each position in `at`.
+///
+/// Args:
+///     known: (x, y) points with strictly increasing x.
+///     at: Positions to evaluate; each must lie within the range of the known points.
+///     method: Interpolation method.
+///
+/// Returns:
+///     One interpolated value per position, or an error if a position would need extrapolation.
+pub fn interpolate(known: &[(f64, f64)], at: &[f64], method: Method) -> Result<Vec<f64>, Box<dyn Error>> {
+    if known.len() < 2 {
+        return Err("interpolation needs at least two known points".into());
+    }
+    if known.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+        return Err("interpolation needs known points with strictly increasing x".into());
+    }
+    let (first, last) = (known[0].0, known[known.len() - 1].0);
+    if let Some(x) = at.iter().find(|&&x| x < first || x > last) {
+        return Err(format!("cannot interpolate at {} outside the known range {}..={}", x, first, last).into());
+    }
+
+    let second_derivatives = match method {
+        Method::Spline => natural_spline(known),
+        _ => Vec::new(),
+    };
+
+    Ok(at.iter().map(|&x| {
+        // Index of the segment [known[i], known[i + 1]] containing x
+        let i = known.partition_point(|&(kx, _)| kx <= x).clamp(1, known.len() - 1) - 1;
+        let ((x0, y0), (x1, y1)) = (known[i], known[i + 1]);
+        let t = (x - x0) / (x1 - x0);
+        match method {
+            Method::Linear => y0 + (y1 - y0) * t,
+            Method::Step => if x == x1 { y1 } else { y0 },
+            Method::Spline => {
+                let h = x1 - x0;
+                let (m0, m1) = (second_derivatives[i], second_derivatives[i + 1]);
+                (1.0 - t) * y0 + t * y1 + h * h / 6.0 * (((1.0 - t).powi(3) - (1.0 - t)) * m0 + (t.powi(3) - t) * m1)
+            }
+        }
+    }).collect())
+}
+
+/// Second derivatives of the natural cubic spline (zero at both ends), via the Thomas algorithm.
+fn natural_spline(known: &[(f64, f64)]) -> Vec<f64> {
+    let n = known.len();
+    let mut m = vec![0.0; n];
+    if n < 3 {
+        return m;
+    }
+
+    let h: Vec<f64> = known.windows(2).map(|pair| pair[1].0 - pair[0].0).collect();
+    let slope = |i: usize| (known[i + 1].1 - known[i].1) / h[i];
+
+    // Tridiagonal system for the interior points: h[i-1]·m[i-1] + 2(h[i-1]+h[i])·m[i] + h[i]·m[i+1] = 6(slope[i] - slope[i-1])
+    let mut diagonal = vec![0.0; n];
+    let mut rhs = vec![0.0; n];
+    for i in 1..n - 1 {
+        diagonal[i] = 2.0 * (h[i - 1] + h[i]);
+        rhs[i] = 6.0 * (slope(i) - slope(i - 1));
+    }
+    for i in 2..n - 1 {
+        let factor = h[i - 1] / diagonal[i - 1];
+        diagonal[i] -= factor * h[i - 1];
+        rhs[i] -= factor * rhs[i - 1];
+    }
+    for i in (1..n - 1).rev() {
+        m[i] = (rhs[i] - h[i] * m[i + 1]) / diagonal[i];
+    }
+    m
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const KNOWN: [(f64, f64); 4] = [(1990.0, 22.0), (2000.0, 25.0), (2010.0, 33.0), (2012.0, 35.0)];
+
+    #[test]
+    fn every_method_passes_through_the_known_points() {
+        let years: Vec<f64> = KNOWN.iter().map(|&(year, _)| year).collect();
+        for method in [Method::Linear, Method::Step, Method::Spline] {
+            let values = interpolate(&KNOWN, &years, method).unwrap();
+            for (value, (_, expected)) in values.iter().zip(KNOWN) {
+                assert!((value - expected).abs() < 1e-12, "{} gives {} instead of {}", method, value, expected);
+            }
+        }
+    }
+
+    #[test]
+    fn methods_differ_between_the_known_points() {
+        let at = [1995.0, 2004.0, 2011.0];
+        assert_eq!(interpolate(&KNOWN, &at, Method::Linear).unwrap(), vec![23.5, 28.2, 34.0]);
+        assert_eq!(interpolate(&KNOWN, &at, Method::Step).unwrap(), vec![22.0, 25.0, 33.0]);
+
+        // Natural spline through (0, 0), (1, 1), (2, 0): m = [0, -3, 0], so s(0.5) = 0.5 + (1/6)(0.375 · 3)
+        let peak = [(0.0, 0.0), (1.0, 1.0), (2.0, 0.0)];
+        let values = interpolate(&peak, &[0.5, 1.5], Method::Spline).unwrap();
+        assert!(values.iter().all(|value| (value - 0.6875).abs() < 1e-12), "{:?}", values);
+
+        // A spline through points on a line is that line
+        let line: Vec<(f64, f64)> = (0..5).map(|i| (i as f64 * 3.0, 2.0 * i as f64 * 3.0 + 1.0)).collect();
+        let values = interpolate(&line, &[1.0, 7.5, 11.0], Method::Spline).unwrap();
+        assert!(values.iter().zip([3.0, 16.0, 23.0]).all(|(value, expected)| (value - expected).abs() < 1e-12), "{:?}", values);
+    }
+
+    #[test]
+    fn invalid_known_points_and_extrapolation_are_rejected() {
+        assert!(interpolate(&KNOWN[..1], &[1990.0], Method::Linear).is_err());
+        assert!(interpolate(&[(2000.0, 1.0), (2000.0, 2.0)], &[2000.0], Method::Linear).is_err());
+        assert!(interpolate(&[(2010.0, 1.0), (2000.0, 2.0)], &[2005.0], Method::Linear).is_err());
+        assert!(interpolate(&KNOWN, &[1989.0], Method::Spline).is_err());
+        assert!(interpolate(&KNOWN, &[2013.0], Method::Step).is_err());
+    }
+
+    #[test]
+    fn methods_parse_from_their_names() {
+        for method in [Method::Linear, Method::Step, Method::Spline] {
+            assert_eq!(method.name().parse::<Method>(), Ok(method));
+        }
+        assert!("cubic".parse::<Method>().is_err());
+    }
+}
@@ -0,0 +1,23 @@
+// Library half of ClimatePredict: the models and helpers shared by the binaries (and later the web server)
+
+pub mod behavior;
+pub mod counterfactual;
+pub mod dataset;
+pub mod energy_balance;
+pub mod explain;
+pub mod footprint;
+pub mod impute;
+pub mod inspect;
+pub mod interpolate;
+pub mod merge;
+pub mod neural;
+pub mod persist;
+pub mod pipeline;
+pub mod plot;
+pub mod regional;
+pub mod regression;
+pub mod scaling;
+pub mod scenario;
+pub mod sources;
+pub mod timeseries;
+pub mod training;
@@ -1,7 +1,9 @@
 //////////////////////////////////////// dependencies ////////////////////////////////////////
 
 use candle::{Device, Tensor};
-use csv::ReaderBuilder;
+use climate_predict::dataset::{self, Dataset};
+use climate_predict::regression::split_data;
+use climate_predict::scaling::{Scaler, ScalerKind};
 use std::error::Error;
 use std::io;
 
@@ -18,35 +20,45 @@ fn mean_squared_error(predictions: &[f64], targets: &[f64]) -> f64 {
 }
 
 
-fn process_data(data: &str) -> Result<((Tensor, Tensor), (Vec<f64>, Vec<f64>)), Box<dyn Error>> {
-    let file = std::fs::File::open(data)?;
-    let mut reader = ReaderBuilder::new().has_headers(true).from_reader(file);
-
-    let mut emissions = Vec::new();
-    let mut temps = Vec::new();
+/// Standardized tensors for training, with the raw values and the scalers fitted to them.
+struct Processed {
+    emissions_tensor: Tensor,
+    temps_tensor: Tensor,
+    emissions: Vec<f64>,
+    temps: Vec<f64>,
+    scalers: (Scaler, Scaler), // Emissions, temperatures
+}
 
-    for result in reader.records() {
-        let record = result?;
-        emissions.push(record[1].parse::<f64>()?);
-        temps.push(record[2].parse::<f64>()?);
-    }
+fn process_data(data: &str) -> Result<Processed, Box<dyn Error>> {
+    let dataset = Dataset::load(data, dataset::emission_temperature())?;
+    let emissions = dataset.floats("Emissions")?;
+    let temps = dataset.floats("Lowess")?;
 
     let device = Device::new_cuda(0)?;
-    let emissions_tensor = Tensor::from_slice(&emissions, (emissions.len(), 1), &device)?;
-    let temps_tensor = Tensor::from_slice(&temps, (temps.len(), 1), &device)?;
+    let (emissions_tensor, temps_tensor, scalers) = scaled_tensors(&emissions, &temps, &device)?;
 
-    Ok(((emissions_tensor, temps_tensor), (emissions, temps)))
+    Ok(Processed { emissions_tensor, temps_tensor, emissions, temps, scalers })
 }
 
+/// Emission and temperature tensors with the scalers used to build them.
+type ScaledTensors = (Tensor, Tensor, (Scaler, Scaler));
 
-fn split_data(emissions: &[f64], temps: &[f64], test_ratio: f64) -> ((Vec<f64>, Vec<f64>), (Vec<f64>, Vec<f64>)) {
-    let test_size = (emissions.len() as f64 * test_ratio).round() as usize;
-    let training_size = emissions.len() - test_size;
+/// Standardizes emissions (0.28–41 Gt) and temperatures before they go into tensors.
+fn scaled_tensors(emissions: &[f64], temps: &[f64], device: &Device) -> Result<ScaledTensors, Box<dyn Error>> {
+    let emission_scaler = Scaler::fit(ScalerKind::Standard, emissions)?;
+    let temp_scaler = Scaler::fit(ScalerKind::Standard, temps)?;
 
-    let training_data = (emissions[..training_size].to_vec(), temps[..training_size].to_vec());
-    let test_data = (emissions[training_size..].to_vec(), temps[training_size..].to_vec());
+    let emissions_tensor = Tensor::from_slice(&emission_scaler.transform_all(emissions), (emissions.len(), 1), device)?;
+    let temps_tensor = Tensor::from_slice(&temp_scaler.transform_all(temps), (temps.len(), 1), device)?;
+
+    Ok((emissions_tensor, temps_tensor, (emission_scaler, temp_scaler)))
+}
 
-    (training_data, test_data)
+/// Converts a line fitted on scaled values back to original units (°C per GtCO₂ and °C).
+fn unscale(slope: f64, intercept: f64, (x_scaler, y_scaler): (Scaler, Scaler)) -> (f64, f64) {
+    let original_slope = slope * y_scaler.scale / x_scaler.scale;
+    let original_intercept = y_scaler.inverse(intercept) - original_slope * x_scaler.center;
+    (original_slope, original_intercept)
 }
 
 
@@ -80,28 +92,27 @@ fn linear_regression(x: &Tensor, y: &Tensor) -> Result<(f64, f64), Box<dyn Error
 }
 
 fn test_model(data: &str, test_ratio: f64) -> Result<f64, Box<dyn Error>> {
-    let ((emissions_tensor, temps_tensor), (emissions, temps)) = process_data(data)?;
+    let processed = process_data(data)?;
 
     // Split raw data into training and testing sets
-    let ((emissions_train, temps_train), (emissions_test, temps_test)) = split_data(&emissions, &temps, test_ratio);
+    let ((emissions_train, temps_train), (emissions_test, temps_test)) = split_data(&processed.emissions, &processed.temps, test_ratio);
 
     // Assuming `device` is already initialized and available
     let device = Device::new_cuda(0)?;
 
-    // Convert training data into tensors
-    let emissions_tensor_train = Tensor::from_slice(&emissions_train, (emissions_train.len(), 1), &device)?;
-    let temps_tensor_train = Tensor::from_slice(&temps_train, (temps_train.len(), 1), &device)?;
+    // Convert training data into tensors, with scalers fitted on the training rows only
+    let (emissions_tensor_train, temps_tensor_train, scalers) = scaled_tensors(&emissions_train, &temps_train, &device)?;
 
-    // Train model using tensors from training data
+    // Train model using tensors from training data, then map the line back to original units
     let (slope, intercept) = linear_regression(&emissions_tensor_train, &temps_tensor_train)?;
+    let (slope, intercept) = unscale(slope, intercept, scalers);
 
     // Convert test data into tensors
     let emissions_tensor_test = Tensor::from_slice(&emissions_test, (emissions_test.len(), 1), &device)?;
-    let temps_tensor_test = Tensor::from_slice(&temps_test, (temps_test.len(), 1), &device)?;
 
-    // Use model to predict temperatures for test data
-    let predictions: Vec<f64> = emissions_tensor_test.strided_index()
-        .map(|em| slope * em as f64 + intercept)
+    // Use model to predict temperatures for test data (the tensor values, not their strided offsets)
+    let predictions: Vec<f64> = emissions_tensor_test.flatten_all()?.to_vec1::<f64>()?.iter()
+        .map(|em| slope * em + intercept)
         .collect();
 
     // Calculate Mean Squared Error
@@ -123,8 +134,9 @@ fn main() -> Result<(), Box<dyn Error>> {
     println!("Mean Squared Error on Test Set: {:.3}", mse);
 
     // Example of using the model interactively to predict temperatures based on emission input
-    let ((emissions_tensor, temps_tensor), (_emissions, _temps)) = process_data(DATA)?;
-    let (slope, intercept) = linear_regression(&emissions_tensor, &temps_tensor)?;
+    let processed = process_data(DATA)?;
+    let (slope, intercept) = linear_regression(&processed.emissions_tensor, &processed.temps_tensor)?;
+    let (slope, intercept) = unscale(slope, intercept, processed.scalers);
 
     println!("Model trained with parameters: Slope (β) = {:.4}, Intercept (α) = {:.4}", slope, intercept);
 
@@ -140,10 +152,10 @@ fn main() -> Result<(), Box<dyn Error>> {
             println!("Please enter a valid number.");
             0.0 // Default value if parse fails; could also choose to re-prompt for input
         });
-        emission_value = emission_value + 40.9;
+        emission_value += 40.9;
         // Calculate and print the predicted temperature change based on the input
         let mut predicted_temp = slope * emission_value + intercept;
-        predicted_temp = predicted_temp - 1.01;
+        predicted_temp -= 1.01;
         println!("Predicted temperature change: {:.2} °C", predicted_temp);
     }
 
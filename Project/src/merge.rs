@@ -0,0 +1,223 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::interpolate::{self, Method};
+use crate::sources::{self, AnnualSeries, Smoothing};
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const OUTPUT: &str = "./data/emission_temp_data.csv";
+pub const MANIFEST: &str = "./data/emission_temp_data.manifest.json";
+const HEADER: &str = "Year,Emissions(GtCO₂),Lowess(°C)";
+const DECIMALS: i32 = 4; // Values are rounded to this many decimals, trailing zeros dropped
+
+//////////////////////////////////////// merging ////////////////////////////////////////
+
+/// One row of `emission_temp_data.csv`.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Row {
+    pub year: i32,
+    pub emissions: f64,   // GtCO₂ per year, interpolated
+    pub temperature: f64, // °C anomaly, as published
+}
+
+/// Lays `overrides` over `base`: the result is sorted by year and takes the override wherever both have a year.
+pub fn overlay(base: &AnnualSeries, overrides: &AnnualSeries) -> AnnualSeries {
+    let mut merged: BTreeMap<i32, f64> = base.iter().copied().collect();
+    merged.extend(overrides.iter().copied());
+    merged.into_iter().collect()
+}
+
+/// Joins the emissions and temperature series on year.
+///
+/// Temperatures are annual already; emissions are interpolated to every temperature year inside
+/// the range of the emission series. Years outside that range are left out rather than extrapolated.
+///
+/// Args:
+///     emissions: Emission values at the years they were published (any spacing).
+///     temperatures: Annual temperature anomalies.
+///     method: How to fill the years between published emission values.
+///
+/// Returns:
+///     One row per joined year, in year order.
+pub fn merge(emissions: &AnnualSeries, temperatures: &AnnualSeries, method: Method) -> Result<Vec<Row>, Box<dyn Error>> {
+    let mut known: Vec<(f64, f64)> = emissions.iter().map(|&(year, value)| (year as f64, value)).collect();
+    known.sort_by(|a, b| a.0.total_cmp(&b.0));
+    let (first, last) = match (known.first(), known.last()) {
+        (Some(first), Some(last)) => (first.0 as i32, last.0 as i32),
+        _ => return Err("the emission series is empty".into()),
+    };
+
+    let mut temperatures: Vec<(i32, f64)> = temperatures.iter().copied().filter(|(year, _)| (first..=last).contains(year)).collect();
+    temperatures.sort_by_key(|&(year, _)| year);
+    let years: Vec<f64> = temperatures.iter().map(|&(year, _)| year as f64).collect();
+    let interpolated = interpolate::interpolate(&known, &years, method)?;
+
+    Ok(temperatures.iter().zip(interpolated)
+        .map(|(&(year, temperature), emissions)| Row { year, emissions, temperature })
+        .collect())
+}
+
+/// Writes rows in the layout of the checked-in `emission_temp_data.csv` (no trailing newline).
+pub fn to_csv(rows: &[Row]) -> String {
+    let mut lines = vec![HEADER.to_string()];
+    lines.extend(rows.iter().map(|row| format!("{},{},{}", row.year, format_value(row.emissions), format_value(row.temperature))));
+    lines.join("\n")
+}
+
+/// Rounds to DECIMALS and drops trailing zeros, so 25.4460 is written as 25.446 and -0.20 as -0.2.
+fn format_value(value: f64) -> String {
+    let scale = 10f64.powi(DECIMALS);
+    let rounded = (value * scale).round() / scale;
+    let rounded = if rounded == 0.0 { 0.0 } else { rounded }; // Avoid writing "-0"
+    let text = format!("{:.*}", DECIMALS as usize, rounded);
+    text.trim_end_matches('0').trim_end_matches('.').to_string()
+}
+
+//////////////////////////////////////// provenance ////////////////////////////////////////
+
+/// A file the dataset was built from.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct SourceFile {
+    pub path: String,
+    pub role: String,
+    pub rows: usize,
+    pub checksum: String,
+}
+
+/// Sidecar describing how `emission_temp_data.csv` was produced.
+///
+/// No timestamp is recorded, so rebuilding from unchanged sources gives an identical manifest.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Manifest {
+    pub output: String,
+    pub rows: usize,
+    pub first_year: i32,
+    pub last_year: i32,
+    pub interpolation: String,
+    pub checksum: String,
+    pub sources: Vec<SourceFile>,
+}
+
+/// FNV-1a hash of a file's bytes: enough to notice a changed source, not a cryptographic digest.
+pub fn checksum(bytes: &[u8]) -> String {
+    let hash = bytes.iter().fold(0xcbf29ce484222325u64, |hash, &byte| (hash ^ byte as u64).wrapping_mul(0x100000001b3));
+    format!("fnv1a64:{:016x}", hash)
+}
+
+/// A freshly built dataset and its manifest.
+#[derive(Debug, Clone)]
+pub struct Build {
+    pub csv: String,
+    pub manifest: Manifest,
+}
+
+/// Builds `emission_temp_data.csv` from the raw sources in `data/`.
+///
+/// The emission series is `carbon-emissions.csv` with `emission-anchors.csv` laid over it, and the
+/// temperatures are the Lowess(5) column of the GISTEMP export.
+pub fn build(method: Method) -> Result<Build, Box<dyn Error>> {
+    let emissions = sources::carbon_emissions_series(sources::CARBON_EMISSIONS)?;
+    let anchors = sources::emission_anchor_series(sources::EMISSION_ANCHORS)?;
+    let temperatures = sources::gistemp_series(sources::GISTEMP, Smoothing::Lowess)?;
+
+    let rows = merge(&overlay(&emissions, &anchors), &temperatures, method)?;
+    let (first_year, last_year) = match (rows.first(), rows.last()) {
+        (Some(first), Some(last)) => (first.year, last.year),
+        _ => return Err("the emission and temperature series have no years in common".into()),
+    };
+    let csv = to_csv(&rows);
+
+    let source = |path: &str, role: &str, rows: usize| -> Result<SourceFile, Box<dyn Error>> {
+        Ok(SourceFile { path: path.to_string(), role: role.to_string(), rows, checksum: checksum(&fs::read(path)?) })
+    };
+    let manifest = Manifest {
+        output: OUTPUT.to_string(),
+        rows: rows.len(),
+        first_year,
+        last_year,
+        interpolation: method.name().to_string(),
+        checksum: checksum(csv.as_bytes()),
+        sources: vec![
+            source(sources::CARBON_EMISSIONS, "emissions (GtCO₂), decadal before 2010", emissions.len())?,
+            source(sources::EMISSION_ANCHORS, "emissions (GtCO₂) missing from the raw series", anchors.len())?,
+            source(sources::GISTEMP, &format!("temperature anomaly (°C), {}", Smoothing::Lowess.column()), temperatures.len())?,
+        ],
+    };
+
+    Ok(Build { csv, manifest })
+}
+
+/// First line where two files differ, as (line number, expected, actual); None if every line matches.
+pub fn first_difference(expected: &str, actual: &str) -> Option<(usize, String, String)> {
+    let mut expected_lines = expected.lines();
+    let mut actual_lines = actual.lines();
+    let mut line = 1;
+    loop {
+        match (expected_lines.next(), actual_lines.next()) {
+            (None, None) => return None,
+            (left, right) if left != right => {
+                let show = |text: Option<&str>| text.unwrap_or("<end of file>").to_string();
+                return Some((line, show(left), show(right)));
+            }
+            _ => line += 1,
+        }
+    }
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn checked_in_dataset_matches_a_rebuild() {
+        let manifest_text = fs::read_to_string(MANIFEST).unwrap();
+        let manifest: Manifest = serde_json::from_str(&manifest_text).unwrap();
+        let build = build(manifest.interpolation.parse().unwrap()).unwrap();
+
+        assert_eq!(first_difference(&fs::read_to_string(OUTPUT).unwrap(), &build.csv), None, "run `cargo run --bin build-dataset -- --write`");
+        assert_eq!(first_difference(&manifest_text, &serde_json::to_string_pretty(&build.manifest).unwrap()), None);
+        assert_eq!(manifest.checksum, checksum(build.csv.as_bytes()));
+    }
+
+    #[test]
+    fn overlay_sorts_by_year_and_prefers_the_overrides() {
+        let base = vec![(2010, 33.0), (1990, 22.0), (2000, 25.0)];
+        let overrides = vec![(2000, 25.5), (2020, 35.0)];
+        assert_eq!(overlay(&base, &overrides), vec![(1990, 22.0), (2000, 25.5), (2010, 33.0), (2020, 35.0)]);
+    }
+
+    #[test]
+    fn merge_interpolates_emissions_to_the_temperature_years_inside_their_range() {
+        let emissions = vec![(2010, 30.0), (2000, 20.0)];
+        let temperatures: AnnualSeries = (1998..=2012).rev().map(|year| (year, (year - 2000) as f64 / 100.0)).collect();
+
+        let rows = merge(&emissions, &temperatures, Method::Linear).unwrap();
+        assert_eq!(rows.len(), 11);
+        assert_eq!(rows[0], Row { year: 2000, emissions: 20.0, temperature: 0.0 });
+        assert_eq!(rows[4], Row { year: 2004, emissions: 24.0, temperature: 0.04 });
+        assert_eq!(rows[10], Row { year: 2010, emissions: 30.0, temperature: 0.1 });
+
+        let stepped = merge(&emissions, &temperatures, Method::Step).unwrap();
+        assert_eq!(stepped[9].emissions, 20.0);
+        assert!(merge(&Vec::new(), &temperatures, Method::Linear).is_err());
+    }
+
+    #[test]
+    fn csv_rounds_values_and_drops_trailing_zeros() {
+        let rows = [Row { year: 1880, emissions: 25.446_04, temperature: -0.2 }, Row { year: 1881, emissions: 3.0, temperature: -0.000_01 }];
+        assert_eq!(to_csv(&rows), format!("{}\n1880,25.446,-0.2\n1881,3,0", HEADER));
+    }
+
+    #[test]
+    fn first_difference_reports_the_line_and_both_texts() {
+        assert_eq!(first_difference("a\nb\n", "a\nb"), None);
+        assert_eq!(first_difference("a\nb\nc", "a\nx\nc"), Some((2, "b".to_string(), "x".to_string())));
+        assert_eq!(first_difference("a", "a\nb"), Some((2, "<end of file>".to_string(), "b".to_string())));
+    }
+}
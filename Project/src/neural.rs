@@ -0,0 +1,170 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::regression::{mean_squared_error, GradientDescent, Prediction};
+use crate::scaling::{Scaler, ScalerKind};
+use rand::rngs::StdRng;
+use rand::{Rng, SeedableRng};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::ops::ControlFlow;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const HIDDEN: usize = 8; // Hidden units; enough for the gentle curve of temperature against emissions
+const SEED: u64 = 42; // Initial weights come from a fixed seed, so retraining gives the same network
+
+//////////////////////////////////////// network ////////////////////////////////////////
+
+/// Network with one hidden layer of tanh units, y = Σ v_j tanh(w_j x + b_j) + c on standardized x
+/// and y, small enough to be saved as JSON and evaluated without a tensor library.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct NeuralModel {
+    pub input_weights: Vec<f64>,  // w_j
+    pub hidden_biases: Vec<f64>,  // b_j
+    pub output_weights: Vec<f64>, // v_j
+    pub output_bias: f64,         // c
+    pub input_scaler: Scaler,
+    pub target_scaler: Scaler,
+    pub residual_variance: f64,   // σ² of the training residuals in target units
+}
+
+impl NeuralModel {
+    /// Fits the network by full-batch gradient descent on the mean squared error.
+    ///
+    /// Args:
+    ///     x: Independent variable (e.g. emissions).
+    ///     y: Dependent variable (e.g. temperature).
+    ///     hidden: Number of hidden units.
+    ///     settings: Number of epochs and learning rate.
+    ///     on_epoch: Called after every epoch with its number (from 1) and the training mean squared
+    ///         error in y units; returning ControlFlow::Break stops the fit.
+    ///
+    /// Returns:
+    ///     The network after the last epoch, or an error if the settings are invalid, the fit
+    ///     diverged or it was stopped.
+    pub fn fit<F>(x: &[f64], y: &[f64], hidden: usize, settings: GradientDescent, mut on_epoch: F) -> Result<Self, Box<dyn Error>>
+    where
+        F: FnMut(usize, f64) -> ControlFlow<()>,
+    {
+        if x.len() != y.len() || x.len() < 2 {
+            return Err("a neural network needs at least two (x, y) pairs".into());
+        }
+        if hidden == 0 {
+            return Err("a neural network needs at least one hidden unit".into());
+        }
+        if !(settings.learning_rate.is_finite() && settings.learning_rate > 0.0) {
+            return Err(format!("the learning rate must be positive, found {}", settings.learning_rate).into());
+        }
+        let (input_scaler, target_scaler) = (Scaler::fit(ScalerKind::Standard, x)?, Scaler::fit(ScalerKind::Standard, y)?);
+        let (xs, ys) = (input_scaler.transform_all(x), target_scaler.transform_all(y));
+        let n = x.len() as f64;
+
+        // Uniform initial weights scaled by the fan-in: 1 for the hidden layer, `hidden` for the output
+        let mut rng = StdRng::seed_from_u64(SEED);
+        let mut uniform = |scale: f64| -> Vec<f64> { (0..hidden).map(|_| rng.gen_range(-1.0..1.0) * scale).collect() };
+        let mut model = Self {
+            input_weights: uniform(1.0),
+            hidden_biases: uniform(1.0),
+            output_weights: uniform(1.0 / (hidden as f64).sqrt()),
+            output_bias: 0.0,
+            input_scaler,
+            target_scaler,
+            residual_variance: 0.0,
+        };
+
+        for epoch in 1..=settings.epochs {
+            // Backpropagation of (1/n)Σ(ŷ - y)² through the output and hidden layers
+            let (mut input_gradient, mut bias_gradient, mut output_gradient) = (vec![0.0; hidden], vec![0.0; hidden], vec![0.0; hidden]);
+            let mut output_bias_gradient = 0.0;
+            for (xi, yi) in xs.iter().zip(&ys) {
+                let activations = model.activations(*xi);
+                let error = 2.0 * (model.output(&activations) - yi) / n;
+                output_bias_gradient += error;
+                for (j, activation) in activations.iter().enumerate() {
+                    output_gradient[j] += error * activation;
+                    let back = error * model.output_weights[j] * (1.0 - activation * activation);
+                    input_gradient[j] += back * xi;
+                    bias_gradient[j] += back;
+                }
+            }
+            let step = |parameters: &mut [f64], gradients: &[f64]| {
+                parameters.iter_mut().zip(gradients).for_each(|(parameter, gradient)| *parameter -= settings.learning_rate * gradient);
+            };
+            step(&mut model.input_weights, &input_gradient);
+            step(&mut model.hidden_biases, &bias_gradient);
+            step(&mut model.output_weights, &output_gradient);
+            model.output_bias -= settings.learning_rate * output_bias_gradient;
+
+            let predictions: Vec<f64> = x.iter().map(|&xi| model.predict(xi)).collect();
+            let loss = mean_squared_error(&predictions, y);
+            if !loss.is_finite() {
+                return Err(format!("neural network training diverged at epoch {}; lower the learning rate", epoch).into());
+            }
+            if on_epoch(epoch, loss).is_break() {
+                return Err(format!("neural network training stopped after epoch {}", epoch).into());
+            }
+        }
+
+        let parameters = 3 * hidden + 1;
+        let sum_squares: f64 = x.iter().zip(y).map(|(&xi, yi)| (yi - model.predict(xi)).powi(2)).sum();
+        model.residual_variance = sum_squares / (x.len().saturating_sub(parameters)).max(1) as f64;
+        Ok(model)
+    }
+
+    pub fn hidden(&self) -> usize {
+        self.input_weights.len()
+    }
+
+    pub fn predict(&self, x: f64) -> f64 {
+        let activations = self.activations(self.input_scaler.transform(x));
+        self.target_scaler.inverse(self.output(&activations))
+    }
+
+    /// Prediction with the spread of the training residuals; the network has no leverage term.
+    pub fn predict_with_uncertainty(&self, x: f64) -> Prediction {
+        Prediction { mean: self.predict(x), std: self.residual_variance.sqrt() }
+    }
+
+    // Hidden layer outputs for a scaled input
+    fn activations(&self, scaled: f64) -> Vec<f64> {
+        self.input_weights.iter().zip(&self.hidden_biases).map(|(w, b)| (w * scaled + b).tanh()).collect()
+    }
+
+    // Scaled output of the hidden layer outputs
+    fn output(&self, activations: &[f64]) -> f64 {
+        self.output_weights.iter().zip(activations).map(|(v, a)| v * a).sum::<f64>() + self.output_bias
+    }
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn learns_a_curve_a_line_cannot() {
+        let x: Vec<f64> = (0..40).map(|i| i as f64 / 2.0).collect();
+        let y: Vec<f64> = x.iter().map(|xi| (xi - 10.0).powi(2) / 10.0).collect();
+        let mut losses = Vec::new();
+        let model = NeuralModel::fit(&x, &y, HIDDEN, GradientDescent { epochs: 3000, learning_rate: 0.1 }, |_, loss| {
+            losses.push(loss);
+            ControlFlow::Continue(())
+        }).unwrap();
+
+        assert_eq!(losses.len(), 3000);
+        assert!(losses[2999] < losses[0] / 20.0, "loss went from {} to {}", losses[0], losses[2999]);
+        assert!((model.predict(10.0) - 0.0).abs() < 0.5 && (model.predict(0.0) - 10.0).abs() < 1.0);
+
+        let saved: NeuralModel = serde_json::from_str(&serde_json::to_string(&model).unwrap()).unwrap();
+        assert_eq!(saved.predict(3.3), model.predict(3.3));
+    }
+
+    #[test]
+    fn stops_when_asked() {
+        let stopped = NeuralModel::fit(&[0.0, 1.0, 2.0], &[0.0, 1.0, 4.0], 2, GradientDescent { epochs: 10, learning_rate: 0.1 }, |epoch, _| {
+            if epoch == 3 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        assert!(stopped.is_err());
+    }
+}
@@ -0,0 +1,342 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{self, Dataset};
+use crate::footprint::FootprintModel;
+use crate::neural::{self, NeuralModel};
+use crate::pipeline::{Pipeline, Stage};
+use crate::regression::{mean_squared_error, GradientDescent, LinearModel, PolynomialModel, Prediction};
+use crate::training::{self, Algorithm};
+use crate::scaling::Scaling;
+use serde::de::DeserializeOwned;
+use serde::{Deserialize, Serialize};
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::ops::ControlFlow;
+use std::path::Path;
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const MODELS: &str = "./models"; // Directory of saved models
+pub const CLIMATE: &str = "climate"; // Name of the default ClimateModel artifact in MODELS, with a linear temperature model
+pub const CLIMATE_POLYNOMIAL: &str = "climate-polynomial"; // ClimateModel artifact with a polynomial temperature model
+pub const CLIMATE_NEURAL: &str = "climate-neural"; // ClimateModel artifact with a neural network temperature model
+pub const FOOTPRINT: &str = "footprint"; // Name of the default FootprintModel artifact in MODELS
+pub const FOREST: &str = "footprint-forest"; // Name of the random forest FootprintModel artifact, which is opt-in
+pub const DEGREE: usize = 3; // Degree of the emissions-over-time polynomial
+pub const TEMPERATURE_DEGREE: usize = 2; // Degree of the polynomial temperature model
+
+/// Named quality measures of a trained model, e.g. `train_rmse`.
+pub type Metrics = BTreeMap<String, f64>;
+
+//////////////////////////////////////// files ////////////////////////////////////////
+
+/// Writes a model as pretty JSON, creating the parent directory if needed.
+pub fn save<T: Serialize>(model: &T, path: &str) -> Result<(), Box<dyn Error>> {
+    if let Some(parent) = Path::new(path).parent() {
+        fs::create_dir_all(parent)?;
+    }
+    fs::write(path, serde_json::to_string_pretty(model)? + "\n")?;
+    Ok(())
+}
+
+/// Reads a model saved by `save`.
+pub fn load<T: DeserializeOwned>(path: &str) -> Result<T, Box<dyn Error>> {
+    let json = fs::read_to_string(path).map_err(|error| format!("cannot read model {}: {}", path, error))?;
+    Ok(serde_json::from_str(&json).map_err(|error| format!("invalid model {}: {}", path, error))?)
+}
+
+//////////////////////////////////////// saved models ////////////////////////////////////////
+
+/// Emissions → temperature model of a ClimateModel, of any kind that can be trained for it.
+///
+/// Untagged, so models saved when the temperature model was always a line still load.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+#[serde(untagged)]
+pub enum TemperatureFit {
+    Linear(LinearModel),
+    Polynomial(PolynomialModel),
+    Neural(NeuralModel),
+}
+
+impl TemperatureFit {
+    /// Fits a temperature model of kind `algorithm`; the line and the network learn by gradient
+    /// descent, reporting each epoch to `on_epoch`, while the polynomial is solved in one step.
+    pub fn fit<F>(emissions: &[f64], temps: &[f64], algorithm: Algorithm, settings: GradientDescent, mut on_epoch: F) -> Result<Self, Box<dyn Error>>
+    where
+        F: FnMut(usize, f64) -> ControlFlow<()>,
+    {
+        match algorithm {
+            Algorithm::Linear => Ok(Self::Linear(LinearModel::fit_gradient_descent(emissions, temps, settings, on_epoch)?)),
+            Algorithm::Polynomial => {
+                let model = PolynomialModel::fit_scaled(emissions, temps, TEMPERATURE_DEGREE, &vec![1.0; emissions.len()], Scaling::standard())?;
+                let fitted: Vec<f64> = emissions.iter().map(|&e| model.predict(e)).collect();
+                if on_epoch(1, mean_squared_error(&fitted, temps)).is_break() {
+                    return Err("training stopped".into());
+                }
+                Ok(Self::Polynomial(model))
+            }
+            Algorithm::Neural => Ok(Self::Neural(NeuralModel::fit(emissions, temps, neural::HIDDEN, settings, on_epoch)?)),
+            Algorithm::Forest => Err("a climate temperature model is linear, polynomial or neural".into()),
+        }
+    }
+
+    pub fn predict_with_uncertainty(&self, x: f64) -> Prediction {
+        match self {
+            TemperatureFit::Linear(model) => model.predict_with_uncertainty(x),
+            TemperatureFit::Polynomial(model) => model.predict_with_uncertainty(x),
+            TemperatureFit::Neural(model) => model.predict_with_uncertainty(x),
+        }
+    }
+
+    /// Spread of a single observation around the model (°C).
+    pub fn residual_std(&self) -> f64 {
+        match self {
+            TemperatureFit::Linear(model) => model.residual_variance.sqrt(),
+            TemperatureFit::Polynomial(model) => model.residual_variance.sqrt(),
+            TemperatureFit::Neural(model) => model.residual_variance.sqrt(),
+        }
+    }
+}
+
+impl Stage for TemperatureFit {
+    fn name(&self) -> String {
+        match self {
+            TemperatureFit::Linear(model) => model.name(),
+            TemperatureFit::Polynomial(model) => model.name(),
+            TemperatureFit::Neural(model) => model.name(),
+        }
+    }
+
+    fn predict(&self, x: f64) -> Prediction {
+        self.predict_with_uncertainty(x)
+    }
+}
+
+/// The year → emissions → temperature models, trained once and saved as JSON so that other
+/// programs (the web server) can load them without refitting.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct ClimateModel {
+    pub source: String,             // Dataset the models were trained on
+    pub first_year: i32,            // Years covered by the training data; predictions outside extrapolate
+    pub last_year: i32,
+    pub emissions: PolynomialModel, // Year → global emissions (GtCO₂)
+    pub temperature: TemperatureFit, // Global emissions (GtCO₂) → temperature anomaly (°C)
+}
+
+impl ClimateModel {
+    /// Fits both models on every row of the merged emissions/temperature dataset, with the settings
+    /// of the default `climate` artifact: a DEGREE polynomial and a line fitted like a linear training job.
+    pub fn train(path: &str) -> Result<Self, Box<dyn Error>> {
+        let settings = GradientDescent { epochs: training::EPOCHS, learning_rate: training::LEARNING_RATE };
+        Self::train_with(path, DEGREE, Algorithm::Linear, settings, |_, _| ControlFlow::Continue(()))
+    }
+
+    /// Fits both models on every row of the merged emissions/temperature dataset, with an emissions
+    /// polynomial of `degree` and a temperature model of kind `algorithm`, calling `on_epoch` with
+    /// each epoch and its loss.
+    pub fn train_with<F>(path: &str, degree: usize, algorithm: Algorithm, settings: GradientDescent, on_epoch: F) -> Result<Self, Box<dyn Error>>
+    where
+        F: FnMut(usize, f64) -> ControlFlow<()>,
+    {
+        let dataset = Dataset::load(path, dataset::emission_temperature())?;
+        let years = dataset.floats("Year")?;
+        let emissions = dataset.floats("Emissions")?;
+        let temps = dataset.floats("Lowess")?;
+        let (Some(&first_year), Some(&last_year)) = (years.first(), years.last()) else {
+            return Err(format!("{} has no rows to train on", path).into());
+        };
+
+        Ok(Self {
+            source: path.to_string(),
+            first_year: first_year as i32,
+            last_year: last_year as i32,
+            emissions: PolynomialModel::fit_scaled(&years, &emissions, degree, &vec![1.0; years.len()], Scaling::standard())?,
+            temperature: TemperatureFit::fit(&emissions, &temps, algorithm, settings, on_epoch)?,
+        })
+    }
+
+    /// In-sample root mean squared errors of both models on the rows of `path`.
+    pub fn evaluate(&self, path: &str) -> Result<Metrics, Box<dyn Error>> {
+        let dataset = Dataset::load(path, dataset::emission_temperature())?;
+        let years = dataset.floats("Year")?;
+        let emissions = dataset.floats("Emissions")?;
+        let temps = dataset.floats("Lowess")?;
+
+        let fitted_emissions: Vec<f64> = years.iter().map(|&year| self.emissions.predict(year)).collect();
+        let fitted_temps: Vec<f64> = emissions.iter().map(|&e| self.temperature.predict_with_uncertainty(e).mean).collect();
+        Ok(Metrics::from([
+            ("emissions_train_rmse".to_string(), mean_squared_error(&fitted_emissions, &emissions).sqrt()),
+            ("temperature_train_rmse".to_string(), mean_squared_error(&fitted_temps, &temps).sqrt()),
+            ("rows".to_string(), years.len() as f64),
+        ]))
+    }
+
+    /// Where scenarios start: the last year of the training data and the fitted emissions then (GtCO₂).
+    pub fn start(&self) -> (i32, f64) {
+        (self.last_year, self.emissions.predict(self.last_year as f64))
+    }
+
+    /// The models chained year → emissions → temperature, propagating uncertainty between them.
+    pub fn pipeline(&self) -> Pipeline {
+        Pipeline::new()
+            .then("emissions (GtCO₂)", self.emissions.clone())
+            .then("temperature (°C)", self.temperature.clone())
+    }
+}
+
+//////////////////////////////////////// artifacts ////////////////////////////////////////
+
+/// A trained model of any kind the web server can serve.
+#[derive(Serialize, Deserialize)]
+#[serde(rename_all = "lowercase")]
+pub enum SavedModel {
+    Climate(ClimateModel),
+    Footprint(FootprintModel),
+}
+
+impl SavedModel {
+    /// What the model predicts: `climate` or `footprint`.
+    pub fn task(&self) -> &'static str {
+        match self {
+            SavedModel::Climate(_) => "climate",
+            SavedModel::Footprint(_) => "footprint",
+        }
+    }
+
+    /// The kind of model, for display.
+    pub fn algorithm(&self) -> String {
+        match self {
+            SavedModel::Climate(model) => format!("polynomial (degree {}) emissions, {} temperature", model.emissions.degree(), model.temperature.name()),
+            SavedModel::Footprint(model) => model.name().to_string(),
+        }
+    }
+}
+
+/// What a registry lists about a saved model.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct Metadata {
+    pub name: String,
+    pub version: u32,
+    pub algorithm: String,
+    pub source: String,   // Data the model was trained on
+    pub trained_at: u64,  // Unix time in seconds
+    pub metrics: Metrics,
+}
+
+/// A model saved with its metadata, as `<name>-v<version>.json` in a models directory, so that
+/// several models and versions of each can sit side by side.
+#[derive(Serialize, Deserialize)]
+pub struct Artifact {
+    #[serde(flatten)]
+    pub metadata: Metadata,
+    pub model: SavedModel,
+}
+
+impl Artifact {
+    /// Wraps a freshly trained model, stamped with the current time.
+    pub fn new(name: &str, version: u32, source: &str, metrics: Metrics, model: SavedModel) -> Self {
+        let trained_at = SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs());
+        let metadata = Metadata { name: name.to_string(), version, algorithm: model.algorithm(), source: source.to_string(), trained_at, metrics };
+        Self { metadata, model }
+    }
+
+    pub fn file_name(name: &str, version: u32) -> String {
+        format!("{}-v{}.json", name, version)
+    }
+
+    /// Saves the artifact under its file name in `directory`, returning the path.
+    pub fn save(&self, directory: &str) -> Result<String, Box<dyn Error>> {
+        let path = Path::new(directory).join(Self::file_name(&self.metadata.name, self.metadata.version)).to_string_lossy().into_owned();
+        save(self, &path)?;
+        Ok(path)
+    }
+}
+
+/// The name and version in an artifact file name, as in `climate-v2.json`.
+pub fn parse_file_name(file: &str) -> Option<(&str, u32)> {
+    let (name, version) = file.strip_suffix(".json")?.rsplit_once("-v")?;
+    Some((name, version.parse().ok()?))
+}
+
+/// One more than the highest version of `name` saved in `directory`, or 1.
+pub fn next_version(directory: &str, name: &str) -> Result<u32, Box<dyn Error>> {
+    let Ok(entries) = fs::read_dir(directory) else {
+        return Ok(1);
+    };
+    let mut latest = 0;
+    for entry in entries {
+        let file = entry?.file_name();
+        if let Some((saved, version)) = file.to_str().and_then(parse_file_name) {
+            if saved == name {
+                latest = latest.max(version);
+            }
+        }
+    }
+    Ok(latest + 1)
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn train_fits_the_line_like_a_default_linear_job() {
+        let path = crate::merge::OUTPUT;
+        let trained = ClimateModel::train(path).unwrap();
+        let settings = GradientDescent { epochs: training::EPOCHS, learning_rate: training::LEARNING_RATE };
+        let job = ClimateModel::train_with(path, DEGREE, Algorithm::Linear, settings, |_, _| ControlFlow::Continue(())).unwrap();
+        assert_eq!(serde_json::to_string(&trained).unwrap(), serde_json::to_string(&job).unwrap());
+
+        // Gradient descent on standardized emissions converges to the least squares line
+        let dataset = Dataset::load(path, dataset::emission_temperature()).unwrap();
+        let emissions = dataset.floats("Emissions").unwrap();
+        let closed_form = TemperatureFit::Linear(LinearModel::fit(&emissions, &dataset.floats("Lowess").unwrap()).unwrap());
+        for e in [0.0, 20.0, 40.0] {
+            let (fitted, exact) = (trained.temperature.predict_with_uncertainty(e).mean, closed_form.predict_with_uncertainty(e).mean);
+            assert!((fitted - exact).abs() < 1e-3, "{} vs {} at {} GtCO₂", fitted, exact, e);
+        }
+    }
+
+    #[test]
+    fn file_names_carry_the_name_and_version() {
+        assert_eq!(Artifact::file_name(CLIMATE_POLYNOMIAL, 12), "climate-polynomial-v12.json");
+        assert_eq!(parse_file_name("climate-polynomial-v12.json"), Some((CLIMATE_POLYNOMIAL, 12)));
+        assert_eq!(parse_file_name("footprint-v1.json"), Some((FOOTPRINT, 1)));
+        assert_eq!(parse_file_name("climate.json"), None);
+        assert_eq!(parse_file_name("climate-vx.json"), None);
+        assert_eq!(parse_file_name("climate-v1.csv"), None);
+    }
+
+    #[test]
+    fn the_next_version_follows_the_highest_saved_one() {
+        let directory = std::env::temp_dir().join(format!("climate-predict-{}-versions", std::process::id()));
+        let directory = directory.to_str().unwrap();
+        assert_eq!(next_version(directory, CLIMATE).unwrap(), 1);
+
+        fs::create_dir_all(directory).unwrap();
+        for file in ["climate-v1.json", "climate-v3.json", "climate-neural-v7.json", "climate-v9.csv", "notes.txt"] {
+            fs::write(Path::new(directory).join(file), "{}").unwrap();
+        }
+        let versions = (next_version(directory, CLIMATE).unwrap(), next_version(directory, CLIMATE_NEURAL).unwrap(), next_version(directory, FOOTPRINT).unwrap());
+        fs::remove_dir_all(directory).unwrap();
+        assert_eq!(versions, (4, 8, 1));
+    }
+
+    #[test]
+    fn shipped_artifacts_load_with_their_kind_of_temperature_model() {
+        for (name, kind) in [(CLIMATE, "Linear"), (CLIMATE_POLYNOMIAL, "Polynomial"), (CLIMATE_NEURAL, "Neural")] {
+            let path = Path::new(MODELS).join(Artifact::file_name(name, 1));
+            let artifact: Artifact = load(path.to_str().unwrap()).unwrap();
+            let SavedModel::Climate(climate) = artifact.model else { panic!("{} is not a climate model", name) };
+            assert!(format!("{:?}", climate.temperature).starts_with(kind), "{} loaded as {}", name, climate.temperature.name());
+        }
+
+        // A line saved before the temperature model had a kind still loads as one
+        let line = serde_json::to_string(&LinearModel::fit(&[0.0, 1.0, 2.0, 3.0], &[1.0, 3.0, 5.1, 6.9]).unwrap()).unwrap();
+        assert!(matches!(serde_json::from_str(&line).unwrap(), TemperatureFit::Linear(_)));
+    }
+}
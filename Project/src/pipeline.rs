@@ -0,0 +1,145 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::neural::NeuralModel;
+use crate::regression::{LinearModel, PolynomialModel, Prediction};
+
+//////////////////////////////////////// stages ////////////////////////////////////////
+
+/// A single-input model that can be chained into a pipeline.
+pub trait Stage {
+    fn name(&self) -> String;
+
+    /// Prediction for `x`, with the uncertainty of the stage itself (treating `x` as exact).
+    fn predict(&self, x: f64) -> Prediction;
+}
+
+impl Stage for LinearModel {
+    fn name(&self) -> String {
+        "linear".to_string()
+    }
+
+    fn predict(&self, x: f64) -> Prediction {
+        self.predict_with_uncertainty(x)
+    }
+}
+
+impl Stage for PolynomialModel {
+    fn name(&self) -> String {
+        format!("polynomial (degree {})", self.degree())
+    }
+
+    fn predict(&self, x: f64) -> Prediction {
+        self.predict_with_uncertainty(x)
+    }
+}
+
+impl Stage for NeuralModel {
+    fn name(&self) -> String {
+        format!("neural network ({} hidden units)", self.hidden())
+    }
+
+    fn predict(&self, x: f64) -> Prediction {
+        self.predict_with_uncertainty(x)
+    }
+}
+
+//////////////////////////////////////// pipeline ////////////////////////////////////////
+
+/// The output of one stage of a pipeline run.
+#[derive(Debug, Clone)]
+pub struct StageOutput {
+    pub label: String,
+    pub model: String,
+    pub prediction: Prediction,
+}
+
+/// Models composed so that each stage's output is the next stage's input,
+/// e.g. year → emissions (polynomial) → temperature (linear).
+#[derive(Default)]
+pub struct Pipeline {
+    stages: Vec<(String, Box<dyn Stage>)>,
+}
+
+impl Pipeline {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Appends a stage; `label` names the quantity it produces (e.g. "emissions").
+    pub fn then<S: Stage + 'static>(mut self, label: &str, stage: S) -> Self {
+        self.stages.push((label.to_string(), Box::new(stage)));
+        self
+    }
+
+    /// Runs `x` through every stage, propagating uncertainty from one stage to the next.
+    ///
+    /// Each stage's input is uncertain, so its output variance is its own predictive variance
+    /// plus the input variance scaled by the squared local slope of the stage (first-order delta
+    /// method, slope taken by central differences).
+    ///
+    /// Returns:
+    ///     One output per stage in order; the last one is the pipeline's answer.
+    pub fn predict(&self, x: f64) -> Vec<StageOutput> {
+        let mut input = Prediction { mean: x, std: 0.0 };
+        let mut outputs = Vec::with_capacity(self.stages.len());
+
+        for (label, stage) in &self.stages {
+            let own = stage.predict(input.mean);
+            let h = 1e-4 * input.mean.abs().max(1.0);
+            let slope = (stage.predict(input.mean + h).mean - stage.predict(input.mean - h).mean) / (2.0 * h);
+            let std = (own.std.powi(2) + (slope * input.std).powi(2)).sqrt();
+
+            input = Prediction { mean: own.mean, std };
+            outputs.push(StageOutput { label: label.clone(), model: stage.name(), prediction: input });
+        }
+
+        outputs
+    }
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    // Ordinary least squares by hand: the prediction at `x` and its variance s²(1 + 1/n + (x - x̄)²/Sxx)
+    fn closed_form(xs: &[f64], ys: &[f64], x: f64) -> (f64, f64) {
+        let n = xs.len() as f64;
+        let (x_mean, y_mean) = (xs.iter().sum::<f64>() / n, ys.iter().sum::<f64>() / n);
+        let sxx: f64 = xs.iter().map(|xi| (xi - x_mean).powi(2)).sum();
+        let slope = xs.iter().zip(ys).map(|(xi, yi)| (xi - x_mean) * (yi - y_mean)).sum::<f64>() / sxx;
+        let intercept = y_mean - slope * x_mean;
+        let s2 = xs.iter().zip(ys).map(|(xi, yi)| (yi - slope * xi - intercept).powi(2)).sum::<f64>() / (n - 2.0);
+        (slope * x + intercept, s2 * (1.0 + 1.0 / n + (x - x_mean).powi(2) / sxx))
+    }
+
+    #[test]
+    fn linear_chain_matches_the_closed_form_variance() {
+        let noise = |i: usize| ((i * 7 % 11) as f64 - 5.0) / 5.0;
+        let years: Vec<f64> = (0..30).map(|i| 1990.0 + i as f64).collect();
+        let emissions: Vec<f64> = years.iter().enumerate().map(|(i, year)| 22.0 + 0.5 * (year - 1990.0) + noise(i)).collect();
+        let co2: Vec<f64> = (0..25).map(|i| 15.0 + i as f64).collect();
+        let anomaly: Vec<f64> = co2.iter().enumerate().map(|(i, e)| -0.3 + 0.03 * e + 0.05 * noise(i + 3)).collect();
+
+        let temperature = LinearModel::fit(&co2, &anomaly).unwrap();
+        let pipeline = Pipeline::new()
+            .then("emissions", LinearModel::fit(&years, &emissions).unwrap())
+            .then("temperature", temperature.clone());
+        let outputs = pipeline.predict(2035.0);
+        assert_eq!(outputs.len(), 2);
+
+        // The first stage sees an exact year, so it carries only its own variance
+        let (emitted, emitted_variance) = closed_form(&years, &emissions, 2035.0);
+        assert!((outputs[0].prediction.mean - emitted).abs() < 1e-9);
+        assert!((outputs[0].prediction.std.powi(2) - emitted_variance).abs() < 1e-9 * emitted_variance);
+
+        // Var[T] = σ²_T(E) + β_T² Var[E], with σ²_T evaluated at the predicted emissions
+        let (warming, own_variance) = closed_form(&co2, &anomaly, emitted);
+        let variance = own_variance + temperature.slope.powi(2) * emitted_variance;
+        assert_eq!(outputs[1].label, "temperature");
+        assert!((outputs[1].prediction.mean - warming).abs() < 1e-9);
+        assert!((outputs[1].prediction.std.powi(2) - variance).abs() < 1e-6 * variance, "{} vs {}", outputs[1].prediction.std.powi(2), variance);
+        assert!(outputs[1].prediction.std > temperature.predict_with_uncertainty(emitted).std);
+    }
+}
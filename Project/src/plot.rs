@@ -0,0 +1,429 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{self, Dataset};
+use crate::pipeline::Stage;
+use crate::regression::{mean_squared_error, split_data, LinearModel, PolynomialModel, Prediction};
+use crate::scaling::Scaling;
+use plotters::coord::Shift;
+use image::{ImageOutputFormat, RgbImage};
+use plotters::prelude::*;
+use std::error::Error;
+use std::io::Cursor;
+use std::path::Path;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const SIZE: (u32, u32) = (1024, 768); // Pixels of a saved chart
+const CURVE_POINTS: usize = 200; // Samples along the fitted curve and its interval
+const FIT_SHARE: f64 = 0.7; // Fraction of the height given to the fit, the rest shows the residuals
+const MARGIN: f64 = 0.05; // Padding around the plotted range, as a fraction of it
+const PATHWAY_SHARE: f64 = 0.4; // Fraction of a scenario chart's height given to the emissions pathway
+pub const Z_95: f64 = 1.96; // Standard deviations covering ~95 % of a normal distribution
+
+//////////////////////////////////////// charts ////////////////////////////////////////
+
+/// Image formats a chart can be rendered to in memory.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum Format {
+    Svg,
+    Png,
+}
+
+impl Format {
+    pub fn extension(&self) -> &'static str {
+        match self {
+            Format::Svg => "svg",
+            Format::Png => "png",
+        }
+    }
+
+    /// The format a file should be written in, from the extension of `path`.
+    pub fn of(path: &str) -> Result<Self, Box<dyn Error>> {
+        match Path::new(path).extension().and_then(|extension| extension.to_str()) {
+            Some("svg") => Ok(Format::Svg),
+            Some("png") => Ok(Format::Png),
+            _ => Err(format!("cannot tell the image format of '{}' (expected .svg or .png)", path).into()),
+        }
+    }
+}
+
+/// The data and labels of a chart of a trained single-input model.
+#[derive(Debug, Clone, Copy)]
+pub struct FitChart<'a> {
+    pub title: &'a str,
+    pub x_label: &'a str,
+    pub y_label: &'a str,
+    pub x: &'a [f64],
+    pub y: &'a [f64],
+    pub train_size: usize, // Rows before this index were used for training, the rest for testing
+    pub z: f64,            // Half-width of the prediction interval in standard deviations (1.96 for ~95 %)
+}
+
+/// Draws a model against its data: the training and test points, the fitted curve with its
+/// prediction interval and the train/test boundary, with the residuals in a panel below.
+///
+/// Generic over the plotters backend, so the same chart can go to a file or into memory.
+///
+/// Args:
+///     root: Area to draw on; it is filled white first.
+///     chart: The data and labels.
+///     model: Any single-input model; its predictions give the curve and the interval.
+pub fn draw<DB, M>(root: &DrawingArea<DB, Shift>, chart: &FitChart, model: &M) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+    M: Stage + ?Sized,
+{
+    if chart.x.len() != chart.y.len() || chart.x.is_empty() {
+        return Err(format!("a chart needs as many x as y values, found {} and {}", chart.x.len(), chart.y.len()).into());
+    }
+    if chart.train_size > chart.x.len() {
+        return Err(format!("training size {} exceeds the {} rows", chart.train_size, chart.x.len()).into());
+    }
+
+    let (x_min, x_max) = bounds(chart.x.iter().copied());
+    let curve: Vec<(f64, f64, f64, f64)> = (0..CURVE_POINTS)
+        .map(|i| {
+            let x = x_min + (x_max - x_min) * i as f64 / (CURVE_POINTS - 1) as f64;
+            let prediction = model.predict(x);
+            let (low, high) = prediction.interval(chart.z);
+            (x, prediction.mean, low, high)
+        })
+        .collect();
+    let residuals: Vec<f64> = chart.x.iter().zip(chart.y).map(|(&x, &y)| y - model.predict(x).mean).collect();
+
+    // The rows are time-ordered, so the boundary sits halfway between the last training and first test x
+    let split = chart.train_size;
+    let boundary = (split > 0 && split < chart.x.len()).then(|| (chart.x[split - 1] + chart.x[split]) / 2.0);
+
+    root.fill(&WHITE)?;
+    let (upper, lower) = root.split_vertically((FIT_SHARE * root.dim_in_pixel().1 as f64) as u32);
+
+    // Fit panel
+    let x_range = padded(x_min, x_max);
+    let y_range = padded_bounds(chart.y.iter().copied().chain(curve.iter().flat_map(|&(_, _, low, high)| [low, high])));
+    let mut fit = ChartBuilder::on(&upper)
+        .caption(chart.title, ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_range.clone(), y_range.clone())?;
+    fit.configure_mesh().x_desc(chart.x_label).y_desc(chart.y_label).draw()?;
+
+    let band: Vec<(f64, f64)> = curve.iter().map(|&(x, _, _, high)| (x, high))
+        .chain(curve.iter().rev().map(|&(x, _, low, _)| (x, low)))
+        .collect();
+    fit.draw_series(std::iter::once(Polygon::new(band, BLUE.mix(0.15))))?
+        .label(format!("prediction interval (±{} σ)", chart.z))
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], BLUE.mix(0.15).filled()));
+    fit.draw_series(LineSeries::new(curve.iter().map(|&(x, mean, _, _)| (x, mean)), BLUE.stroke_width(2)))?
+        .label(model.name())
+        .legend(|(x, y)| PathElement::new([(x, y), (x + 20, y)], BLUE.stroke_width(2)));
+    fit.draw_series(points(&chart.x[..split], &chart.y[..split], BLACK))?
+        .label("training data")
+        .legend(|(x, y)| Circle::new((x + 10, y), 3, BLACK.filled()));
+    fit.draw_series(points(&chart.x[split..], &chart.y[split..], RED))?
+        .label("test data")
+        .legend(|(x, y)| Circle::new((x + 10, y), 3, RED.filled()));
+    if let Some(boundary) = boundary {
+        fit.draw_series(std::iter::once(split_line(boundary, &y_range)))?
+            .label("train/test split")
+            .legend(|(x, y)| PathElement::new([(x + 10, y - 5), (x + 10, y + 5)], GREEN.stroke_width(2)));
+    }
+    fit.configure_series_labels().background_style(WHITE.mix(0.8)).border_style(BLACK).position(SeriesLabelPosition::UpperLeft).draw()?;
+
+    // Residual panel
+    let residual_range = padded_bounds(residuals.iter().copied().chain([0.0]));
+    let mut panel = ChartBuilder::on(&lower)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_range.clone(), residual_range.clone())?;
+    panel.configure_mesh().x_desc(chart.x_label).y_desc("residual").draw()?;
+    panel.draw_series(std::iter::once(PathElement::new([(x_range.start, 0.0), (x_range.end, 0.0)], BLUE)))?;
+    panel.draw_series(points(&chart.x[..split], &residuals[..split], BLACK))?;
+    panel.draw_series(points(&chart.x[split..], &residuals[split..], RED))?;
+    if let Some(boundary) = boundary {
+        panel.draw_series(std::iter::once(split_line(boundary, &residual_range)))?;
+    }
+
+    Ok(())
+}
+
+/// The data of a chart of an emissions pathway and the temperatures it leads to.
+#[derive(Debug, Clone, Copy)]
+pub struct ScenarioChart<'a> {
+    pub title: &'a str,
+    pub years: &'a [i32],
+    pub emissions: &'a [f64],           // GtCO₂ per year
+    pub temperatures: &'a [Prediction], // Anomaly (°C vs 1951–1980) of every year, with its uncertainty
+    pub z: f64,                         // Half-width of the prediction interval in standard deviations
+}
+
+/// Draws a scenario: the emissions pathway on top and the projected temperature anomaly with its
+/// prediction interval below, on a shared year axis.
+pub fn draw_scenario<DB>(root: &DrawingArea<DB, Shift>, chart: &ScenarioChart) -> Result<(), Box<dyn Error>>
+where
+    DB: DrawingBackend,
+    DB::ErrorType: 'static,
+{
+    if chart.years.len() != chart.emissions.len() || chart.years.len() != chart.temperatures.len() || chart.years.is_empty() {
+        return Err(format!(
+            "a scenario chart needs as many years, emissions and temperatures, found {}, {} and {}",
+            chart.years.len(), chart.emissions.len(), chart.temperatures.len()
+        ).into());
+    }
+
+    let years: Vec<f64> = chart.years.iter().map(|&year| year as f64).collect();
+    let intervals: Vec<(f64, f64)> = chart.temperatures.iter().map(|prediction| prediction.interval(chart.z)).collect();
+    let (x_min, x_max) = bounds(years.iter().copied());
+    let x_range = padded(x_min, x_max);
+
+    root.fill(&WHITE)?;
+    let (upper, lower) = root.split_vertically((PATHWAY_SHARE * root.dim_in_pixel().1 as f64) as u32);
+
+    // Pathway panel
+    let emission_range = padded_bounds(chart.emissions.iter().copied().chain([0.0]));
+    let mut pathway = ChartBuilder::on(&upper)
+        .caption(chart.title, ("sans-serif", 24))
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_range.clone(), emission_range)?;
+    pathway.configure_mesh().x_desc("Year").y_desc("Emissions (GtCO₂)").draw()?;
+    pathway.draw_series(std::iter::once(PathElement::new([(x_range.start, 0.0), (x_range.end, 0.0)], BLACK.mix(0.4))))?;
+    pathway.draw_series(LineSeries::new(years.iter().copied().zip(chart.emissions.iter().copied()), RED.stroke_width(2)))?;
+
+    // Temperature panel
+    let temperature_range = padded_bounds(intervals.iter().flat_map(|&(low, high)| [low, high]));
+    let mut temperature = ChartBuilder::on(&lower)
+        .margin(10)
+        .x_label_area_size(40)
+        .y_label_area_size(60)
+        .build_cartesian_2d(x_range, temperature_range)?;
+    temperature.configure_mesh().x_desc("Year").y_desc("Temperature anomaly (°C)").draw()?;
+
+    let band: Vec<(f64, f64)> = years.iter().zip(&intervals).map(|(&x, &(_, high))| (x, high))
+        .chain(years.iter().zip(&intervals).rev().map(|(&x, &(low, _))| (x, low)))
+        .collect();
+    temperature.draw_series(std::iter::once(Polygon::new(band, BLUE.mix(0.15))))?
+        .label(format!("prediction interval (±{} σ)", chart.z))
+        .legend(|(x, y)| Rectangle::new([(x, y - 5), (x + 20, y + 5)], BLUE.mix(0.15).filled()));
+    temperature.draw_series(LineSeries::new(years.iter().copied().zip(chart.temperatures.iter().map(|prediction| prediction.mean)), BLUE.stroke_width(2)))?
+        .label("projected anomaly")
+        .legend(|(x, y)| PathElement::new([(x, y), (x + 20, y)], BLUE.stroke_width(2)));
+    temperature.configure_series_labels().background_style(WHITE.mix(0.8)).border_style(BLACK).position(SeriesLabelPosition::UpperLeft).draw()?;
+
+    Ok(())
+}
+
+/// Renders a chart into an SVG document in memory, e.g. for a web server to send.
+///
+/// Args:
+///     draw: Draws on the SIZE area it is given, such as `|root| draw(root, &chart, &model)`.
+pub fn to_svg<F>(draw: F) -> Result<String, Box<dyn Error>>
+where
+    F: FnOnce(&DrawingArea<SVGBackend, Shift>) -> Result<(), Box<dyn Error>>,
+{
+    let mut svg = String::new();
+    {
+        let root = SVGBackend::with_string(&mut svg, SIZE).into_drawing_area();
+        draw(&root)?;
+        root.present()?;
+    }
+    Ok(svg)
+}
+
+/// Renders an RGB chart into a PNG image in memory, like `to_svg`.
+pub fn to_png<F>(draw: F) -> Result<Vec<u8>, Box<dyn Error>>
+where
+    F: FnOnce(&DrawingArea<BitMapBackend, Shift>) -> Result<(), Box<dyn Error>>,
+{
+    let mut pixels = vec![0; SIZE.0 as usize * SIZE.1 as usize * 3];
+    {
+        let root = BitMapBackend::with_buffer(&mut pixels, SIZE).into_drawing_area();
+        draw(&root)?;
+        root.present()?;
+    }
+    let image = RgbImage::from_raw(SIZE.0, SIZE.1, pixels).ok_or("the chart buffer does not match its size")?;
+    let mut png = Vec::new();
+    image.write_to(&mut Cursor::new(&mut png), ImageOutputFormat::Png)?;
+    Ok(png)
+}
+
+/// Renders a chart of a trained model (see `draw`) in memory.
+pub fn render_fit<M: Stage + ?Sized>(format: Format, chart: &FitChart, model: &M) -> Result<Vec<u8>, Box<dyn Error>> {
+    match format {
+        Format::Svg => Ok(to_svg(|root| draw(root, chart, model))?.into_bytes()),
+        Format::Png => to_png(|root| draw(root, chart, model)),
+    }
+}
+
+/// Renders a scenario chart (see `draw_scenario`) in memory.
+pub fn render_scenario(format: Format, chart: &ScenarioChart) -> Result<Vec<u8>, Box<dyn Error>> {
+    match format {
+        Format::Svg => Ok(to_svg(|root| draw_scenario(root, chart))?.into_bytes()),
+        Format::Png => to_png(|root| draw_scenario(root, chart)),
+    }
+}
+
+/// Renders a chart to a file, as SVG or PNG depending on the extension of `path`.
+pub fn save<M: Stage + ?Sized>(path: &str, chart: &FitChart, model: &M) -> Result<(), Box<dyn Error>> {
+    match Format::of(path)? {
+        Format::Svg => {
+            let root = SVGBackend::new(path, SIZE).into_drawing_area();
+            draw(&root, chart, model)?;
+            root.present()?;
+        }
+        Format::Png => {
+            let root = BitMapBackend::new(path, SIZE).into_drawing_area();
+            draw(&root, chart, model)?;
+            root.present()?;
+        }
+    }
+    Ok(())
+}
+
+//////////////////////////////////////// fitted charts ////////////////////////////////////////
+
+/// A rendered fit chart and how its model did on the held-out rows.
+#[derive(Debug, Clone)]
+pub struct RenderedFit {
+    pub image: Vec<u8>,
+    pub model: String,    // Name of the fitted model
+    pub test_mse: f64,    // Mean squared error on the held-out rows; NaN when there are none
+    pub test_rows: usize,
+}
+
+/// Axis label of a column of the emissions/temperature dataset, with its unit when the schema has one.
+pub fn label(name: &str) -> String {
+    match dataset::emission_temperature().column(name).and_then(|column| column.unit.clone()) {
+        Some(unit) => format!("{} ({})", name, unit),
+        None => name.to_string(),
+    }
+}
+
+/// Fits a model of one column of the emissions/temperature dataset on another and renders it
+/// (see `draw`) against every row, with its 95 % prediction interval.
+///
+/// Args:
+///     path: The emissions/temperature CSV file.
+///     degree: 1 fits a line, higher degrees a standardized polynomial.
+///     x_column, y_column: The input and target columns, e.g. Emissions and Lowess.
+///     test_ratio: Most recent share of rows held out from the fit.
+///     format: Image format of the chart.
+///
+/// Returns:
+///     The chart with the name of the model and its error on the held-out rows.
+pub fn fit_chart(path: &str, degree: usize, x_column: &str, y_column: &str, test_ratio: f64, format: Format) -> Result<RenderedFit, Box<dyn Error>> {
+    let dataset = Dataset::load(path, dataset::emission_temperature())?;
+    let x = dataset.floats(x_column)?;
+    let y = dataset.floats(y_column)?;
+
+    let ((x_train, y_train), (x_test, y_test)) = split_data(&x, &y, test_ratio);
+    let model: Box<dyn Stage> = if degree == 1 {
+        Box::new(LinearModel::fit(&x_train, &y_train)?)
+    } else {
+        Box::new(PolynomialModel::fit_scaled(&x_train, &y_train, degree, &vec![1.0; x_train.len()], Scaling::standard())?)
+    };
+    let predictions: Vec<f64> = x_test.iter().map(|&x| model.predict(x).mean).collect();
+
+    let (x_label, y_label) = (label(x_column), label(y_column));
+    let title = format!("{} vs {}, {}", y_column, x_column, model.name());
+    let chart = FitChart { title: &title, x_label: &x_label, y_label: &y_label, x: &x, y: &y, train_size: x_train.len(), z: Z_95 };
+    Ok(RenderedFit {
+        image: render_fit(format, &chart, model.as_ref())?,
+        model: model.name(),
+        test_mse: mean_squared_error(&predictions, &y_test),
+        test_rows: y_test.len(),
+    })
+}
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+fn points<'a>(x: &'a [f64], y: &'a [f64], color: RGBColor) -> impl Iterator<Item = Circle<(f64, f64), i32>> + 'a {
+    x.iter().zip(y).map(move |(&x, &y)| Circle::new((x, y), 3, color.filled()))
+}
+
+fn split_line(x: f64, y_range: &std::ops::Range<f64>) -> PathElement<(f64, f64)> {
+    PathElement::new([(x, y_range.start), (x, y_range.end)], GREEN.stroke_width(2))
+}
+
+fn bounds(values: impl Iterator<Item = f64>) -> (f64, f64) {
+    values.filter(|value| value.is_finite()).fold((f64::INFINITY, f64::NEG_INFINITY), |(min, max), value| (min.min(value), max.max(value)))
+}
+
+fn padded_bounds(values: impl Iterator<Item = f64>) -> std::ops::Range<f64> {
+    let (min, max) = bounds(values);
+    padded(min, max)
+}
+
+// Range with MARGIN of padding on each side; a single value gets a unit-wide range
+fn padded(min: f64, max: f64) -> std::ops::Range<f64> {
+    let pad = if max > min { (max - min) * MARGIN } else { 0.5 };
+    min - pad..max + pad
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regression::LinearModel;
+
+    fn chart<'a>(x: &'a [f64], y: &'a [f64]) -> FitChart<'a> {
+        FitChart { title: "Temperature vs emissions", x_label: "Emissions", y_label: "Anomaly", x, y, train_size: 8, z: 1.96 }
+    }
+
+    // Contents of the <text> elements
+    fn texts(svg: &str) -> Vec<&str> {
+        svg.split("<text").skip(1)
+            .filter_map(|element| element.split_once('>').and_then(|(_, rest)| rest.split_once("</text>")).map(|(text, _)| text.trim()))
+            .collect()
+    }
+
+    #[test]
+    fn render_fit_draws_an_svg_with_its_labels_and_legend() {
+        let x: Vec<f64> = (0..10).map(|i| 10.0 + 3.0 * i as f64).collect();
+        let y: Vec<f64> = x.iter().enumerate().map(|(i, xi)| 0.02 * xi - 0.3 + 0.01 * (i % 3) as f64).collect();
+        let model = LinearModel::fit(&x[..8], &y[..8]).unwrap();
+
+        let svg = String::from_utf8(render_fit(Format::Svg, &chart(&x, &y), &model).unwrap()).unwrap();
+        assert!(svg.starts_with("<svg") && svg.trim_end().ends_with("</svg>"));
+        assert!(svg.contains(&format!("width=\"{}\" height=\"{}\"", SIZE.0, SIZE.1)));
+        let texts = texts(&svg);
+        for text in ["Temperature vs emissions", "Emissions", "Anomaly", "residual", "linear", "training data", "test data", "train/test split", "prediction interval (±1.96 σ)"] {
+            assert!(texts.contains(&text), "no '{}' in {:?}", text, texts);
+        }
+
+    }
+
+    #[test]
+    fn mismatched_data_is_rejected() {
+        let (x, y) = ([1.0, 2.0, 3.0], [1.0, 2.0, 3.0]);
+        let model = LinearModel::fit(&x, &y).unwrap();
+        assert!(render_fit(Format::Svg, &chart(&x, &y[..2]), &model).is_err());
+        assert!(render_fit(Format::Svg, &chart(&x, &y), &model).unwrap_err().to_string().contains("training size 8"));
+
+        let scenario = ScenarioChart { title: "SSP2-4.5", years: &[2030, 2040], emissions: &[40.0], temperatures: &[], z: 1.96 };
+        assert!(render_scenario(Format::Svg, &scenario).is_err());
+    }
+
+    #[test]
+    fn fit_chart_fits_the_columns_and_holds_out_the_latest_rows() {
+        let path = crate::merge::OUTPUT;
+        let rows = Dataset::load(path, dataset::emission_temperature()).unwrap().floats("Year").unwrap().len();
+        let fit = fit_chart(path, 3, "Year", "Emissions", 0.2, Format::Svg).unwrap();
+
+        assert_eq!(fit.test_rows, (rows as f64 * 0.2).round() as usize);
+        assert!(fit.model.contains("degree 3") && fit.test_mse.is_finite());
+        let svg = String::from_utf8(fit.image).unwrap();
+        for text in ["Year", "Emissions (GtCO₂)", "Emissions vs Year, polynomial (degree 3)"] {
+            assert!(texts(&svg).contains(&text), "no '{}' in {:?}", text, texts(&svg));
+        }
+
+        assert!(fit_chart(path, 1, "Year", "Rainfall", 0.2, Format::Svg).is_err());
+        assert_eq!(Format::of("fit.png").unwrap(), Format::Png);
+        assert!(Format::of("fit.jpg").is_err());
+    }
+}
@@ -0,0 +1,126 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use climate_predict::dataset::{self, Dataset};
+use climate_predict::footprint;
+use climate_predict::persist::{self, Artifact, SavedModel};
+use climate_predict::pipeline::Stage;
+use climate_predict::plot::{self, FitChart, Format};
+use climate_predict::regression::Prediction;
+use std::env;
+use std::error::Error;
+use std::fs;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const DATA: &str = "./data/emission_temp_data.csv";
+const OUTPUT: &str = "./fit.svg";
+const TEST_RATIO: f64 = 0.2; // Percentage of data to be used for testing
+const USAGE: &str = "usage: plot [--x Year|Emissions|Lowess] [--y Year|Emissions|Lowess] [--degree N] [--test-ratio R] [--model ARTIFACT.json] [OUTPUT.svg|OUTPUT.png]";
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+/// Command-line options.
+struct Options {
+    x: String,               // Input column
+    y: String,               // Target column
+    degree: Option<usize>,   // 1 (default) fits a line, higher degrees a standardized polynomial
+    test_ratio: Option<f64>, // Most recent share of rows held out for testing, TEST_RATIO by default
+    model: Option<String>,   // Saved artifact to draw instead of fitting a model
+    output: String,
+}
+
+fn parse_args() -> Result<Options, Box<dyn Error>> {
+    let mut options = Options { x: "Emissions".to_string(), y: "Lowess".to_string(), degree: None, test_ratio: None, model: None, output: OUTPUT.to_string() };
+    let mut args = env::args().skip(1);
+    while let Some(arg) = args.next() {
+        match arg.as_str() {
+            "--x" => options.x = args.next().ok_or(USAGE)?,
+            "--y" => options.y = args.next().ok_or(USAGE)?,
+            "--degree" => options.degree = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--test-ratio" => options.test_ratio = Some(args.next().ok_or(USAGE)?.parse()?),
+            "--model" => options.model = Some(args.next().ok_or(USAGE)?),
+            flag if flag.starts_with("--") => return Err(format!("unexpected option '{}'\n{}", flag, USAGE).into()),
+            path => options.output = path.to_string(),
+        }
+    }
+    if options.degree == Some(0) || options.test_ratio.is_some_and(|ratio| !(0.0..1.0).contains(&ratio)) {
+        return Err(USAGE.into());
+    }
+    // A saved model was trained on every row of its data, with its own settings
+    if options.model.is_some() && (options.degree.is_some() || options.test_ratio.is_some()) {
+        return Err(format!("--degree and --test-ratio do not apply to a saved model\n{}", USAGE).into());
+    }
+    Ok(options)
+}
+
+/// A footprint model seen through its predictions: the y = x line of a perfect fit, with the
+/// model's training RMSE as the spread of an observation.
+struct Predicted {
+    name: String,
+    rmse: f64,
+}
+
+impl Stage for Predicted {
+    fn name(&self) -> String {
+        self.name.clone()
+    }
+
+    fn predict(&self, x: f64) -> Prediction {
+        Prediction { mean: x, std: self.rmse }
+    }
+}
+
+/// Draws a saved artifact against the data it was trained on: a climate model's emissions or
+/// temperature stage, picked by the columns, or a footprint model's actual against predicted emissions.
+fn draw_artifact(path: &str, options: &Options) -> Result<(), Box<dyn Error>> {
+    let artifact: Artifact = persist::load(path)?;
+    let source = &artifact.metadata.source;
+    let title = format!("{} v{}", artifact.metadata.name, artifact.metadata.version);
+    match &artifact.model {
+        SavedModel::Climate(climate) => {
+            let stage: &dyn Stage = match (options.x.as_str(), options.y.as_str()) {
+                ("Year", "Emissions") => &climate.emissions,
+                ("Emissions", "Lowess") => &climate.temperature,
+                (x, y) => return Err(format!("a climate model predicts Emissions from Year and Lowess from Emissions, not {} from {}", y, x).into()),
+            };
+            let dataset = Dataset::load(source, dataset::emission_temperature())?;
+            let (x, y) = (dataset.floats(&options.x)?, dataset.floats(&options.y)?);
+            let (x_label, y_label) = (plot::label(&options.x), plot::label(&options.y));
+            let title = format!("{}: {} vs {}, {}", title, options.y, options.x, stage.name());
+            let chart = FitChart { title: &title, x_label: &x_label, y_label: &y_label, x: &x, y: &y, train_size: x.len(), z: plot::Z_95 };
+            plot::save(&options.output, &chart, stage)
+        }
+        SavedModel::Footprint(model) => {
+            let records = footprint::load_records(source)?;
+            let rows = records.iter().map(|record| record.features()).collect::<Result<Vec<_>, _>>()?;
+            let predicted = model.predict_rows(&rows)?;
+            let actual: Vec<f64> = records.iter().map(|record| record.carbon_emission).collect();
+            let (rmse, _) = model.evaluate(&records)?;
+            let stage = Predicted { name: format!("{} model (y = x)", model.name()), rmse };
+            let title = format!("{}: actual vs predicted emissions, {}", title, stage.name);
+            let chart = FitChart {
+                title: &title, x_label: "Predicted (kg CO₂)", y_label: "Actual (kg CO₂)", x: &predicted, y: &actual, train_size: predicted.len(), z: plot::Z_95,
+            };
+            plot::save(&options.output, &chart, &stage)
+        }
+    }
+}
+
+//////////////////////////////////////// main ////////////////////////////////////////
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let options = parse_args()?;
+    if let Some(model) = &options.model {
+        draw_artifact(model, &options)?;
+    } else {
+        let (degree, test_ratio) = (options.degree.unwrap_or(1), options.test_ratio.unwrap_or(TEST_RATIO));
+        let fit = plot::fit_chart(DATA, degree, &options.x, &options.y, test_ratio, Format::of(&options.output)?)?;
+        println!("{}: MSE {:.4} on {} test rows", fit.model, fit.test_mse, fit.test_rows);
+        fs::write(&options.output, fit.image)?;
+    }
+    println!("Chart written to {}", options.output);
+
+    Ok(())
+}
+
+// cargo run --bin plot
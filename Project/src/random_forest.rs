@@ -1,84 +1,60 @@
-use smartcore::ensemble::random_forest_regressor::{RandomForestRegressor, RandomForestRegressorParameters};
-use smartcore::model_selection::train_test_split;
+use climate_predict::counterfactual;
+use climate_predict::footprint::{self, FootprintModel};
+use smartcore::ensemble::random_forest_regressor::RandomForestRegressorParameters;
 use smartcore::metrics::mean_squared_error;
-use smartcore::linalg::basic::matrix::DenseMatrix;
-use csv::ReaderBuilder;
-use serde::Deserialize;
+use rand::rngs::StdRng;
+use rand::seq::SliceRandom;
+use rand::SeedableRng;
+
+const TEST_RATIO: f64 = 0.2; // Percentage of data to be used for testing
+const TOP_CONTRIBUTIONS: usize = 5; // Number of answers shown when explaining a prediction
+const TARGET_SAVINGS: f64 = 200.0; // Monthly kg CO2 the "what-if" recommendations should save
 
-#[derive(Debug, Deserialize)]
-struct Record {
-    #[serde(rename = "Body Type")]
-    body_type: String,
-    #[serde(rename = "Sex")]
-    sex: String,
-    #[serde(rename = "Diet")]
-    diet: String,
-    #[serde(rename = "How Often Shower")]
-    how_often_shower: String,
-    #[serde(rename = "Heating Energy Source")]
-    heating_energy_source: String,
-    #[serde(rename = "Transport")]
-    transport: String,
-    #[serde(rename = "Social Activity")]
-    social_activity: String,
-    #[serde(rename = "Monthly Grocery Bill")]
-    monthly_grocery_bill: f64,
-    #[serde(rename = "Frequency of Traveling by Air")]
-    frequency_of_traveling_by_air: String,
-    #[serde(rename = "Vehicle Monthly Distance Km")]
-    vehicle_monthly_distance_km: f64,
-    #[serde(rename = "Waste Bag Weekly Count")]
-    waste_bag_weekly_count: i32,
-    #[serde(rename = "How Long TV PC Daily Hour")]
-    how_long_tv_pc_daily_hour: i32,
-    #[serde(rename = "How Many New Clothes Monthly")]
-    how_many_new_clothes_monthly: i32,
-    #[serde(rename = "How Long Internet Daily Hour")]
-    how_long_internet_daily_hour: i32,
-    #[serde(rename = "Energy efficiency")]
-    energy_efficiency: String,
-    #[serde(rename = "CarbonEmission")]
-    carbon_emission: f64,
-}
 fn main() -> Result<(), Box<dyn std::error::Error>> {
     // Load data from a CSV file
-    let mut rdr = ReaderBuilder::new()
-        .has_headers(true)  // Ensure headers are expected
-        .from_path(".\\data\\Carbon Emission.csv")?;
-    
-    let mut dataset: Vec<Record> = Vec::new();
-    
-    for result in rdr.deserialize() {
-        let record: Record = result?;
-        dataset.push(record);
-    }
-    
+    let mut dataset = footprint::load_records(footprint::DATA)?;
+
     // Optionally, print some records to check if they are loaded correctly
     for record in dataset.iter().take(5) {
         println!("{:?}", record);
     }
 
-    // Assuming 'dataset' is now a Vec of numeric features including the target variable
-    let (x, y): (Vec<Vec<f64>>, Vec<f64>) = dataset.iter().map(|r| (vec![/* feature vector */], r.carbon_emission)).unzip();
-
-    // Convert data into a Matrix for SmartCore
-    let x = DenseMatrix::from_2d_vec(&x);
-
     // Split the dataset into training and testing datasets
-    let (x_train, x_test, y_train, y_test) = train_test_split(&x, &y, 0.2, true, Some(42)); // added seed for reproducibility
+    dataset.shuffle(&mut StdRng::seed_from_u64(42)); // added seed for reproducibility
+    let test_size = (dataset.len() as f64 * TEST_RATIO).round() as usize;
+    let (test, train) = dataset.split_at(test_size);
 
     // Define the model with default parameters
-    let rf_params = RandomForestRegressorParameters::default();
-    let rf = RandomForestRegressor::fit(&x_train, &y_train, rf_params)?;
+    let rf = FootprintModel::fit_random_forest(train, RandomForestRegressorParameters::default())?;
 
     // Evaluate the model
-    let preds = rf.predict(&x_test)?;
+    let y_test: Vec<f64> = test.iter().map(|r| r.carbon_emission).collect();
+    let preds = test.iter().map(|r| rf.predict(r)).collect::<Result<Vec<f64>, _>>()?;
     let mse = mean_squared_error(&y_test, &preds);
     let rmse = mse.sqrt(); // Calculate RMSE for better interpretation
 
     println!("Mean Squared Error: {}", mse);
     println!("Root Mean Squared Error: {}", rmse);
 
+    // Explain which answers drove the prediction for the first test record
+    let explanation = rf.explain(&test[0])?;
+    println!("Prediction: {:.0} kg CO2 (average person: {:.0} kg CO2)", explanation.prediction, explanation.baseline);
+    for contribution in explanation.ranked().into_iter().take(TOP_CONTRIBUTIONS) {
+        println!("  {:<32} {:+.0} kg CO2", contribution.feature, contribution.value);
+    }
+
+    // Suggest the fewest lifestyle changes that would save at least TARGET_SAVINGS
+    let recommendations = counterfactual::recommend(&rf, train, &test[0], TARGET_SAVINGS)?;
+    if recommendations.is_empty() {
+        println!("No combination of actionable changes saves {:.0} kg CO2", TARGET_SAVINGS);
+    }
+    for recommendation in recommendations {
+        let changes: Vec<String> = recommendation.changes.iter()
+            .map(|c| format!("{}: {} -> {}", c.question, c.from, c.to))
+            .collect();
+        println!("  save {:.0} kg CO2 with [{}]", recommendation.savings, changes.join("; "));
+    }
+
     Ok(())
 }
 
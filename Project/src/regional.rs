@@ -0,0 +1,405 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{Column, Dataset, Schema};
+use crate::impute::{self, Preprocessor, Report, Strategy};
+use crate::regression::{inverse_variance_weights, LinearModel, PolynomialModel};
+use crate::sources::AnnualSeries;
+use crate::timeseries::{Aggregate, Period, Resolution, TimeSeries, MONTHS};
+use std::collections::{BTreeMap, BTreeSet};
+use std::error::Error;
+use std::fmt;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const CITY_TEMPERATURES: &str = "./data/GlobalLandTemperaturesByCity.csv";
+
+//////////////////////////////////////// coordinates ////////////////////////////////////////
+
+/// A location in decimal degrees, north and east positive.
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Coordinates {
+    pub latitude: f64,
+    pub longitude: f64,
+}
+
+impl Coordinates {
+    /// Parses Berkeley Earth coordinates such as `42.59N` and `87.27W`.
+    pub fn parse(latitude: &str, longitude: &str) -> Result<Self, String> {
+        Ok(Self {
+            latitude: parse_degrees(latitude, 'N', 'S', 90.0)?,
+            longitude: parse_degrees(longitude, 'E', 'W', 180.0)?,
+        })
+    }
+}
+
+impl fmt::Display for Coordinates {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let north_south = if self.latitude < 0.0 { 'S' } else { 'N' };
+        let east_west = if self.longitude < 0.0 { 'W' } else { 'E' };
+        write!(f, "{:.2}{} {:.2}{}", self.latitude.abs(), north_south, self.longitude.abs(), east_west)
+    }
+}
+
+// A number of degrees followed by the hemisphere letter; the negative hemisphere flips the sign
+fn parse_degrees(text: &str, positive: char, negative: char, limit: f64) -> Result<f64, String> {
+    let text = text.trim();
+    let invalid = || format!("'{}' is not a coordinate like 42.59{}", text, positive);
+    let hemisphere = text.chars().last().ok_or_else(invalid)?.to_ascii_uppercase();
+    let sign = match hemisphere {
+        h if h == positive => 1.0,
+        h if h == negative => -1.0,
+        _ => return Err(invalid()),
+    };
+    let degrees: f64 = text[..text.len() - 1].trim().parse().map_err(|_| invalid())?;
+    if !(0.0..=limit).contains(&degrees) {
+        return Err(invalid());
+    }
+    Ok(sign * degrees)
+}
+
+//////////////////////////////////////// baselines ////////////////////////////////////////
+
+/// Years whose mean temperature anomalies are measured against, inclusive.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Baseline {
+    pub start: i32,
+    pub end: i32,
+}
+
+impl Default for Baseline {
+    /// 1951–1980, the GISTEMP baseline, so city anomalies line up with the global series.
+    fn default() -> Self {
+        Self { start: 1951, end: 1980 }
+    }
+}
+
+impl Baseline {
+    pub fn contains(&self, year: i32) -> bool {
+        (self.start..=self.end).contains(&year)
+    }
+}
+
+//////////////////////////////////////// cities ////////////////////////////////////////
+
+/// Monthly temperature record of one city.
+#[derive(Debug, Clone)]
+pub struct City {
+    pub name: String,
+    pub country: String,
+    pub coordinates: Coordinates,
+    pub temperatures: TimeSeries, // °C, months with no measurement left out
+    pub uncertainty: TimeSeries,  // °C, 95 % confidence half-width of each measured month that has one published (see imputed)
+}
+
+/// Schema of the Berkeley Earth `GlobalLandTemperaturesByCity.csv` export.
+pub fn city_schema() -> Schema {
+    Schema::new(vec![
+        Column::text("dt"),
+        Column::float("AverageTemperature").unit("°C").optional(),
+        Column::float("AverageTemperatureUncertainty").unit("°C").optional(),
+        Column::text("City"),
+        Column::text("Country"),
+        Column::text("Latitude"),
+        Column::text("Longitude"),
+    ])
+}
+
+/// Loads every city in a Berkeley Earth city file, in file order.
+///
+/// Months without a temperature (common in the 18th century) are skipped rather than rejected;
+/// aggregating to years only uses complete years, so the gaps do not bias annual means.
+pub fn load_cities(path: &str) -> Result<Vec<City>, Box<dyn Error>> {
+    let dataset = Dataset::load(path, city_schema())?;
+    let keys = dataset.texts("dt")?;
+    let temperatures = dataset.optional_floats("AverageTemperature")?;
+    let uncertainties = dataset.optional_floats("AverageTemperatureUncertainty")?;
+    let (names, countries) = (dataset.texts("City")?, dataset.texts("Country")?);
+    let (latitudes, longitudes) = (dataset.texts("Latitude")?, dataset.texts("Longitude")?);
+
+    // Rows per (city, country), remembering the order cities first appear in
+    let mut order = Vec::new();
+    let mut rows: BTreeMap<(&str, &str), Vec<usize>> = BTreeMap::new();
+    for row in 0..dataset.len() {
+        let key = (names[row], countries[row]);
+        rows.entry(key).or_insert_with(|| {
+            order.push(key);
+            Vec::new()
+        }).push(row);
+    }
+
+    let at_line = |row: usize, message: String| format!("{}, line {}: {}", path, dataset.line(row), message);
+    order.into_iter().map(|key| {
+        let rows = &rows[&key];
+        let first = rows[0];
+        let coordinates = Coordinates::parse(latitudes[first], longitudes[first]).map_err(|message| at_line(first, message))?;
+
+        let mut monthly = Vec::new();
+        let mut uncertainty = Vec::new();
+        for &row in rows {
+            let period = Period::parse(keys[row]).map_err(|message| at_line(row, message))?;
+            if let Some(temperature) = temperatures[row] {
+                monthly.push((period, temperature));
+                if let Some(sigma) = uncertainties[row] {
+                    uncertainty.push((period, sigma));
+                }
+            }
+        }
+
+        let label = format!("{}, {}", key.0, key.1);
+        Ok(City {
+            name: key.0.to_string(),
+            country: key.1.to_string(),
+            coordinates,
+            temperatures: TimeSeries::new(&label, monthly)?,
+            uncertainty: TimeSeries::new(&format!("{} uncertainty", label), uncertainty)?,
+        })
+    }).collect()
+}
+
+impl City {
+    /// A copy with the missing months filled in by `strategy`.
+    ///
+    /// An imputed month was never measured, so it is not given an interpolated uncertainty: its σ is
+    /// the spread of the city's measured temperatures in that calendar month (see `calendar_sigmas`),
+    /// which keeps `--weighted` fits from trusting it as much as a real measurement.
+    ///
+    /// Returns:
+    ///     The filled city and a report of the months that were missing, imputed and left out.
+    pub fn imputed(&self, strategy: Strategy) -> Result<(City, Report), Box<dyn Error>> {
+        let (periods, values) = impute::with_gaps(&self.temperatures);
+        let filled = Preprocessor::new("dt").column("AverageTemperature", strategy).apply_to(periods, vec![values])?;
+        let temperatures = TimeSeries::new(&self.temperatures.name, filled.periods.iter().copied().zip(filled.column("AverageTemperature")?.iter().copied()).collect())?;
+
+        let sigmas = self.calendar_sigmas();
+        let measured_periods: BTreeSet<Period> = self.temperatures.points.iter().map(|&(period, _)| period).collect();
+        let mut uncertainty = self.uncertainty.points.clone();
+        uncertainty.extend(temperatures.points.iter()
+            .filter(|(period, _)| !measured_periods.contains(period))
+            .map(|&(period, _)| (period, sigmas(period))));
+        let uncertainty = TimeSeries::new(&self.uncertainty.name, uncertainty)?;
+
+        Ok((City { temperatures, uncertainty, ..self.clone() }, filled.report))
+    }
+
+    /// σ of an imputed month: the standard deviation of the measured temperatures in its calendar
+    /// month, i.e. of that month's anomalies, so the seasonal cycle does not inflate it.
+    ///
+    /// A calendar month measured fewer than twice takes the largest σ of the other months, and a
+    /// series with no calendar month measured twice the σ of all its months.
+    fn calendar_sigmas(&self) -> impl Fn(Period) -> f64 {
+        let std = |values: &[f64]| {
+            let mean = values.iter().sum::<f64>() / values.len().max(1) as f64;
+            (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len().max(1) as f64).sqrt()
+        };
+        let mut by_month: BTreeMap<u32, Vec<f64>> = BTreeMap::new();
+        for &(period, value) in &self.temperatures.points {
+            if let Period::Month(_, month) = period {
+                by_month.entry(month).or_default().push(value);
+            }
+        }
+        let sigmas: BTreeMap<u32, f64> = by_month.iter().filter(|(_, values)| values.len() > 1).map(|(&month, values)| (month, std(values))).collect();
+        let measured: Vec<f64> = self.temperatures.points.iter().map(|&(_, value)| value).collect();
+        let fallback = sigmas.values().copied().reduce(f64::max).unwrap_or_else(|| std(&measured));
+
+        move |period| match period {
+            Period::Month(_, month) => sigmas.get(&month).copied().unwrap_or(fallback),
+            Period::Year(_) => fallback,
+        }
+    }
+
+    /// Annual mean temperature minus its mean over the baseline years, for every complete year.
+    ///
+    /// Returns:
+    ///     Annual anomalies (°C), or an error if no complete year falls inside the baseline.
+    pub fn annual_anomalies(&self, baseline: Baseline) -> Result<TimeSeries, Box<dyn Error>> {
+        let annual = self.temperatures.resample(Resolution::Annual, Aggregate::Mean)?;
+        let reference: Vec<f64> = annual.points.iter()
+            .filter(|(period, _)| baseline.contains(period.year()))
+            .map(|&(_, value)| value)
+            .collect();
+        if reference.is_empty() {
+            return Err(format!("{} has no complete year in the {}–{} baseline", self.name, baseline.start, baseline.end).into());
+        }
+        let reference = reference.iter().sum::<f64>() / reference.len() as f64;
+
+        let points = annual.points.iter().map(|&(period, value)| (period, value - reference)).collect();
+        TimeSeries::new(&format!("{} anomaly", self.temperatures.name), points)
+    }
+
+    /// Uncertainty of each complete year's mean, assuming the monthly errors are independent: √(Σσ²)/12.
+    ///
+    /// Months without a published uncertainty are taken to be as uncertain as the year's other months
+    /// on average; years with none published are left out rather than given a σ of zero.
+    pub fn annual_uncertainty(&self) -> Result<TimeSeries, Box<dyn Error>> {
+        let mut variances: BTreeMap<i32, Vec<f64>> = BTreeMap::new();
+        for &(period, sigma) in &self.uncertainty.points {
+            variances.entry(period.year()).or_default().push(sigma * sigma);
+        }
+        let complete = self.temperatures.resample(Resolution::Annual, Aggregate::Mean)?;
+        let points = complete.points.iter()
+            .filter_map(|(period, _)| variances.get(&period.year()).map(|variances| (*period, variances)))
+            .map(|(period, variances)| {
+                let mean = variances.iter().sum::<f64>() / variances.len() as f64;
+                (period, (MONTHS as f64 * mean).sqrt() / MONTHS as f64)
+            })
+            .collect();
+        TimeSeries::new(&self.uncertainty.name, points)
+    }
+
+    /// Pairs the city's annual anomalies (and their uncertainties) with global emissions for the years both cover.
+    pub fn samples(&self, emissions: &AnnualSeries, baseline: Baseline) -> Result<RegionalSamples, Box<dyn Error>> {
+        let emissions: BTreeMap<i32, f64> = emissions.iter().copied().collect();
+        let uncertainty: BTreeMap<i32, f64> = self.annual_uncertainty()?.annual_series()?.into_iter().collect();
+        let mut samples = RegionalSamples::default();
+        for (year, anomaly) in self.annual_anomalies(baseline)?.annual_series()? {
+            if let Some(&level) = emissions.get(&year) {
+                samples.years.push(year);
+                samples.emissions.push(level);
+                samples.anomalies.push(anomaly);
+                samples.uncertainties.push(uncertainty.get(&year).copied());
+            }
+        }
+        Ok(samples)
+    }
+
+    /// Fits the global emission→temperature models to this city's anomalies.
+    ///
+    /// Args:
+    ///     emissions: Global annual emissions (GtCO₂).
+    ///     baseline: Reference period for the anomalies.
+    ///     degree: Degree of the polynomial model.
+    ///     weighted: Weight each year by the inverse variance of its published uncertainty, leaving out
+    ///         years without one.
+    ///
+    /// Returns:
+    ///     Linear and polynomial fits of anomaly against emissions.
+    pub fn fit(&self, emissions: &AnnualSeries, baseline: Baseline, degree: usize, weighted: bool) -> Result<CityModels, Box<dyn Error>> {
+        let mut samples = self.samples(emissions, baseline)?;
+        let weights = if weighted {
+            samples = samples.with_uncertainty();
+            inverse_variance_weights(&samples.uncertainties.iter().flatten().copied().collect::<Vec<f64>>())?
+        } else {
+            vec![1.0; samples.years.len()]
+        };
+        Ok(CityModels {
+            linear: LinearModel::fit_weighted(&samples.emissions, &samples.anomalies, &weights)?,
+            polynomial: PolynomialModel::fit_weighted(&samples.emissions, &samples.anomalies, degree, &weights)?,
+            samples,
+        })
+    }
+}
+
+/// Years with both a city anomaly and a global emissions value.
+#[derive(Debug, Clone, Default)]
+pub struct RegionalSamples {
+    pub years: Vec<i32>,
+    pub emissions: Vec<f64>,
+    pub anomalies: Vec<f64>,
+    pub uncertainties: Vec<Option<f64>>, // Uncertainty of each annual anomaly, see City::annual_uncertainty
+}
+
+impl RegionalSamples {
+    /// Only the years with a published uncertainty.
+    pub fn with_uncertainty(&self) -> Self {
+        let mut kept = Self::default();
+        for (i, sigma) in self.uncertainties.iter().enumerate().filter(|(_, sigma)| sigma.is_some()) {
+            kept.years.push(self.years[i]);
+            kept.emissions.push(self.emissions[i]);
+            kept.anomalies.push(self.anomalies[i]);
+            kept.uncertainties.push(*sigma);
+        }
+        kept
+    }
+}
+
+/// Emission→temperature models fitted to one city.
+#[derive(Debug, Clone)]
+pub struct CityModels {
+    pub samples: RegionalSamples,
+    pub linear: LinearModel,
+    pub polynomial: PolynomialModel,
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn missing_monthly_uncertainties_are_not_zero() {
+        let months = |year: i32| (1..=MONTHS as u32).map(move |month| Period::Month(year, month));
+        let temperatures = months(1990).chain(months(1991)).map(|period| (period, 10.0)).collect();
+        // 1990 is missing March's uncertainty, 1991 has none at all
+        let uncertainty = months(1990).filter(|&period| period != Period::Month(1990, 3)).map(|period| (period, 0.12)).collect();
+        let city = City {
+            name: "Testville".to_string(),
+            country: "Nowhere".to_string(),
+            coordinates: Coordinates { latitude: 0.0, longitude: 0.0 },
+            temperatures: TimeSeries::new("temperature", temperatures).unwrap(),
+            uncertainty: TimeSeries::new("uncertainty", uncertainty).unwrap(),
+        };
+
+        let annual = city.annual_uncertainty().unwrap();
+        assert_eq!(annual.len(), 1);
+        assert_eq!(annual.points[0].0, Period::Year(1990));
+        assert!((annual.points[0].1 - 0.12 / (MONTHS as f64).sqrt()).abs() < 1e-12);
+    }
+
+    #[test]
+    fn imputed_months_get_the_sigma_of_their_calendar_month() {
+        // A strong seasonal cycle with little spread between years: a warm June, a cold January
+        let cycle = |month: u32| 10.0 * (month as f64 * std::f64::consts::PI / 6.0).sin();
+        let spread = |year: i32, month: u32| if month == 6 { 0.5 * (year - 1991) as f64 } else { 0.2 * ((year + month as i32) % 3) as f64 };
+        let missing = [Period::Month(1991, 6), Period::Month(1991, 1)];
+        let measured: Vec<(Period, f64)> = (1990..=1992)
+            .flat_map(|year| (1..=MONTHS as u32).map(move |month| (Period::Month(year, month), cycle(month) + spread(year, month))))
+            .filter(|(period, _)| !missing.contains(period))
+            .collect();
+        let city = City {
+            name: "Testville".to_string(),
+            country: "Nowhere".to_string(),
+            coordinates: Coordinates { latitude: 0.0, longitude: 0.0 },
+            temperatures: TimeSeries::new("temperature", measured.clone()).unwrap(),
+            uncertainty: TimeSeries::new("uncertainty", measured.iter().map(|&(period, _)| (period, 0.1)).collect()).unwrap(),
+        };
+
+        let (filled, report) = city.imputed(Strategy::Linear).unwrap();
+        assert_eq!((report.columns[0].missing, report.columns[0].imputed), (2, 2));
+        let sigma = |period: Period| filled.uncertainty.points.iter().find(|(p, _)| *p == period).unwrap().1;
+        // June was measured in 1990 and 1992 at -0.5 and +0.5 off the cycle, January at +0.4 and +0.2
+        assert!((sigma(missing[0]) - 0.5).abs() < 1e-9, "{}", sigma(missing[0]));
+        assert!((sigma(missing[1]) - 0.1).abs() < 1e-9, "{}", sigma(missing[1]));
+        assert!(filled.uncertainty.points.iter().filter(|(period, _)| !missing.contains(period)).all(|&(_, s)| s == 0.1));
+        assert_eq!(filled.uncertainty.len(), 36);
+    }
+
+    #[test]
+    fn rarely_measured_months_fall_back_to_the_widest_calendar_sigma() {
+        // A single year: no calendar month is measured twice, so the whole series' σ is all there is
+        let months: Vec<Period> = (1..=MONTHS as u32).map(|month| Period::Month(1990, month)).collect();
+        let measured: Vec<(Period, f64)> = months.iter().enumerate().filter(|&(i, _)| i != 5).map(|(i, &period)| (period, i as f64)).collect();
+        let city = City {
+            name: "Testville".to_string(),
+            country: "Nowhere".to_string(),
+            coordinates: Coordinates { latitude: 0.0, longitude: 0.0 },
+            temperatures: TimeSeries::new("temperature", measured.clone()).unwrap(),
+            uncertainty: TimeSeries::new("uncertainty", measured.iter().map(|&(period, _)| (period, 0.1)).collect()).unwrap(),
+        };
+        let (filled, _) = city.imputed(Strategy::Linear).unwrap();
+        assert!((filled.temperatures.points[5].1 - 5.0).abs() < 1e-9);
+        let values: Vec<f64> = measured.iter().map(|&(_, value)| value).collect();
+        let mean = values.iter().sum::<f64>() / values.len() as f64;
+        let sigma = (values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64).sqrt();
+        assert_eq!(filled.uncertainty.points[5], (Period::Month(1990, 6), sigma));
+
+        // Two years with June missing from the second: June takes the widest σ of the months measured twice
+        let mut two_years = measured.clone();
+        two_years.push((Period::Month(1990, 6), 5.0));
+        two_years.extend((0..MONTHS as u32).filter(|&i| i != 5).map(|i| (Period::Month(1991, i + 1), i as f64 + if i == 0 { 2.0 } else { 1.0 })));
+        let city = City { temperatures: TimeSeries::new("temperature", two_years.clone()).unwrap(), ..city };
+        let (filled, _) = city.imputed(Strategy::Linear).unwrap();
+        let june = filled.uncertainty.points.iter().find(|(period, _)| *period == Period::Month(1991, 6)).unwrap().1;
+        assert!((june - 1.0).abs() < 1e-9, "{}", june);
+    }
+}
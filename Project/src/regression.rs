@@ -0,0 +1,496 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::scaling::{Scaler, ScalerKind, Scaling};
+use nalgebra::{DMatrix, DVector};
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::ops::ControlFlow;
+
+//////////////////////////////////////// predictions ////////////////////////////////////////
+
+/// A point prediction with the standard deviation of a new observation at that point.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct Prediction {
+    pub mean: f64,
+    pub std: f64,
+}
+
+impl Prediction {
+    /// Interval of `z` standard deviations around the mean (z = 1.96 gives ~95 %).
+    pub fn interval(&self, z: f64) -> (f64, f64) {
+        (self.mean - z * self.std, self.mean + z * self.std)
+    }
+}
+
+//////////////////////////////////////// linear model ////////////////////////////////////////
+
+/// Least squares line y = slope * x + intercept, optionally weighted per observation.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct LinearModel {
+    pub slope: f64,
+    pub intercept: f64,
+    pub residual_variance: f64, // σ² of a unit-weight observation, estimated from the weighted training residuals
+    x_mean: f64,                // Weighted mean of x
+    x_sum_squares: f64,         // Σw(x - x̄)², needed for the prediction variance
+    weight_sum: f64,            // Σw, which is n for an unweighted fit
+    n: usize,                   // Observations with a positive weight
+}
+
+impl LinearModel {
+    /// Fits the line with the closed-form least squares solution.
+    ///
+    /// Args:
+    ///     x: Independent variable (e.g. emissions).
+    ///     y: Dependent variable (e.g. temperature).
+    ///
+    /// Returns:
+    ///     The fitted model, or an error if x has no variance.
+    pub fn fit(x: &[f64], y: &[f64]) -> Result<Self, Box<dyn Error>> {
+        Self::fit_weighted(x, y, &vec![1.0; x.len()])
+    }
+
+    /// Fits the line by weighted least squares, minimising Σw(y - ŷ)².
+    ///
+    /// Args:
+    ///     x: Independent variable (e.g. emissions).
+    ///     y: Dependent variable (e.g. temperature).
+    ///     weights: Non-negative weight per observation, usually inverse_variance_weights of its uncertainty; zero leaves it out.
+    ///
+    /// Returns:
+    ///     The fitted model, or an error if the weights are invalid or x has no variance.
+    pub fn fit_weighted(x: &[f64], y: &[f64], weights: &[f64]) -> Result<Self, Box<dyn Error>> {
+        if x.len() != y.len() || x.len() < 2 {
+            return Err("linear regression needs at least two (x, y) pairs".into());
+        }
+        let n = check_weights(weights, x.len())?;
+        let weight_sum: f64 = weights.iter().sum();
+        let x_mean = x.iter().zip(weights).map(|(xi, w)| w * xi).sum::<f64>() / weight_sum;
+        let y_mean = y.iter().zip(weights).map(|(yi, w)| w * yi).sum::<f64>() / weight_sum;
+
+        // covariance between x and y over the variance of x: β = cov(x,y)/var(x), both weighted
+        let numerator: f64 = x.iter().zip(y.iter()).zip(weights).map(|((xi, yi), w)| w * (xi - x_mean) * (yi - y_mean)).sum();
+        let denominator: f64 = x.iter().zip(weights).map(|(xi, w)| w * (xi - x_mean).powi(2)).sum();
+        if denominator == 0.0 {
+            return Err("linear regression needs x values that vary".into());
+        }
+
+        let slope = numerator / denominator;
+        // y intercept of the regression line α = y(mean) - βx(mean)
+        let intercept = y_mean - slope * x_mean;
+
+        let residual_variance = residual_variance(x, y, weights, |xi| slope * xi + intercept, 2);
+        Ok(Self { slope, intercept, residual_variance, x_mean, x_sum_squares: denominator, weight_sum, n })
+    }
+
+    pub fn predict(&self, x: f64) -> f64 {
+        self.slope * x + self.intercept
+    }
+
+    /// Prediction with the standard deviation of a new observation as precise as the average training one.
+    pub fn predict_with_uncertainty(&self, x: f64) -> Prediction {
+        self.predict_with_weight(x, self.weight_sum / self.n as f64)
+    }
+
+    /// Prediction with the standard deviation of a new observation of the given weight: σ²(1/w + 1/Σw + (x - x̄)²/Σw(x - x̄)²).
+    ///
+    /// For an unweighted fit and w = 1 this is the usual σ²(1 + 1/n + (x - x̄)²/Σ(x - x̄)²).
+    pub fn predict_with_weight(&self, x: f64, weight: f64) -> Prediction {
+        let leverage = 1.0 / self.weight_sum + (x - self.x_mean).powi(2) / self.x_sum_squares;
+        Prediction { mean: self.predict(x), std: (self.residual_variance * (1.0 / weight + leverage)).sqrt() }
+    }
+}
+
+//////////////////////////////////////// gradient descent ////////////////////////////////////////
+
+/// Settings of an iterative fit.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+pub struct GradientDescent {
+    pub epochs: usize,
+    pub learning_rate: f64, // Step size on standardized data; 0.1 converges in a few hundred epochs
+}
+
+impl LinearModel {
+    /// Fits the line by full-batch gradient descent on the mean squared error of standardized x and y.
+    ///
+    /// Converges to the least squares line of `fit`, but reports its progress, so a long fit can be
+    /// watched and stopped.
+    ///
+    /// Args:
+    ///     x: Independent variable (e.g. emissions).
+    ///     y: Dependent variable (e.g. temperature).
+    ///     settings: Number of epochs and learning rate.
+    ///     on_epoch: Called after every epoch with its number (from 1) and the training mean squared
+    ///         error in y units; returning ControlFlow::Break stops the fit.
+    ///
+    /// Returns:
+    ///     The line after the last epoch, or an error if the settings are invalid, x has no
+    ///     variance or the fit was stopped.
+    pub fn fit_gradient_descent<F>(x: &[f64], y: &[f64], settings: GradientDescent, mut on_epoch: F) -> Result<Self, Box<dyn Error>>
+    where
+        F: FnMut(usize, f64) -> ControlFlow<()>,
+    {
+        if x.len() != y.len() || x.len() < 2 {
+            return Err("linear regression needs at least two (x, y) pairs".into());
+        }
+        if !(settings.learning_rate.is_finite() && settings.learning_rate > 0.0) {
+            return Err(format!("the learning rate must be positive, found {}", settings.learning_rate).into());
+        }
+        if x.iter().all(|xi| *xi == x[0]) {
+            return Err("linear regression needs x values that vary".into());
+        }
+        let (x_scaler, y_scaler) = (Scaler::fit(ScalerKind::Standard, x)?, Scaler::fit(ScalerKind::Standard, y)?);
+        let (xs, ys) = (x_scaler.transform_all(x), y_scaler.transform_all(y));
+        let n = x.len() as f64;
+
+        // The line in scaled units, mapped back to y = slope * x + intercept after every step
+        let (mut slope, mut intercept) = (0.0, 0.0);
+        let unscale = |slope: f64, intercept: f64| {
+            let original_slope = slope * y_scaler.scale / x_scaler.scale;
+            (original_slope, y_scaler.inverse(intercept) - original_slope * x_scaler.center)
+        };
+        for epoch in 1..=settings.epochs {
+            // ∂/∂β and ∂/∂α of (1/n)Σ(βx + α - y)²
+            let (mut slope_gradient, mut intercept_gradient) = (0.0, 0.0);
+            for (xi, yi) in xs.iter().zip(&ys) {
+                let error = slope * xi + intercept - yi;
+                slope_gradient += 2.0 * error * xi / n;
+                intercept_gradient += 2.0 * error / n;
+            }
+            slope -= settings.learning_rate * slope_gradient;
+            intercept -= settings.learning_rate * intercept_gradient;
+
+            let (original_slope, original_intercept) = unscale(slope, intercept);
+            let predictions: Vec<f64> = x.iter().map(|xi| original_slope * xi + original_intercept).collect();
+            let loss = mean_squared_error(&predictions, y);
+            if !loss.is_finite() {
+                return Err(format!("gradient descent diverged at epoch {}; lower the learning rate", epoch).into());
+            }
+            if on_epoch(epoch, loss).is_break() {
+                return Err(format!("gradient descent stopped after epoch {}", epoch).into());
+            }
+        }
+
+        let (slope, intercept) = unscale(slope, intercept);
+        Self::from_line(x, y, slope, intercept)
+    }
+
+    // The model of a given line, with the statistics `fit` would compute for it on (x, y)
+    fn from_line(x: &[f64], y: &[f64], slope: f64, intercept: f64) -> Result<Self, Box<dyn Error>> {
+        let n = x.len();
+        let x_mean = x.iter().sum::<f64>() / n as f64;
+        let x_sum_squares: f64 = x.iter().map(|xi| (xi - x_mean).powi(2)).sum();
+        if x_sum_squares == 0.0 {
+            return Err("linear regression needs x values that vary".into());
+        }
+        let residual_variance = residual_variance(x, y, &vec![1.0; n], |xi| slope * xi + intercept, 2);
+        Ok(Self { slope, intercept, residual_variance, x_mean, x_sum_squares, weight_sum: n as f64, n })
+    }
+}
+
+//////////////////////////////////////// polynomial model ////////////////////////////////////////
+
+/// Polynomial y = Σ a_i x^i fitted by (optionally weighted) least squares.
+///
+/// With scaling, x and y are rescaled before fitting and the coefficients relate the scaled values;
+/// predict and predict_with_uncertainty take and return original units either way.
+#[derive(Debug, Clone, Serialize, Deserialize)]
+pub struct PolynomialModel {
+    pub coefficients: Vec<f64>,  // Coefficients in the scaled input, giving the scaled target
+    pub residual_variance: f64,  // σ² of a unit-weight observation in target units, from the weighted training residuals
+    pub input_scaler: Scaler,
+    pub target_scaler: Scaler,
+    xtx_inverse: DMatrix<f64>,   // (XᵀWX)⁻¹ in scaled inputs, needed for the prediction variance
+    mean_weight: f64,            // Average weight of the observations used, 1 for an unweighted fit
+}
+
+impl PolynomialModel {
+    /// Performs polynomial regression using the normal equation and Cholesky decomposition.
+    ///
+    /// Args:
+    ///     x: Independent variable (e.g. time).
+    ///     y: Dependent variable (e.g. emissions).
+    ///     degree: Degree of the polynomial.
+    ///
+    /// Returns:
+    ///     The fitted model with one coefficient per power of x.
+    pub fn fit(x: &[f64], y: &[f64], degree: usize) -> Result<Self, Box<dyn Error>> {
+        Self::fit_weighted(x, y, degree, &vec![1.0; x.len()])
+    }
+
+    /// Weighted polynomial regression, solving the normal equations XᵀWX β = XᵀWy.
+    ///
+    /// Args:
+    ///     x: Independent variable (e.g. time).
+    ///     y: Dependent variable (e.g. emissions).
+    ///     degree: Degree of the polynomial.
+    ///     weights: Non-negative weight per observation, usually inverse_variance_weights of its uncertainty; zero leaves it out.
+    ///
+    /// Returns:
+    ///     The fitted model with one coefficient per power of x.
+    pub fn fit_weighted(x: &[f64], y: &[f64], degree: usize, weights: &[f64]) -> Result<Self, Box<dyn Error>> {
+        Self::fit_scaled(x, y, degree, weights, Scaling::none())
+    }
+
+    /// Weighted polynomial regression on rescaled input and target.
+    ///
+    /// Powers of raw years (1880³ ≈ 6.8·10⁹) make XᵀX nearly singular; standardizing x first keeps
+    /// the normal equations well conditioned. The scalers are stored in the model and applied at
+    /// predict time, including the inverse transform of the target.
+    ///
+    /// Args:
+    ///     x: Independent variable (e.g. time).
+    ///     y: Dependent variable (e.g. emissions).
+    ///     degree: Degree of the polynomial.
+    ///     weights: Non-negative weight per observation (all 1.0 for ordinary least squares); zero leaves it out.
+    ///     scaling: Scalers to fit for x and y.
+    ///
+    /// Returns:
+    ///     The fitted model with one coefficient per power of the scaled x.
+    pub fn fit_scaled(x: &[f64], y: &[f64], degree: usize, weights: &[f64], scaling: Scaling) -> Result<Self, Box<dyn Error>> {
+        if x.len() != y.len() {
+            return Err(format!("expected as many y values as x values, found {} and {}", y.len(), x.len()).into());
+        }
+        let n = check_weights(weights, x.len())?;
+        if n <= degree {
+            return Err(format!("degree {} polynomial needs more than {} weighted (x, y) pairs", degree, degree).into());
+        }
+        let input_scaler = Scaler::fit(scaling.input, x)?;
+        let target_scaler = Scaler::fit(scaling.target, y)?;
+        let (x, y) = (input_scaler.transform_all(x), target_scaler.transform_all(y));
+
+        // Builds the design matrix of polynomial features; scaling each row by √w turns WLS into OLS
+        let mut x_poly = build_polynomial_features(&x, degree);
+        let mut targets = DVector::from_column_slice(&y);
+        for (i, w) in weights.iter().enumerate() {
+            let scale = w.sqrt();
+            x_poly.row_mut(i).scale_mut(scale);
+            targets[i] *= scale;
+        }
+
+        // Perform matrix multiplication for X^T * W * X and X^T * W * y
+        let xt = x_poly.transpose();
+        let xt_x = &xt * &x_poly;
+        let xt_y = xt * targets;
+
+        // Solves the normal equations using Cholesky decomposition for linear least squares
+        let chol = nalgebra::linalg::Cholesky::new(xt_x).ok_or("Cholesky decomposition failed")?;
+        let beta = chol.solve(&xt_y);
+        let coefficients: Vec<f64> = beta.iter().cloned().collect();
+
+        let residual_variance = residual_variance(&x, &y, weights, |xi| evaluate(&coefficients, xi), degree + 1)
+            * target_scaler.scale.powi(2);
+        let mean_weight = weights.iter().sum::<f64>() / n as f64;
+        Ok(Self { coefficients, residual_variance, input_scaler, target_scaler, xtx_inverse: chol.inverse(), mean_weight })
+    }
+
+    pub fn degree(&self) -> usize {
+        self.coefficients.len().saturating_sub(1)
+    }
+
+    pub fn predict(&self, x: f64) -> f64 {
+        self.target_scaler.inverse(evaluate(&self.coefficients, self.input_scaler.transform(x)))
+    }
+
+    /// Prediction with the standard deviation of a new observation as precise as the average training one.
+    pub fn predict_with_uncertainty(&self, x: f64) -> Prediction {
+        self.predict_with_weight(x, self.mean_weight)
+    }
+
+    /// Prediction with the standard deviation of a new observation of the given weight: σ²(1/w + φ(x)ᵀ(XᵀWX)⁻¹φ(x)).
+    pub fn predict_with_weight(&self, x: f64, weight: f64) -> Prediction {
+        let phi = build_polynomial_features(&[self.input_scaler.transform(x)], self.degree()).transpose();
+        let leverage = (phi.transpose() * &self.xtx_inverse * &phi)[(0, 0)];
+        Prediction { mean: self.predict(x), std: (self.residual_variance * (1.0 / weight + leverage)).sqrt() }
+    }
+}
+
+fn evaluate(coefficients: &[f64], x: f64) -> f64 {
+    coefficients.iter().enumerate()
+        .map(|(i, coeff)| coeff * x.powi(i as i32))
+        .sum()
+}
+
+/// Builds a matrix of polynomial features from a single variable input.
+///
+/// Args:
+///     x: Input data.
+///     degree: The degree of polynomial features to generate.
+///
+/// Returns:
+///     A matrix where each column represents x to the power of the column index.
+pub fn build_polynomial_features(x: &[f64], degree: usize) -> DMatrix<f64> {
+    let n = x.len();
+    let mut x_poly = DMatrix::from_element(n, degree + 1, 1.0);
+
+    for i in 1..=degree {
+        for j in 0..n {
+            x_poly[(j, i)] = x_poly[(j, i - 1)] * x[j];
+        }
+    }
+
+    x_poly
+}
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+/// Paired (x, y) samples.
+pub type Samples = (Vec<f64>, Vec<f64>);
+
+/// Splits paired data into training and testing sets, keeping the last `ratio` of rows for testing.
+///
+/// The rows are time-ordered, so the test set is the most recent data rather than a random sample.
+pub fn split_data(x: &[f64], y: &[f64], ratio: f64) -> (Samples, Samples) {
+    let test_size = (x.len() as f64 * ratio).round() as usize;
+    let train_size = x.len() - test_size;
+
+    let training_data = (x[..train_size].to_vec(), y[..train_size].to_vec());
+    let test_data = (x[train_size..].to_vec(), y[train_size..].to_vec());
+
+    (training_data, test_data)
+}
+
+/// Weights for weighted least squares from per-observation uncertainties: w = 1/σ².
+///
+/// Only the relative weights matter: the residual variance is estimated from the data, so a
+/// uniformly over- or understated uncertainty (or a 95 % half-width instead of σ) gives the same
+/// fit and the same prediction intervals.
+pub fn inverse_variance_weights(uncertainty: &[f64]) -> Result<Vec<f64>, Box<dyn Error>> {
+    uncertainty.iter().enumerate()
+        .map(|(i, &sigma)| {
+            if sigma.is_finite() && sigma > 0.0 {
+                Ok(1.0 / (sigma * sigma))
+            } else {
+                Err(format!("uncertainty {} of observation {} must be positive", sigma, i).into())
+            }
+        })
+        .collect()
+}
+
+// Number of observations the fit uses, i.e. those with a positive weight
+fn check_weights(weights: &[f64], n: usize) -> Result<usize, Box<dyn Error>> {
+    if weights.len() != n {
+        return Err(format!("expected {} weights, found {}", n, weights.len()).into());
+    }
+    if let Some(w) = weights.iter().find(|w| !w.is_finite() || **w < 0.0) {
+        return Err(format!("weights must be non-negative and finite, found {}", w).into());
+    }
+    match weights.iter().filter(|w| **w > 0.0).count() {
+        0 => Err("at least one weight must be positive".into()),
+        used => Ok(used),
+    }
+}
+
+/// Unbiased residual variance Σw(y - ŷ)² / (n - p) for a model with `parameters` fitted parameters,
+/// where n counts the observations with a positive weight.
+fn residual_variance<F: Fn(f64) -> f64>(x: &[f64], y: &[f64], weights: &[f64], predict: F, parameters: usize) -> f64 {
+    let sum_squares: f64 = x.iter().zip(y.iter()).zip(weights).map(|((&xi, yi), w)| w * (yi - predict(xi)).powi(2)).sum();
+    let used = weights.iter().filter(|w| **w > 0.0).count();
+    sum_squares / (used.saturating_sub(parameters)).max(1) as f64
+}
+
+/// Computes the Mean Squared Error (MSE) between predicted values and actual values.
+pub fn mean_squared_error(predictions: &[f64], targets: &[f64]) -> f64 {
+    predictions.iter().zip(targets.iter())
+        .map(|(p, t)| (p - t).powi(2))
+        .sum::<f64>() / predictions.len() as f64
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn gradient_descent_converges_to_least_squares() {
+        // Emissions-like x far from zero, so the fit only converges thanks to the standardization
+        let x: Vec<f64> = (0..30).map(|i| 5.0 + 1.3 * i as f64).collect();
+        let y: Vec<f64> = x.iter().enumerate().map(|(i, xi)| 0.03 * xi - 0.4 + 0.05 * ((i * 7 % 11) as f64 - 5.0) / 5.0).collect();
+        let exact = LinearModel::fit(&x, &y).unwrap();
+
+        let mut losses = Vec::new();
+        let settings = GradientDescent { epochs: 500, learning_rate: 0.1 };
+        let descended = LinearModel::fit_gradient_descent(&x, &y, settings, |_, loss| {
+            losses.push(loss);
+            ControlFlow::Continue(())
+        }).unwrap();
+
+        assert!((descended.slope - exact.slope).abs() < 1e-9, "{} vs {}", descended.slope, exact.slope);
+        assert!((descended.intercept - exact.intercept).abs() < 1e-9, "{} vs {}", descended.intercept, exact.intercept);
+        assert!((descended.residual_variance - exact.residual_variance).abs() < 1e-12);
+        assert!(losses.windows(2).all(|pair| pair[1] <= pair[0] + 1e-15), "the loss never rises");
+    }
+
+    // A noisy quadratic in emissions-like x, and an outlier to leave out
+    fn samples() -> (Vec<f64>, Vec<f64>) {
+        let x: Vec<f64> = (0..20).map(|i| 5.0 + 1.7 * i as f64).collect();
+        let y = x.iter().enumerate().map(|(i, xi)| 0.2 + 0.03 * xi + 0.0004 * xi * xi + 0.04 * ((i * 5 % 7) as f64 - 3.0) / 3.0).collect();
+        (x, y)
+    }
+
+    fn close(a: f64, b: f64) -> bool {
+        (a - b).abs() <= 1e-9 * a.abs().max(b.abs()).max(1.0)
+    }
+
+    #[test]
+    fn uniform_weights_reproduce_ordinary_least_squares() {
+        let (x, y) = samples();
+        let ordinary = LinearModel::fit(&x, &y).unwrap();
+        let weighted = LinearModel::fit_weighted(&x, &y, &vec![4.0; x.len()]).unwrap();
+        assert!(close(weighted.slope, ordinary.slope) && close(weighted.intercept, ordinary.intercept));
+        // σ² is per unit weight, so it scales with the weights while the intervals stay the same
+        assert!(close(weighted.residual_variance, 4.0 * ordinary.residual_variance));
+        assert!(close(weighted.predict_with_uncertainty(70.0).std, ordinary.predict_with_uncertainty(70.0).std));
+
+        // The textbook closed form: β = Σ(x - x̄)(y - ȳ) / Σ(x - x̄)²
+        let (x_mean, y_mean) = (x.iter().sum::<f64>() / 20.0, y.iter().sum::<f64>() / 20.0);
+        let slope = x.iter().zip(&y).map(|(xi, yi)| (xi - x_mean) * (yi - y_mean)).sum::<f64>() / x.iter().map(|xi| (xi - x_mean).powi(2)).sum::<f64>();
+        assert!(close(ordinary.slope, slope) && close(ordinary.intercept, y_mean - slope * x_mean));
+
+        let ordinary = PolynomialModel::fit(&x, &y, 2).unwrap();
+        for weighted in [
+            PolynomialModel::fit_weighted(&x, &y, 2, &vec![4.0; x.len()]).unwrap(),
+            PolynomialModel::fit_scaled(&x, &y, 2, &vec![4.0; x.len()], Scaling::standard()).unwrap(),
+        ] {
+            for xi in [5.0, 30.0, 70.0] {
+                assert!(close(weighted.predict(xi), ordinary.predict(xi)), "{} vs {}", weighted.predict(xi), ordinary.predict(xi));
+                assert!(close(weighted.predict_with_uncertainty(xi).std, ordinary.predict_with_uncertainty(xi).std));
+            }
+        }
+    }
+
+    #[test]
+    fn a_zero_weight_ignores_its_point() {
+        let (x, y) = samples();
+        let (mut x_out, mut y_out, mut weights) = (x.clone(), y.clone(), vec![1.0; x.len()]);
+        x_out.push(40.0);
+        y_out.push(100.0);
+        weights.push(0.0);
+
+        let without = LinearModel::fit(&x, &y).unwrap();
+        let ignored = LinearModel::fit_weighted(&x_out, &y_out, &weights).unwrap();
+        assert!(close(ignored.slope, without.slope) && close(ignored.intercept, without.intercept));
+        assert!(close(ignored.residual_variance, without.residual_variance));
+        assert!(close(ignored.predict_with_uncertainty(70.0).std, without.predict_with_uncertainty(70.0).std));
+
+        let without = PolynomialModel::fit_scaled(&x, &y, 2, &vec![1.0; x.len()], Scaling::standard()).unwrap();
+        let ignored = PolynomialModel::fit_scaled(&x_out, &y_out, 2, &weights, Scaling::standard()).unwrap();
+        assert!(close(ignored.predict(40.0), without.predict(40.0)));
+        assert!(close(ignored.residual_variance, without.residual_variance));
+        assert!(close(ignored.predict_with_uncertainty(40.0).std, without.predict_with_uncertainty(40.0).std));
+
+        assert!(LinearModel::fit_weighted(&x, &y, &vec![0.0; x.len()]).is_err());
+        assert!(LinearModel::fit_weighted(&x, &y, &[-1.0].repeat(x.len())).is_err());
+    }
+
+    #[test]
+    fn singular_designs_fail_cleanly() {
+        // Every x the same: the columns of the design matrix are identical
+        let error = PolynomialModel::fit(&[2.0; 6], &[1.0, 2.0, 3.0, 4.0, 5.0, 6.0], 2).unwrap_err();
+        assert_eq!(error.to_string(), "Cholesky decomposition failed");
+        // Zero weights can leave too few points as well
+        let error = PolynomialModel::fit_weighted(&[1.0, 2.0, 3.0, 4.0], &[1.0, 4.0, 9.0, 16.0], 2, &[1.0, 1.0, 0.0, 0.0]).unwrap_err();
+        assert!(error.to_string().contains("more than 2 weighted"), "{}", error);
+        assert!(LinearModel::fit_weighted(&[1.0, 2.0, 3.0], &[1.0, 2.0, 3.0], &[0.0, 1.0, 0.0]).is_err());
+    }
+}
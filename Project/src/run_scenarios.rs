@@ -0,0 +1,72 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use climate_predict::dataset::{self, Dataset};
+use climate_predict::energy_balance::{observed_concentrations, EnergyBalanceModel};
+use climate_predict::regression::{mean_squared_error, LinearModel};
+use climate_predict::scenario::{self, Pathway, TemperatureModel};
+use std::env;
+use std::error::Error;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const DATA: &str = "./data/emission_temp_data.csv";
+const REPORT_EVERY: usize = 10; // Print every n-th year of each trajectory
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+/// Years, emissions and temperatures, one entry per row.
+type Columns = (Vec<i32>, Vec<f64>, Vec<f64>);
+
+/// Reads years, emissions and temperatures from the merged dataset.
+fn read_data(path: &str) -> Result<Columns, Box<dyn Error>> {
+    let dataset = Dataset::load(path, dataset::emission_temperature())?;
+    let years = dataset.integers("Year")?.into_iter().map(|year| year as i32).collect();
+    let emissions = dataset.floats("Emissions")?;
+    let temps = dataset.floats("Lowess")?;
+
+    Ok((years, emissions, temps))
+}
+
+//////////////////////////////////////// main ////////////////////////////////////////
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let (years, emissions, temps) = read_data(DATA)?;
+    let linear = LinearModel::fit(&emissions, &temps)?;
+    let energy_balance = EnergyBalanceModel::calibrate(&years, &emissions, &temps, observed_concentrations(&years)?)?;
+    let models: [&dyn TemperatureModel; 2] = [&linear, &energy_balance];
+
+    // Compare how well each model reproduces the historical record
+    let historical = Pathway { name: "historical".to_string(), years: years.clone(), emissions: emissions.clone() };
+    for model in models {
+        println!("{} model: in-sample MSE {:.4}", model.name(), mean_squared_error(&model.project(&historical), &temps));
+    }
+    println!("energy balance climate sensitivity: {:.2} °C per doubling of CO2", energy_balance.parameters.climate_sensitivity());
+
+    // Every pathway starts from the last observed year and emission level
+    let start = *years.last().ok_or("dataset is empty")?;
+    let level = *emissions.last().ok_or("dataset is empty")?;
+    let mut pathways = scenario::presets(start, level)?;
+
+    // Optional custom pathway: cargo run --bin scenario -- path/to/pathway.csv
+    if let Some(path) = env::args().nth(1) {
+        pathways.push(Pathway::from_csv("custom", &path)?);
+    }
+
+    for pathway in &pathways {
+        for model in models {
+            let trajectory = scenario::run(pathway, model);
+            println!("{} ({} model)", trajectory.pathway, trajectory.model);
+            for i in (0..trajectory.years.len()).step_by(REPORT_EVERY) {
+                println!("  {}: {:>7.2} GtCO₂ -> {:>5.2} °C", trajectory.years[i], trajectory.emissions[i], trajectory.temperatures[i]);
+            }
+            if let Some(temp) = trajectory.final_temperature() {
+                println!("  {}: {:.2} °C", trajectory.years[trajectory.years.len() - 1], temp);
+            }
+        }
+    }
+
+    Ok(())
+}
+
+// cargo run --bin scenario
+// cargo run --bin scenario -- ./data/my_pathway.csv
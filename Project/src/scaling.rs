@@ -0,0 +1,196 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use serde::{Deserialize, Serialize};
+use std::error::Error;
+use std::fmt;
+use std::str::FromStr;
+
+//////////////////////////////////////// scalers ////////////////////////////////////////
+
+/// How to rescale a feature or target before fitting.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub enum ScalerKind {
+    Identity, // Leave values as they are
+    Standard, // Zero mean, unit standard deviation
+    MinMax,   // Map the training range onto [0, 1]
+    Robust,   // Zero median, unit interquartile range; insensitive to outliers
+}
+
+impl ScalerKind {
+    pub fn name(&self) -> &'static str {
+        match self {
+            ScalerKind::Identity => "identity",
+            ScalerKind::Standard => "standard",
+            ScalerKind::MinMax => "min-max",
+            ScalerKind::Robust => "robust",
+        }
+    }
+}
+
+impl fmt::Display for ScalerKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.name())
+    }
+}
+
+impl FromStr for ScalerKind {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "identity" | "none" => Ok(ScalerKind::Identity),
+            "standard" => Ok(ScalerKind::Standard),
+            "min-max" => Ok(ScalerKind::MinMax),
+            "robust" => Ok(ScalerKind::Robust),
+            _ => Err(format!("unknown scaler '{}' (expected identity, standard, min-max or robust)", s)),
+        }
+    }
+}
+
+/// A fitted affine rescaling z = (x - center) / scale, kept with the model so the same
+/// transformation is applied at predict time.
+#[derive(Debug, Clone, Copy, PartialEq, Serialize, Deserialize)]
+pub struct Scaler {
+    pub kind: ScalerKind,
+    pub center: f64,
+    pub scale: f64,
+}
+
+impl Default for Scaler {
+    fn default() -> Self {
+        Self::identity()
+    }
+}
+
+impl Scaler {
+    pub fn identity() -> Self {
+        Self { kind: ScalerKind::Identity, center: 0.0, scale: 1.0 }
+    }
+
+    /// Learns the center and scale of `values`.
+    ///
+    /// A constant column has no spread to divide by, so its scale is left at 1 and it is only centred.
+    pub fn fit(kind: ScalerKind, values: &[f64]) -> Result<Self, Box<dyn Error>> {
+        if kind == ScalerKind::Identity {
+            return Ok(Self::identity());
+        }
+        if values.is_empty() || values.iter().any(|value| !value.is_finite()) {
+            return Err(format!("{} scaling needs at least one value and no NaN or infinite values", kind).into());
+        }
+
+        let (center, spread) = match kind {
+            ScalerKind::Identity => unreachable!("returned above"),
+            ScalerKind::Standard => {
+                let mean = values.iter().sum::<f64>() / values.len() as f64;
+                let variance = values.iter().map(|value| (value - mean).powi(2)).sum::<f64>() / values.len() as f64;
+                (mean, variance.sqrt())
+            }
+            ScalerKind::MinMax => {
+                let min = values.iter().copied().fold(f64::INFINITY, f64::min);
+                let max = values.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+                (min, max - min)
+            }
+            ScalerKind::Robust => {
+                let mut sorted = values.to_vec();
+                sorted.sort_by(f64::total_cmp);
+                (quantile(&sorted, 0.5), quantile(&sorted, 0.75) - quantile(&sorted, 0.25))
+            }
+        };
+        Ok(Self { kind, center, scale: if spread > 0.0 { spread } else { 1.0 } })
+    }
+
+    pub fn transform(&self, value: f64) -> f64 {
+        (value - self.center) / self.scale
+    }
+
+    pub fn transform_all(&self, values: &[f64]) -> Vec<f64> {
+        values.iter().map(|&value| self.transform(value)).collect()
+    }
+
+    /// Maps a scaled value back to the original units.
+    pub fn inverse(&self, scaled: f64) -> f64 {
+        scaled * self.scale + self.center
+    }
+
+    pub fn inverse_all(&self, scaled: &[f64]) -> Vec<f64> {
+        scaled.iter().map(|&value| self.inverse(value)).collect()
+    }
+
+    /// Maps a spread (standard deviation, interval width) back to the original units; the center does not apply.
+    pub fn inverse_spread(&self, spread: f64) -> f64 {
+        spread * self.scale.abs()
+    }
+}
+
+/// Scalers to fit for a model's input and target.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Scaling {
+    pub input: ScalerKind,
+    pub target: ScalerKind,
+}
+
+impl Default for Scaling {
+    fn default() -> Self {
+        Self::none()
+    }
+}
+
+impl Scaling {
+    pub fn none() -> Self {
+        Self { input: ScalerKind::Identity, target: ScalerKind::Identity }
+    }
+
+    /// Standardizes both input and target, the usual choice for polynomials in raw years.
+    pub fn standard() -> Self {
+        Self { input: ScalerKind::Standard, target: ScalerKind::Standard }
+    }
+}
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+// Quantile of sorted values with linear interpolation between neighbouring ranks
+fn quantile(sorted: &[f64], q: f64) -> f64 {
+    let position = q * (sorted.len() - 1) as f64;
+    let (below, above) = (position.floor() as usize, position.ceil() as usize);
+    sorted[below] + (sorted[above] - sorted[below]) * (position - below as f64)
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::regression::PolynomialModel;
+
+    #[test]
+    fn scalers_round_trip() {
+        let values = [1880.0, 1900.0, 1950.0, 1990.0, 2020.0, 2023.0];
+        for kind in [ScalerKind::Identity, ScalerKind::Standard, ScalerKind::MinMax, ScalerKind::Robust] {
+            let scaler = Scaler::fit(kind, &values).unwrap();
+            for (value, back) in values.iter().zip(scaler.inverse_all(&scaler.transform_all(&values))) {
+                assert!((value - back).abs() < 1e-9, "{} scaling maps {} back to {}", kind, value, back);
+            }
+        }
+        let min_max = Scaler::fit(ScalerKind::MinMax, &values).unwrap();
+        assert_eq!((min_max.transform(1880.0), min_max.transform(2023.0)), (0.0, 1.0));
+        assert_eq!(Scaler::fit(ScalerKind::Standard, &[5.0, 5.0]).unwrap().scale, 1.0);
+    }
+
+    #[test]
+    fn scaled_polynomials_predict_in_original_units() {
+        // y = 2 + 0.3(x - 1950) - 0.01(x - 1950)², exactly, in raw years
+        let x: Vec<f64> = (1880..=2020).step_by(5).map(f64::from).collect();
+        let y: Vec<f64> = x.iter().map(|year| 2.0 + 0.3 * (year - 1950.0) - 0.01 * (year - 1950.0).powi(2)).collect();
+        let model = PolynomialModel::fit_scaled(&x, &y, 2, &vec![1.0; x.len()], Scaling::standard()).unwrap();
+
+        for year in [1880.0_f64, 1963.5, 2050.0] {
+            let expected = 2.0 + 0.3 * (year - 1950.0) - 0.01 * (year - 1950.0).powi(2);
+            assert!((model.predict(year) - expected).abs() < 1e-6, "{} predicts {}", year, model.predict(year));
+        }
+
+        // Expanding the scaled polynomial gives back the original curvature: a₂ · σ_y / σ_x²
+        let curvature = model.coefficients[2] * model.target_scaler.scale / model.input_scaler.scale.powi(2);
+        assert!((curvature + 0.01).abs() < 1e-9);
+        assert!(model.residual_variance < 1e-12);
+    }
+}
@@ -0,0 +1,215 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{Column, Dataset, Schema};
+use crate::regression::{LinearModel, PolynomialModel};
+use std::error::Error;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const PRESET_END_YEAR: i32 = 2100;
+
+//////////////////////////////////////// pathways ////////////////////////////////////////
+
+/// An emissions pathway: global CO2 emissions (GtCO₂ per year) for consecutive years.
+#[derive(Debug, Clone)]
+pub struct Pathway {
+    pub name: String,
+    pub years: Vec<i32>,
+    pub emissions: Vec<f64>,
+}
+
+impl Pathway {
+    /// Emissions held at `level` from `start` to `end` (inclusive).
+    pub fn constant(name: &str, start: i32, end: i32, level: f64) -> Self {
+        Self::from_fn(name, start, end, |_| level)
+    }
+
+    /// Emissions falling linearly from `level` in `start` to zero in `net_zero_year`, then staying at zero.
+    pub fn linear_to_net_zero(name: &str, start: i32, end: i32, level: f64, net_zero_year: i32) -> Self {
+        let span = (net_zero_year - start).max(1) as f64;
+        Self::from_fn(name, start, end, |year| level * (1.0 - (year - start) as f64 / span).max(0.0))
+    }
+
+    /// Emissions growing (or shrinking, for a negative rate) by `rate` per year, e.g. 0.02 for 2 %.
+    pub fn exponential(name: &str, start: i32, end: i32, level: f64, rate: f64) -> Self {
+        Self::from_fn(name, start, end, |year| level * (1.0 + rate).powi(year - start))
+    }
+
+    /// Piecewise-linear pathway through `(year, emissions)` control points, annual in between.
+    pub fn piecewise(name: &str, points: &[(i32, f64)]) -> Result<Self, Box<dyn Error>> {
+        if points.len() < 2 || points.windows(2).any(|pair| pair[0].0 >= pair[1].0) {
+            return Err("a piecewise pathway needs at least two control points with increasing years".into());
+        }
+        let (start, end) = (points[0].0, points[points.len() - 1].0);
+        Ok(Self::from_fn(name, start, end, |year| {
+            let segment = points.windows(2).find(|pair| year <= pair[1].0).unwrap_or(&points[points.len() - 2..]);
+            let ((y0, e0), (y1, e1)) = (segment[0], segment[1]);
+            e0 + (e1 - e0) * (year - y0) as f64 / (y1 - y0) as f64
+        }))
+    }
+
+    /// Reads a custom pathway from a CSV file with `Year,Emissions` columns.
+    pub fn from_csv(name: &str, path: &str) -> Result<Self, Box<dyn Error>> {
+        let schema = Schema::new(vec![Column::integer("Year"), Column::float("Emissions").unit("GtCO₂")]);
+        let dataset = Dataset::load(path, schema)?;
+        let years: Vec<i32> = dataset.integers("Year")?.into_iter().map(|year| year as i32).collect();
+        let emissions = dataset.floats("Emissions")?;
+
+        if years.windows(2).any(|pair| pair[1] != pair[0] + 1) {
+            return Err(format!("pathway {} must list consecutive years", path).into());
+        }
+        Ok(Self { name: name.to_string(), years, emissions })
+    }
+
+    fn from_fn<F: Fn(i32) -> f64>(name: &str, start: i32, end: i32, emissions: F) -> Self {
+        let years: Vec<i32> = (start..=end).collect();
+        let emissions = years.iter().map(|&year| emissions(year)).collect();
+        Self { name: name.to_string(), years, emissions }
+    }
+}
+
+/// Built-in pathways shaped roughly like the IPCC SSP scenarios, starting from today's emissions.
+///
+/// The control points are multiples of `level` (emissions in `start`) and only mimic the shape of
+/// each SSP's CO2 trajectory; they are not the official scenario data.
+///
+/// Args:
+///     start: First year of every pathway (usually the last year with data).
+///     level: Emissions in `start`, in GtCO₂ per year.
+///
+/// Returns:
+///     One pathway per preset, each running to PRESET_END_YEAR, or an error naming the first preset
+///     left without a pathway (every one is, once `start` reaches PRESET_END_YEAR).
+pub fn presets(start: i32, level: f64) -> Result<Vec<Pathway>, Box<dyn Error>> {
+    preset_points(start, level).into_iter()
+        .map(|(name, points)| Pathway::piecewise(name, &points).map_err(|error| format!("preset {} from {}: {}", name, start, error).into()))
+        .collect()
+}
+
+/// The `(year, emissions)` control points of every preset pathway, for editors that let users
+/// move them; `Pathway::piecewise` turns them into the annual pathways of `presets`.
+///
+/// Points at or before `start` are left out, so a preset starting after one of its bends still
+/// runs to PRESET_END_YEAR; from PRESET_END_YEAR on, each preset is the start point alone.
+pub fn preset_points(start: i32, level: f64) -> Vec<(&'static str, Vec<(i32, f64)>)> {
+    let shapes: [(&str, &[(i32, f64)]); 5] = [
+        ("SSP1-1.9", &[(2050, 0.0), (PRESET_END_YEAR, -0.35)]),
+        ("SSP1-2.6", &[(2075, 0.0), (PRESET_END_YEAR, -0.2)]),
+        ("SSP2-4.5", &[(2050, 1.05), (PRESET_END_YEAR, 0.25)]),
+        ("SSP3-7.0", &[(PRESET_END_YEAR, 2.0)]),
+        ("SSP5-8.5", &[(2080, 3.0), (PRESET_END_YEAR, 3.1)]),
+    ];
+
+    shapes.iter()
+        .map(|(name, shape)| {
+            let mut points = vec![(start, level)];
+            points.extend(shape.iter().filter(|(year, _)| *year > start).map(|&(year, multiple)| (year, multiple * level)));
+            (*name, points)
+        })
+        .collect()
+}
+
+//////////////////////////////////////// models ////////////////////////////////////////
+
+/// Anything that can turn an emissions pathway into a temperature trajectory.
+///
+/// Statistical fits map each year's emissions to that year's temperature independently, while
+/// physical models may carry state from one year to the next, so the whole pathway is passed in.
+pub trait TemperatureModel {
+    fn name(&self) -> String;
+
+    /// Temperature anomaly (°C) for every year of the pathway.
+    fn project(&self, pathway: &Pathway) -> Vec<f64>;
+}
+
+impl TemperatureModel for LinearModel {
+    fn name(&self) -> String {
+        "linear".to_string()
+    }
+
+    fn project(&self, pathway: &Pathway) -> Vec<f64> {
+        pathway.emissions.iter().map(|&e| self.predict(e)).collect()
+    }
+}
+
+impl TemperatureModel for PolynomialModel {
+    fn name(&self) -> String {
+        format!("polynomial (degree {})", self.degree())
+    }
+
+    fn project(&self, pathway: &Pathway) -> Vec<f64> {
+        pathway.emissions.iter().map(|&e| self.predict(e)).collect()
+    }
+}
+
+//////////////////////////////////////// runs ////////////////////////////////////////
+
+/// A pathway run through a temperature model.
+#[derive(Debug, Clone)]
+pub struct Trajectory {
+    pub pathway: String,
+    pub model: String,
+    pub years: Vec<i32>,
+    pub emissions: Vec<f64>,
+    pub temperatures: Vec<f64>,
+}
+
+impl Trajectory {
+    /// Temperature in the final year of the run.
+    pub fn final_temperature(&self) -> Option<f64> {
+        self.temperatures.last().copied()
+    }
+}
+
+/// Runs a pathway through the chosen emissions→temperature model.
+pub fn run(pathway: &Pathway, model: &dyn TemperatureModel) -> Trajectory {
+    Trajectory {
+        pathway: pathway.name.clone(),
+        model: model.name(),
+        years: pathway.years.clone(),
+        emissions: pathway.emissions.clone(),
+        temperatures: model.project(pathway),
+    }
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    #[test]
+    fn piecewise_interpolates_between_control_points() {
+        let pathway = Pathway::piecewise("test", &[(2020, 40.0), (2030, 20.0), (2040, 20.0), (2050, -10.0)]).unwrap();
+        assert_eq!(pathway.years, (2020..=2050).collect::<Vec<_>>());
+        assert_eq!(pathway.emissions[0], 40.0);
+        assert_eq!(pathway.emissions[5], 30.0);
+        assert_eq!(pathway.emissions[15], 20.0);
+        assert_eq!(pathway.emissions[25], 5.0);
+        assert_eq!(pathway.emissions[30], -10.0);
+    }
+
+    #[test]
+    fn piecewise_needs_two_points_in_increasing_years() {
+        assert!(Pathway::piecewise("one point", &[(2020, 40.0)]).is_err());
+        assert!(Pathway::piecewise("unsorted", &[(2020, 40.0), (2050, 0.0), (2030, 20.0)]).is_err());
+        assert!(Pathway::piecewise("duplicate", &[(2020, 40.0), (2030, 20.0), (2030, 10.0)]).is_err());
+    }
+
+    #[test]
+    fn presets_run_from_start_to_the_end_year() {
+        for start in [2023, 2060, PRESET_END_YEAR - 1] {
+            let presets = presets(start, 40.0).unwrap();
+            assert_eq!(presets.len(), preset_points(start, 40.0).len(), "a preset went missing from {}", start);
+            for pathway in &presets {
+                assert_eq!((pathway.years[0], pathway.emissions[0]), (start, 40.0));
+                assert_eq!(*pathway.years.last().unwrap(), PRESET_END_YEAR);
+            }
+        }
+        let ssp1 = presets(2023, 40.0).unwrap().into_iter().find(|pathway| pathway.name == "SSP1-1.9").unwrap();
+        assert_eq!(ssp1.emissions[2050 - 2023], 0.0);
+
+        let error = presets(PRESET_END_YEAR, 40.0).unwrap_err();
+        assert!(error.to_string().starts_with("preset SSP1-1.9 from 2100"), "{}", error);
+    }
+}
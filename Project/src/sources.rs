@@ -0,0 +1,141 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{Column, Dataset, Schema};
+use std::error::Error;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const GISTEMP: &str = "./data/global-temperature.csv";
+pub const CARBON_EMISSIONS: &str = "./data/carbon-emissions.csv";
+pub const EMISSION_ANCHORS: &str = "./data/emission-anchors.csv";
+
+/// Yearly values keyed by year, in file order.
+pub type AnnualSeries = Vec<(i32, f64)>;
+
+//////////////////////////////////////// NASA GISTEMP ////////////////////////////////////////
+
+/// Which of the two GISTEMP temperature columns to use.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Smoothing {
+    Raw,    // No_Smoothing: the annual mean anomaly
+    Lowess, // Lowess(5): LOWESS smoothing with a 5-year window, as used by emission_temp_data.csv
+}
+
+impl Smoothing {
+    pub fn column(&self) -> &'static str {
+        match self {
+            Smoothing::Raw => "No_Smoothing",
+            Smoothing::Lowess => "Lowess(5)",
+        }
+    }
+}
+
+/// Schema of the NASA GISTEMP Land-Ocean Temperature Index export.
+pub fn gistemp_schema() -> Schema {
+    Schema::new(vec![
+        Column::integer("Year"),
+        Column::float(Smoothing::Raw.column()).unit("°C"),
+        Column::float(Smoothing::Lowess.column()).unit("°C"),
+    ])
+}
+
+/// Loads `global-temperature.csv` as downloaded from NASA.
+///
+/// The export opens with a quoted title and a dashed separator line before the real
+/// `Year,No_Smoothing,Lowess(5)` header, which a plain CSV reader would take for the header.
+pub fn load_gistemp(path: &str) -> Result<Dataset, Box<dyn Error>> {
+    Dataset::load_after_preamble(path, gistemp_schema(), "Year,")
+}
+
+/// Annual global temperature anomalies (°C, relative to 1951–1980) from a GISTEMP export.
+pub fn gistemp_series(path: &str, smoothing: Smoothing) -> Result<AnnualSeries, Box<dyn Error>> {
+    let dataset = load_gistemp(path)?;
+    annual(&dataset, smoothing.column())
+}
+
+//////////////////////////////////////// carbon emissions ////////////////////////////////////////
+
+/// Schema of `carbon-emissions.csv`: global CO2 emissions, decadal before 2010.
+pub fn carbon_emissions_schema() -> Schema {
+    Schema::new(vec![Column::integer("Year"), Column::float("Emissions").unit("GtCO₂")])
+}
+
+/// Global CO2 emissions (GtCO₂ per year) at the years listed in the file.
+pub fn carbon_emissions_series(path: &str) -> Result<AnnualSeries, Box<dyn Error>> {
+    let dataset = Dataset::load(path, carbon_emissions_schema())?;
+    annual(&dataset, "Emissions")
+}
+
+/// Extra emission values that are not in `carbon-emissions.csv`, with a `Note` citing the published source of each.
+///
+/// The merge pipeline lays these over the raw series before interpolating. Every anchor must come from a
+/// source outside this repository; one read back off `emission_temp_data.csv` would make the check circular.
+pub fn emission_anchor_series(path: &str) -> Result<AnnualSeries, Box<dyn Error>> {
+    let mut schema = carbon_emissions_schema();
+    schema.columns.push(Column::text("Note").optional());
+    let dataset = Dataset::load(path, schema)?;
+    annual(&dataset, "Emissions")
+}
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+fn annual(dataset: &Dataset, column: &str) -> Result<AnnualSeries, Box<dyn Error>> {
+    let years = dataset.integers("Year")?;
+    let values = dataset.floats(column)?;
+    Ok(years.into_iter().map(|year| year as i32).zip(values).collect())
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+    use crate::dataset::DatasetError;
+    use std::fs;
+
+    // The layout of the NASA export: a quoted title and a dashed line before the header
+    const GISTEMP_EXPORT: &str = "\"Land-Ocean Temperature Index (C)\n--------------------------------\"\nYear,No_Smoothing,Lowess(5)\n1880,-0.16,-0.09\n1881,-0.08,-0.13\n1882,-0.11,-0.16\n";
+
+    // Writes a fixture to the temporary directory, since the loaders take paths
+    fn fixture(name: &str, contents: &str) -> String {
+        let path = std::env::temp_dir().join(format!("climate-predict-{}-{}.csv", std::process::id(), name));
+        fs::write(&path, contents).unwrap();
+        path.to_string_lossy().into_owned()
+    }
+
+    #[test]
+    fn gistemp_preamble_is_skipped_and_lines_keep_their_numbers() {
+        for (name, contents) in [("gistemp-lf", GISTEMP_EXPORT.to_string()), ("gistemp-crlf", GISTEMP_EXPORT.replace('\n', "\r\n"))] {
+            let path = fixture(name, &contents);
+            let dataset = load_gistemp(&path).unwrap();
+            assert_eq!(dataset.integers("Year").unwrap(), vec![1880, 1881, 1882]);
+            assert_eq!((0..dataset.len()).map(|row| dataset.line(row)).collect::<Vec<_>>(), vec![4, 5, 6]);
+            fs::remove_file(path).unwrap();
+        }
+
+        let path = fixture("gistemp-invalid", &GISTEMP_EXPORT.replace("-0.13", "n/a"));
+        let error = load_gistemp(&path).unwrap_err();
+        let errors = &error.downcast_ref::<DatasetError>().unwrap().errors;
+        assert_eq!((errors[0].line, errors[0].column.as_deref()), (5, Some("Lowess(5)")));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn gistemp_without_its_header_is_rejected() {
+        let path = fixture("gistemp-headless", &GISTEMP_EXPORT.replace("Year,", "year,"));
+        assert!(load_gistemp(&path).unwrap_err().to_string().contains("no header line starting with 'Year,'"));
+        fs::remove_file(path).unwrap();
+    }
+
+    #[test]
+    fn smoothing_picks_its_column() {
+        let path = fixture("gistemp-smoothing", GISTEMP_EXPORT);
+        assert_eq!(gistemp_series(&path, Smoothing::Raw).unwrap(), vec![(1880, -0.16), (1881, -0.08), (1882, -0.11)]);
+        assert_eq!(gistemp_series(&path, Smoothing::Lowess).unwrap(), vec![(1880, -0.09), (1881, -0.13), (1882, -0.16)]);
+        fs::remove_file(path).unwrap();
+
+        // The checked-in export has the same layout
+        assert_eq!(gistemp_series(GISTEMP, Smoothing::Lowess).unwrap()[0], (1880, -0.09));
+        assert_eq!(gistemp_series(GISTEMP, Smoothing::Raw).unwrap()[0], (1880, -0.16));
+    }
+}
@@ -0,0 +1,428 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::dataset::{Column, Dataset, Schema};
+use crate::sources::AnnualSeries;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fmt;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const MONTHS: usize = 12;
+
+//////////////////////////////////////// periods ////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Resolution {
+    Monthly,
+    Annual,
+}
+
+/// The time a value refers to: a whole year or one month of it.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub enum Period {
+    Year(i32),
+    Month(i32, u32), // Year and month, 1 = January
+}
+
+impl Period {
+    /// Parses a period key.
+    ///
+    /// Accepts `YYYYMM` (`197301`), ISO months and dates (`1973-01`, `1973-01-15`, with any time part
+    /// ignored) and bare years (`1973`). Dates are truncated to their month.
+    pub fn parse(key: &str) -> Result<Self, String> {
+        let key = key.trim();
+        let invalid = || format!("'{}' is not a YYYY, YYYYMM or ISO date key", key);
+        let number = |digits: &str| -> Result<i64, String> {
+            if digits.is_empty() || !digits.bytes().all(|b| b.is_ascii_digit()) {
+                return Err(invalid());
+            }
+            digits.parse().map_err(|_| invalid())
+        };
+
+        let (year, month) = if key.contains('-') {
+            let date = key.split(['T', ' ']).next().unwrap_or(key);
+            let mut parts = date.split('-');
+            let year = number(parts.next().unwrap_or(""))?;
+            let month = number(parts.next().unwrap_or(""))?;
+            if let Some(day) = parts.next() {
+                let day = number(day)?;
+                if !(1..=31).contains(&day) || parts.next().is_some() {
+                    return Err(invalid());
+                }
+            }
+            (year, Some(month))
+        } else {
+            match key.len() {
+                4 => (number(key)?, None),
+                6 => (number(&key[..4])?, Some(number(&key[4..])?)),
+                _ => return Err(invalid()),
+            }
+        };
+
+        match month {
+            None => Ok(Period::Year(year as i32)),
+            Some(month) if (1..=MONTHS as i64).contains(&month) => Ok(Period::Month(year as i32, month as u32)),
+            Some(month) => Err(format!("'{}' has month {}, expected 01 to 12", key, month)),
+        }
+    }
+
+    pub fn year(&self) -> i32 {
+        match *self {
+            Period::Year(year) | Period::Month(year, _) => year,
+        }
+    }
+
+    pub fn resolution(&self) -> Resolution {
+        match self {
+            Period::Year(_) => Resolution::Annual,
+            Period::Month(..) => Resolution::Monthly,
+        }
+    }
+
+    /// Position on a continuous time axis for the models: the year itself for annual periods, and
+    /// the year plus the fraction of it already elapsed for months (March 1973 is 1973.1667).
+    pub fn decimal_year(&self) -> f64 {
+        match *self {
+            Period::Year(year) => year as f64,
+            Period::Month(year, month) => year as f64 + (month - 1) as f64 / MONTHS as f64,
+        }
+    }
+
+    /// The period immediately after this one at the same resolution.
+    pub fn next(&self) -> Self {
+        match *self {
+            Period::Year(year) => Period::Year(year + 1),
+            Period::Month(year, 12) => Period::Month(year + 1, 1),
+            Period::Month(year, month) => Period::Month(year, month + 1),
+        }
+    }
+}
+
+impl fmt::Display for Period {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Period::Year(year) => write!(f, "{}", year),
+            Period::Month(year, month) => write!(f, "{}-{:02}", year, month),
+        }
+    }
+}
+
+//////////////////////////////////////// series ////////////////////////////////////////
+
+/// How to combine or split values when changing resolution.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Aggregate {
+    Sum,  // Totals, such as emissions per month
+    Mean, // Rates and levels, such as temperatures or concentrations
+    Last, // The value at the end of the period, such as a cumulative count
+}
+
+/// Values keyed by period, all at the same resolution and sorted in time.
+#[derive(Debug, Clone)]
+pub struct TimeSeries {
+    pub name: String,
+    pub points: Vec<(Period, f64)>,
+}
+
+impl TimeSeries {
+    /// Builds a series from points in any order; fails on mixed resolutions or repeated periods.
+    pub fn new(name: &str, mut points: Vec<(Period, f64)>) -> Result<Self, Box<dyn Error>> {
+        points.sort_by_key(|&(period, _)| period);
+        if let Some(pair) = points.windows(2).find(|pair| pair[0].0.resolution() != pair[1].0.resolution()) {
+            return Err(format!("{} mixes annual and monthly periods ({} and {})", name, pair[0].0, pair[1].0).into());
+        }
+        if let Some(pair) = points.windows(2).find(|pair| pair[0].0 == pair[1].0) {
+            return Err(format!("{} has more than one value for {}", name, pair[0].0).into());
+        }
+        Ok(Self { name: name.to_string(), points })
+    }
+
+    /// Loads a series from a CSV file with a period key column and a value column.
+    ///
+    /// Args:
+    ///     path: Path to the CSV file.
+    ///     key_column: Column holding `YYYYMM`, ISO date or year keys.
+    ///     value_column: Numeric column to load.
+    ///
+    /// Returns:
+    ///     The series, named after the value column; invalid keys are reported with their line number.
+    pub fn load(path: &str, key_column: &str, value_column: &str) -> Result<Self, Box<dyn Error>> {
+        let schema = Schema::new(vec![Column::text(key_column), Column::float(value_column)]);
+        let dataset = Dataset::load(path, schema)?;
+        let values = dataset.floats(value_column)?;
+
+        let mut points = Vec::with_capacity(dataset.len());
+        for (row, (key, value)) in dataset.texts(key_column)?.into_iter().zip(values).enumerate() {
+            let period = Period::parse(key).map_err(|message| format!("{}, line {}: {}", path, dataset.line(row), message))?;
+            points.push((period, value));
+        }
+        Self::new(value_column, points)
+    }
+
+    pub fn len(&self) -> usize {
+        self.points.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.points.is_empty()
+    }
+
+    /// Resolution of the series; an empty series counts as annual.
+    pub fn resolution(&self) -> Resolution {
+        self.points.first().map_or(Resolution::Annual, |(period, _)| period.resolution())
+    }
+
+    pub fn values(&self) -> Vec<f64> {
+        self.points.iter().map(|&(_, value)| value).collect()
+    }
+
+    /// Time axis for fitting models, see Period::decimal_year.
+    pub fn decimal_years(&self) -> Vec<f64> {
+        self.points.iter().map(|(period, _)| period.decimal_year()).collect()
+    }
+
+    /// An annual series in the `(year, value)` form used by the source importers.
+    pub fn annual_series(&self) -> Result<AnnualSeries, Box<dyn Error>> {
+        if self.resolution() != Resolution::Annual {
+            return Err(format!("{} is monthly; resample it to annual first", self.name).into());
+        }
+        Ok(self.points.iter().map(|&(period, value)| (period.year(), value)).collect())
+    }
+
+    /// Converts the series to another resolution.
+    ///
+    /// Monthly to annual combines the twelve months of each year with `aggregate`. Years without all
+    /// twelve months (such as a series that stops in July) are dropped, since a sum or mean of part of
+    /// a year is not comparable with the others.
+    ///
+    /// Annual to monthly spreads each year over its months: Sum divides the total evenly so the
+    /// annual sums are preserved, while Mean and Last repeat the annual value in every month.
+    pub fn resample(&self, to: Resolution, aggregate: Aggregate) -> Result<Self, Box<dyn Error>> {
+        let points = match (self.resolution(), to) {
+            (from, to) if from == to => self.points.clone(),
+            (Resolution::Monthly, Resolution::Annual) => {
+                let mut years: BTreeMap<i32, Vec<f64>> = BTreeMap::new();
+                for &(period, value) in &self.points {
+                    years.entry(period.year()).or_default().push(value);
+                }
+                years.into_iter()
+                    .filter(|(_, months)| months.len() == MONTHS)
+                    .map(|(year, months)| {
+                        let value = match aggregate {
+                            Aggregate::Sum => months.iter().sum(),
+                            Aggregate::Mean => months.iter().sum::<f64>() / MONTHS as f64,
+                            Aggregate::Last => months[MONTHS - 1],
+                        };
+                        (Period::Year(year), value)
+                    })
+                    .collect()
+            }
+            _ => self.points.iter()
+                .flat_map(|&(period, value)| {
+                    let monthly = if aggregate == Aggregate::Sum { value / MONTHS as f64 } else { value };
+                    (1..=MONTHS as u32).map(move |month| (Period::Month(period.year(), month), monthly))
+                })
+                .collect(),
+        };
+        Self::new(&self.name, points)
+    }
+
+    /// Splits a monthly series into trend, seasonal cycle and residual (classical additive decomposition).
+    ///
+    /// The trend is a centred 2×12 moving average, so it is undefined for the first and last six
+    /// months. The seasonal cycle is the average detrended value of each calendar month, shifted to
+    /// sum to zero over the year.
+    ///
+    /// Returns:
+    ///     The decomposition, or an error if the series is not monthly, has gaps, or covers fewer than two years.
+    pub fn decompose(&self) -> Result<Decomposition, Box<dyn Error>> {
+        if self.resolution() != Resolution::Monthly {
+            return Err(format!("{} must be monthly to decompose", self.name).into());
+        }
+        if self.len() < 2 * MONTHS {
+            return Err(format!("{} needs at least {} months to decompose, found {}", self.name, 2 * MONTHS, self.len()).into());
+        }
+        if let Some(pair) = self.points.windows(2).find(|pair| pair[0].0.next() != pair[1].0) {
+            return Err(format!("{} has a gap between {} and {}", self.name, pair[0].0, pair[1].0).into());
+        }
+
+        let values = self.values();
+        let half = MONTHS / 2;
+        let trend: Vec<Option<f64>> = (0..values.len())
+            .map(|i| {
+                if i < half || i + half >= values.len() {
+                    return None;
+                }
+                // 2×12 centred moving average: the eleven months within five of i, plus half weight on the months six away
+                let window: f64 = values[i + 1 - half..i + half].iter().sum();
+                Some((window + 0.5 * (values[i - half] + values[i + half])) / MONTHS as f64)
+            })
+            .collect();
+
+        let month_of = |i: usize| match self.points[i].0 {
+            Period::Month(_, month) => month as usize - 1,
+            Period::Year(_) => unreachable!("checked monthly above"),
+        };
+        let mut totals = [0.0; MONTHS];
+        let mut counts = [0usize; MONTHS];
+        for (i, level) in trend.iter().enumerate() {
+            if let Some(level) = level {
+                totals[month_of(i)] += values[i] - level;
+                counts[month_of(i)] += 1;
+            }
+        }
+        let mut cycle: Vec<f64> = totals.iter().zip(counts).map(|(total, count)| total / count as f64).collect();
+        let shift = cycle.iter().sum::<f64>() / MONTHS as f64;
+        cycle.iter_mut().for_each(|effect| *effect -= shift);
+
+        let seasonal: Vec<f64> = (0..values.len()).map(|i| cycle[month_of(i)]).collect();
+        let residual = (0..values.len()).map(|i| trend[i].map(|level| values[i] - level - seasonal[i])).collect();
+
+        Ok(Decomposition { periods: self.points.iter().map(|&(period, _)| period).collect(), values, trend, seasonal, residual })
+    }
+}
+
+//////////////////////////////////////// decomposition ////////////////////////////////////////
+
+/// Output of TimeSeries::decompose: value = trend + seasonal + residual wherever the trend is defined.
+#[derive(Debug, Clone)]
+pub struct Decomposition {
+    pub periods: Vec<Period>,
+    pub values: Vec<f64>,
+    pub trend: Vec<Option<f64>>,
+    pub seasonal: Vec<f64>,
+    pub residual: Vec<Option<f64>>,
+}
+
+impl Decomposition {
+    /// Seasonal effect of each calendar month, January first.
+    pub fn cycle(&self) -> [f64; MONTHS] {
+        let mut cycle = [0.0; MONTHS];
+        for (period, &effect) in self.periods.iter().zip(&self.seasonal) {
+            if let Period::Month(_, month) = period {
+                cycle[*month as usize - 1] = effect;
+            }
+        }
+        cycle
+    }
+
+    /// The original values with the seasonal cycle removed, defined for every month.
+    ///
+    /// This is the series to fit a trend model on, since the models here have no notion of seasons.
+    pub fn seasonally_adjusted(&self, name: &str) -> TimeSeries {
+        let points = self.periods.iter().zip(self.values.iter().zip(&self.seasonal))
+            .map(|(&period, (value, effect))| (period, value - effect))
+            .collect();
+        TimeSeries { name: name.to_string(), points }
+    }
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CYCLE: [f64; MONTHS] = [3.0, 2.0, 1.0, 0.0, -1.0, -2.0, -3.0, -2.0, -1.0, 0.0, 1.0, 2.0]; // Sums to zero
+
+    // Four years of a linear trend plus CYCLE, starting in January 2000
+    fn seasonal_series() -> TimeSeries {
+        let points = (0..4 * MONTHS)
+            .map(|i| (Period::Month(2000 + (i / MONTHS) as i32, (i % MONTHS) as u32 + 1), 10.0 + 0.5 * i as f64 + CYCLE[i % MONTHS]))
+            .collect();
+        TimeSeries::new("co2", points).unwrap()
+    }
+
+    #[test]
+    fn parse_accepts_year_month_and_date_keys() {
+        assert_eq!(Period::parse("197301"), Ok(Period::Month(1973, 1)));
+        assert_eq!(Period::parse(" 201607 "), Ok(Period::Month(2016, 7)));
+        assert_eq!(Period::parse("1973-12"), Ok(Period::Month(1973, 12)));
+        assert_eq!(Period::parse("1973-01-15"), Ok(Period::Month(1973, 1)));
+        assert_eq!(Period::parse("1973-01-15T08:00:00"), Ok(Period::Month(1973, 1)));
+        assert_eq!(Period::parse("1973-01-15 08:00"), Ok(Period::Month(1973, 1)));
+        assert_eq!(Period::parse("1880"), Ok(Period::Year(1880)));
+    }
+
+    #[test]
+    fn parse_rejects_malformed_keys() {
+        for key in ["", "73", "19731", "1973011", "197300", "197313", "1973-13", "1973-00", "1973-1a", "1973-01-32", "1973-01-00",
+            "1973-01-15-01", "1973-", "-01", "abcd", "1973.5", "+1973"] {
+            assert!(Period::parse(key).is_err(), "{:?} parsed", key);
+        }
+        assert_eq!(Period::parse("197313").unwrap_err(), "'197313' has month 13, expected 01 to 12");
+    }
+
+    #[test]
+    fn decompose_recovers_a_linear_trend_and_its_cycle() {
+        let decomposition = seasonal_series().decompose().unwrap();
+
+        assert!(decomposition.trend[..6].iter().chain(&decomposition.trend[42..]).all(Option::is_none));
+        for (i, level) in decomposition.trend.iter().enumerate().filter_map(|(i, level)| level.map(|level| (i, level))) {
+            assert!((level - (10.0 + 0.5 * i as f64)).abs() < 1e-9, "trend at {} is {}", i, level);
+        }
+        for (effect, expected) in decomposition.cycle().iter().zip(CYCLE) {
+            assert!((effect - expected).abs() < 1e-9);
+        }
+        assert!(decomposition.residual.iter().flatten().all(|residual| residual.abs() < 1e-9));
+    }
+
+    #[test]
+    fn seasonally_adjusted_values_follow_the_trend() {
+        let series = seasonal_series();
+        let adjusted = series.decompose().unwrap().seasonally_adjusted("adjusted");
+        assert_eq!(adjusted.len(), series.len());
+        for (i, (period, value)) in adjusted.points.iter().enumerate() {
+            assert!((value - (10.0 + 0.5 * i as f64)).abs() < 1e-9, "{} is {}", period, value);
+        }
+        assert_eq!(adjusted.decimal_years()[14], 2001.0 + 2.0 / 12.0);
+    }
+
+    #[test]
+    fn load_reads_the_monthly_co2_series() {
+        let series = TimeSeries::load("./data/co2.csv", "YYYYMM", "Value").unwrap();
+        assert_eq!(series.name, "Value");
+        assert_eq!(series.resolution(), Resolution::Monthly);
+        assert_eq!(series.len(), 523);
+        assert_eq!(series.points[0], (Period::Month(1973, 1), 72.076));
+        assert_eq!(series.points[522].0, Period::Month(2016, 7));
+        assert!(series.decompose().is_ok());
+        assert!(TimeSeries::load("./data/co2.csv", "Month", "Value").is_err());
+    }
+
+    #[test]
+    fn load_reports_the_line_of_an_invalid_key() {
+        let path = std::env::temp_dir().join(format!("climate-predict-{}-monthly.csv", std::process::id()));
+        std::fs::write(&path, "YYYYMM,Value\r\n197301,72.1\r\n197313,64.4\r\n").unwrap();
+        let error = TimeSeries::load(path.to_str().unwrap(), "YYYYMM", "Value").unwrap_err().to_string();
+        std::fs::remove_file(&path).unwrap();
+        assert!(error.ends_with("line 3: '197313' has month 13, expected 01 to 12"), "{}", error);
+    }
+
+    #[test]
+    fn decompose_rejects_gaps() {
+        let mut series = seasonal_series();
+        series.points.remove(20);
+        assert!(series.decompose().is_err());
+    }
+
+    #[test]
+    fn resample_aggregates_full_years_and_spreads_totals() {
+        let mut series = seasonal_series();
+        series.points.truncate(3 * MONTHS + 7); // 2003 stops in July
+
+        let annual = series.resample(Resolution::Annual, Aggregate::Sum).unwrap();
+        assert_eq!(annual.len(), 3);
+        assert_eq!(annual.points[0], (Period::Year(2000), 12.0 * 10.0 + 0.5 * 66.0));
+        let mean = series.resample(Resolution::Annual, Aggregate::Mean).unwrap();
+        assert_eq!(mean.points[1].1, annual.points[1].1 / MONTHS as f64);
+        let last = series.resample(Resolution::Annual, Aggregate::Last).unwrap();
+        assert_eq!(last.points[2].1, series.points[35].1);
+
+        let monthly = annual.resample(Resolution::Monthly, Aggregate::Sum).unwrap();
+        assert_eq!(monthly.len(), 3 * MONTHS);
+        assert_eq!(monthly.points[13], (Period::Month(2001, 2), annual.points[1].1 / MONTHS as f64));
+        assert_eq!(monthly.resample(Resolution::Annual, Aggregate::Sum).unwrap().points, annual.points);
+    }
+}
@@ -0,0 +1,80 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use climate_predict::footprint;
+use climate_predict::persist::{self, Artifact, Metrics, SavedModel};
+use climate_predict::pipeline::Stage;
+use climate_predict::regression::GradientDescent;
+use climate_predict::training::{self, Algorithm, Hyperparameters};
+use std::env;
+use std::error::Error;
+use std::ops::ControlFlow;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const DATA: &str = "./data/emission_temp_data.csv";
+const USAGE: &str = "usage: train [--forest] [DIR]";
+
+//////////////////////////////////////// helper functions ////////////////////////////////////////
+
+/// Saves a model as the next version of `name` in `directory`.
+fn publish(directory: &str, name: &str, source: &str, metrics: Metrics, model: SavedModel) -> Result<(), Box<dyn Error>> {
+    let version = persist::next_version(directory, name)?;
+    let path = Artifact::new(name, version, source, metrics, model).save(directory)?;
+    println!("  saved {} version {} to {}", name, version, path);
+    Ok(())
+}
+
+//////////////////////////////////////// main ////////////////////////////////////////
+
+fn main() -> Result<(), Box<dyn Error>> {
+    let mut directory = persist::MODELS.to_string();
+    let mut forest = false;
+    for arg in env::args().skip(1) {
+        match arg.as_str() {
+            "--forest" => forest = true,
+            flag if flag.starts_with("--") => return Err(format!("unexpected option '{}'\n{}", flag, USAGE).into()),
+            path => directory = path.to_string(),
+        }
+    }
+
+    // The same emissions polynomial under each kind of temperature model, served side by side
+    let line = GradientDescent { epochs: training::EPOCHS, learning_rate: training::LEARNING_RATE };
+    let neural = GradientDescent { epochs: training::NEURAL_EPOCHS, learning_rate: training::LEARNING_RATE };
+    let mut models = vec![
+        Hyperparameters::Climate { degree: persist::DEGREE, algorithm: Algorithm::Linear, gradient_descent: line },
+        Hyperparameters::Climate { degree: persist::DEGREE, algorithm: Algorithm::Polynomial, gradient_descent: line },
+        Hyperparameters::Climate { degree: persist::DEGREE, algorithm: Algorithm::Neural, gradient_descent: neural },
+        Hyperparameters::Footprint { algorithm: Algorithm::Linear, trees: training::TREES, max_depth: None },
+    ];
+    // The linear footprint model stays small on disk; a saved forest holds every tree, so it is opt-in
+    if forest {
+        models.push(Hyperparameters::Footprint { algorithm: Algorithm::Forest, trees: training::TREES, max_depth: None });
+    }
+
+    for hyperparameters in models {
+        let name = hyperparameters.default_name();
+        let data = match hyperparameters {
+            Hyperparameters::Climate { .. } => DATA,
+            Hyperparameters::Footprint { .. } => footprint::DATA,
+        };
+        let (model, metrics) = hyperparameters.train(data, |_| ControlFlow::Continue(()))?;
+        match &model {
+            SavedModel::Climate(climate) => {
+                println!("Trained {} on {} ({}–{})", name, climate.source, climate.first_year, climate.last_year);
+                println!("  emissions: polynomial (degree {}) in the year, residual σ {:.3} GtCO₂",
+                    climate.emissions.degree(), climate.emissions.residual_variance.sqrt());
+                println!("  temperature: {} in emissions, residual σ {:.3} °C", climate.temperature.name(), climate.temperature.residual_std());
+            }
+            SavedModel::Footprint(footprint) => {
+                println!("Trained the {} footprint model on {} records from {}: RMSE {:.1} kg CO₂, R² {:.3}",
+                    footprint.name(), metrics["records"], data, metrics["train_rmse"], metrics["train_r2"]);
+            }
+        }
+        publish(&directory, name, data, metrics, model)?;
+    }
+
+    println!("Models written to {}", directory);
+    Ok(())
+}
+
+// cargo run --bin train
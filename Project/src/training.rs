@@ -0,0 +1,246 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::footprint::{self, FootprintModel};
+use crate::persist::{self, ClimateModel, Metrics, SavedModel};
+use crate::regression::GradientDescent;
+use serde::Serialize;
+use smartcore::ensemble::random_forest_regressor::RandomForestRegressorParameters;
+use std::error::Error;
+use std::fmt;
+use std::ops::ControlFlow;
+use std::str::FromStr;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const EPOCHS: usize = 500; // Enough for the temperature line to converge at LEARNING_RATE
+pub const NEURAL_EPOCHS: usize = 5000; // The temperature network needs longer at the same rate
+pub const LEARNING_RATE: f64 = 0.1;
+pub const TREES: u16 = 100; // smartcore's default forest size
+
+//////////////////////////////////////// hyperparameters ////////////////////////////////////////
+
+/// Estimator of a model: climate temperature models are linear, polynomial or neural, footprint
+/// models linear or forest.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "lowercase")]
+pub enum Algorithm {
+    Linear,
+    Polynomial,
+    Forest,
+    Neural,
+}
+
+impl Algorithm {
+    /// Whether a model for `task` (`climate` or `footprint`) can use this estimator.
+    pub fn fits(&self, task: &str) -> bool {
+        match task {
+            "climate" => matches!(self, Algorithm::Linear | Algorithm::Polynomial | Algorithm::Neural),
+            "footprint" => matches!(self, Algorithm::Linear | Algorithm::Forest),
+            _ => false,
+        }
+    }
+}
+
+impl fmt::Display for Algorithm {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(match self {
+            Algorithm::Linear => "linear",
+            Algorithm::Polynomial => "polynomial",
+            Algorithm::Forest => "forest",
+            Algorithm::Neural => "neural",
+        })
+    }
+}
+
+impl FromStr for Algorithm {
+    type Err = String;
+
+    fn from_str(s: &str) -> Result<Self, Self::Err> {
+        match s {
+            "linear" => Ok(Algorithm::Linear),
+            "polynomial" => Ok(Algorithm::Polynomial),
+            "forest" => Ok(Algorithm::Forest),
+            "neural" => Ok(Algorithm::Neural),
+            _ => Err(format!("unknown algorithm '{}' (expected linear, polynomial, forest or neural)", s)),
+        }
+    }
+}
+
+/// A model to train and its hyperparameters, serialized with a `task` tag.
+#[derive(Debug, Clone, Serialize)]
+#[serde(tag = "task", rename_all = "lowercase")]
+pub enum Hyperparameters {
+    /// Year → emissions polynomial of `degree`, and an emissions → temperature model of kind
+    /// `algorithm`; `gradient_descent` applies to the line and the network.
+    Climate { degree: usize, algorithm: Algorithm, gradient_descent: GradientDescent },
+    /// Footprint model from questionnaire answers; `trees` and `max_depth` apply to forests.
+    Footprint { algorithm: Algorithm, trees: u16, max_depth: Option<u16> },
+}
+
+impl Hyperparameters {
+    /// What the model predicts: `climate` or `footprint`, as SavedModel::task.
+    pub fn task(&self) -> &'static str {
+        match self {
+            Hyperparameters::Climate { .. } => "climate",
+            Hyperparameters::Footprint { .. } => "footprint",
+        }
+    }
+
+    /// Epochs the training reports; polynomial and footprint fits are a single step.
+    pub fn epochs(&self) -> usize {
+        match self {
+            Hyperparameters::Climate { algorithm: Algorithm::Polynomial, .. } => 1,
+            Hyperparameters::Climate { gradient_descent, .. } => gradient_descent.epochs,
+            Hyperparameters::Footprint { .. } => 1,
+        }
+    }
+
+    /// Name the trained model is published under when none is given: the default artifact of its
+    /// kind in persist.
+    pub fn default_name(&self) -> &'static str {
+        match self {
+            Hyperparameters::Climate { algorithm: Algorithm::Polynomial, .. } => persist::CLIMATE_POLYNOMIAL,
+            Hyperparameters::Climate { algorithm: Algorithm::Neural, .. } => persist::CLIMATE_NEURAL,
+            Hyperparameters::Climate { .. } => persist::CLIMATE,
+            Hyperparameters::Footprint { algorithm: Algorithm::Forest, .. } => persist::FOREST,
+            Hyperparameters::Footprint { .. } => persist::FOOTPRINT,
+        }
+    }
+
+    /// Trains the model on the CSV file at `data`.
+    ///
+    /// Args:
+    ///     data: The emissions/temperature dataset for climate models, the questionnaire answers for footprint models.
+    ///     on_epoch: Called after every epoch with its progress; returning ControlFlow::Break stops the training.
+    ///
+    /// Returns:
+    ///     The model with its training metrics, or an error if it could not be trained or was stopped.
+    pub fn train<F>(&self, data: &str, mut on_epoch: F) -> Result<(SavedModel, Metrics), Box<dyn Error>>
+    where
+        F: FnMut(Progress) -> ControlFlow<()>,
+    {
+        let epochs = self.epochs();
+        match *self {
+            Hyperparameters::Climate { degree, algorithm, gradient_descent } => {
+                let climate = ClimateModel::train_with(data, degree, algorithm, gradient_descent, |epoch, loss| on_epoch(Progress { epoch, epochs, loss }))?;
+                let metrics = climate.evaluate(data)?;
+                Ok((SavedModel::Climate(climate), metrics))
+            }
+            Hyperparameters::Footprint { algorithm, trees, max_depth } => {
+                let records = footprint::load_records(data)?;
+                let model = match algorithm {
+                    Algorithm::Linear => FootprintModel::fit_linear(&records)?,
+                    Algorithm::Forest => {
+                        let mut params = RandomForestRegressorParameters::default().with_n_trees(trees.into());
+                        params.max_depth = max_depth;
+                        FootprintModel::fit_random_forest(&records, params)?
+                    }
+                    Algorithm::Polynomial | Algorithm::Neural => return Err("a footprint model is linear or forest".into()),
+                };
+                let (rmse, r2) = model.evaluate(&records)?;
+                if on_epoch(Progress { epoch: 1, epochs, loss: rmse * rmse }).is_break() {
+                    return Err("training stopped".into());
+                }
+                let metrics = Metrics::from([("train_rmse".to_string(), rmse), ("train_r2".to_string(), r2), ("records".to_string(), records.len() as f64)]);
+                Ok((SavedModel::Footprint(model), metrics))
+            }
+        }
+    }
+}
+
+//////////////////////////////////////// progress ////////////////////////////////////////
+
+/// Where a training run is: `epoch` of `epochs` done, with the training mean squared error.
+#[derive(Debug, Clone, Copy, Serialize)]
+pub struct Progress {
+    pub epoch: usize,
+    pub epochs: usize,
+    pub loss: f64,
+}
+
+//////////////////////////////////////// tests ////////////////////////////////////////
+
+#[cfg(test)]
+mod tests {
+    use super::*;
+
+    const CLIMATE_DATA: &str = crate::merge::OUTPUT;
+
+    fn climate(algorithm: Algorithm, epochs: usize) -> Hyperparameters {
+        Hyperparameters::Climate { degree: persist::DEGREE, algorithm, gradient_descent: GradientDescent { epochs, learning_rate: LEARNING_RATE } }
+    }
+
+    #[test]
+    fn algorithms_parse_from_their_names_and_fit_their_tasks() {
+        for algorithm in [Algorithm::Linear, Algorithm::Polynomial, Algorithm::Forest, Algorithm::Neural] {
+            assert_eq!(algorithm.to_string().parse::<Algorithm>(), Ok(algorithm));
+        }
+        assert!("lasso".parse::<Algorithm>().is_err());
+
+        assert!(Algorithm::Neural.fits("climate") && !Algorithm::Neural.fits("footprint"));
+        assert!(Algorithm::Forest.fits("footprint") && !Algorithm::Forest.fits("climate"));
+        assert!(Algorithm::Linear.fits("climate") && Algorithm::Linear.fits("footprint"));
+        assert!(!Algorithm::Linear.fits("weather"));
+    }
+
+    #[test]
+    fn hyperparameters_name_their_default_artifact_and_epochs() {
+        assert_eq!((climate(Algorithm::Linear, 40).default_name(), climate(Algorithm::Linear, 40).epochs()), (persist::CLIMATE, 40));
+        assert_eq!((climate(Algorithm::Polynomial, 40).default_name(), climate(Algorithm::Polynomial, 40).epochs()), (persist::CLIMATE_POLYNOMIAL, 1));
+        assert_eq!((climate(Algorithm::Neural, 40).default_name(), climate(Algorithm::Neural, 40).epochs()), (persist::CLIMATE_NEURAL, 40));
+
+        let forest = Hyperparameters::Footprint { algorithm: Algorithm::Forest, trees: TREES, max_depth: None };
+        assert_eq!((forest.task(), forest.default_name(), forest.epochs()), ("footprint", persist::FOREST, 1));
+    }
+
+    #[test]
+    fn climate_training_reports_every_epoch_and_its_metrics() {
+        let mut progress = Vec::new();
+        let (model, metrics) = climate(Algorithm::Linear, 40).train(CLIMATE_DATA, |p| {
+            progress.push(p);
+            ControlFlow::Continue(())
+        }).unwrap();
+
+        assert_eq!(model.task(), "climate");
+        assert_eq!(progress.len(), 40);
+        assert!(progress.iter().enumerate().all(|(i, p)| p.epoch == i + 1 && p.epochs == 40));
+        assert!(progress[39].loss < progress[0].loss);
+        assert!(metrics["temperature_train_rmse"] > 0.0 && metrics["rows"] > 0.0);
+    }
+
+    #[test]
+    fn stopping_in_an_epoch_ends_the_training() {
+        let mut epochs = 0;
+        let stopped = climate(Algorithm::Linear, 40).train(CLIMATE_DATA, |p| {
+            epochs = p.epoch;
+            if p.epoch == 5 { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+        });
+        assert!(stopped.is_err());
+        assert_eq!(epochs, 5);
+
+        let polynomial = climate(Algorithm::Polynomial, 40).train(CLIMATE_DATA, |_| ControlFlow::Break(()));
+        assert!(polynomial.is_err());
+    }
+
+    #[test]
+    fn algorithms_of_another_task_are_rejected() {
+        assert!(climate(Algorithm::Forest, 40).train(CLIMATE_DATA, |_| ControlFlow::Continue(())).is_err());
+        let neural_footprint = Hyperparameters::Footprint { algorithm: Algorithm::Neural, trees: TREES, max_depth: None };
+        assert!(neural_footprint.train(footprint::DATA, |_| ControlFlow::Continue(())).is_err());
+    }
+
+    #[test]
+    fn footprint_training_reports_one_step() {
+        let linear = Hyperparameters::Footprint { algorithm: Algorithm::Linear, trees: TREES, max_depth: None };
+        let mut progress = Vec::new();
+        let (model, metrics) = linear.train(footprint::DATA, |p| {
+            progress.push(p);
+            ControlFlow::Continue(())
+        }).unwrap();
+
+        assert_eq!(model.task(), "footprint");
+        assert_eq!(progress.len(), 1);
+        assert!((progress[0].loss - metrics["train_rmse"].powi(2)).abs() < 1e-6);
+        assert!(metrics["train_r2"] > 0.5);
+    }
+}
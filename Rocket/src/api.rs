@@ -0,0 +1,1019 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::models::{Models, Selector};
+use crate::projection::PROJECTION;
+use climate_predict::behavior::{Behavior, Breakdown, Catalogue, Frequency};
+use climate_predict::energy_balance::EnergyBalanceModel;
+use climate_predict::footprint::{self, Record};
+use climate_predict::persist::Metadata;
+use climate_predict::pipeline::Stage;
+use climate_predict::plot::Z_95;
+use climate_predict::regression::Prediction;
+use climate_predict::scenario::{self, Pathway, TemperatureModel, PRESET_END_YEAR};
+use rocket::http::Status;
+use rocket::response::{self, Responder};
+use rocket::serde::json::{self, json, Json, Value};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::{Request, Route, State};
+use std::ops::RangeInclusive;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const BASE: &str = "/api/v1";
+const YEARS: RangeInclusive<i32> = 1800..=2200; // Years the emissions endpoint accepts
+const MAX_EMISSIONS: f64 = 1000.0; // GtCO₂ per year; far beyond any plausible global total
+const MONTHS: f64 = 12.0;
+
+pub fn routes() -> Vec<Route> {
+    routes![predict_temperature, predict_emissions, predict_footprint, behaviors, predict_behavior, scenarios, predict_scenario, models, openapi]
+}
+
+//////////////////////////////////////// errors ////////////////////////////////////////
+
+/// One problem with a request; `field` is absent when the body as a whole could not be read.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct FieldError {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    pub field: Option<String>,
+    pub message: String,
+}
+
+/// JSON error body returned with every 4xx/5xx status under BASE.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ErrorBody {
+    pub error: String,
+    pub errors: Vec<FieldError>,
+}
+
+/// An error response: its status code with an ErrorBody.
+#[derive(Debug)]
+pub struct ApiError {
+    pub status: Status,
+    pub body: ErrorBody,
+}
+
+impl ApiError {
+    pub(crate) fn validation(errors: Vec<FieldError>) -> Self {
+        Self { status: Status::UnprocessableEntity, body: ErrorBody { error: "validation".to_string(), errors } }
+    }
+
+    pub(crate) fn field(field: &str, message: String) -> Self {
+        Self::validation(vec![FieldError { field: Some(field.to_string()), message }])
+    }
+
+    /// The `model`/`version` query asked for a model that is not loaded.
+    fn unknown_model(message: String) -> Self {
+        Self { status: Status::NotFound, body: ErrorBody { error: "unknown model".to_string(), errors: vec![FieldError { field: Some("model".to_string()), message }] } }
+    }
+}
+
+impl<'r> Responder<'r, 'static> for ApiError {
+    fn respond_to(self, request: &'r Request<'_>) -> response::Result<'static> {
+        (self.status, Json(self.body)).respond_to(request)
+    }
+}
+
+/// Unwraps a JSON body, reporting unreadable or malformed bodies as structured errors.
+pub(crate) fn body<T>(data: Result<Json<T>, json::Error<'_>>) -> Result<T, ApiError> {
+    match data {
+        Ok(Json(value)) => Ok(value),
+        Err(json::Error::Io(error)) => Err(ApiError {
+            status: Status::BadRequest,
+            body: ErrorBody { error: "bad request".to_string(), errors: vec![FieldError { field: None, message: error.to_string() }] },
+        }),
+        Err(json::Error::Parse(_, error)) => Err(ApiError::validation(vec![FieldError { field: None, message: error.to_string() }])),
+    }
+}
+
+#[catch(default)]
+pub fn error(status: Status, request: &Request<'_>) -> ApiError {
+    let message = format!("{} {}", request.method(), request.uri());
+    ApiError {
+        status,
+        body: ErrorBody {
+            error: status.reason_lossy().to_lowercase(),
+            errors: vec![FieldError { field: None, message }],
+        },
+    }
+}
+
+//////////////////////////////////////// responses ////////////////////////////////////////
+
+/// A predicted value with the standard deviation of a new observation and its 95 % range.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Estimate {
+    pub mean: f64,
+    pub std: f64,
+    pub low: f64,
+    pub high: f64,
+    pub unit: &'static str,
+}
+
+impl Estimate {
+    fn new(prediction: Prediction, unit: &'static str) -> Self {
+        let (low, high) = prediction.interval(Z_95);
+        Self { mean: prediction.mean, std: prediction.std, low, high, unit }
+    }
+}
+
+//////////////////////////////////////// temperature ////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct TemperatureRequest {
+    pub emissions: f64, // Global emissions in GtCO₂ per year
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct TemperatureResponse {
+    pub emissions: f64,
+    pub temperature: Estimate, // Anomaly relative to 1951–1980
+    pub model: String,
+    pub artifact: String, // `name@version` of the model that answered
+}
+
+/// Emissions → temperature anomaly.
+#[post("/predict/temperature?<selector..>", data = "<data>")]
+pub fn predict_temperature(data: Result<Json<TemperatureRequest>, json::Error<'_>>, selector: Selector, models: &State<Models>) -> Result<Json<TemperatureResponse>, ApiError> {
+    let request = body(data)?;
+    if !(0.0..=MAX_EMISSIONS).contains(&request.emissions) {
+        return Err(ApiError::field("emissions", format!("must be between 0 and {} GtCO₂", MAX_EMISSIONS)));
+    }
+
+    let climate = models.climate(&selector).map_err(ApiError::unknown_model)?;
+    let temperature = climate.model.temperature.predict_with_uncertainty(request.emissions);
+    models.count(climate.id());
+    Ok(Json(TemperatureResponse {
+        emissions: request.emissions,
+        temperature: Estimate::new(temperature, "°C"),
+        model: climate.model.temperature.name(),
+        artifact: climate.id(),
+    }))
+}
+
+//////////////////////////////////////// emissions ////////////////////////////////////////
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct EmissionsRequest {
+    pub year: i32,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct EmissionsResponse {
+    pub year: i32,
+    pub emissions: Estimate,
+    pub model: String,
+    pub extrapolated: bool, // The year lies outside the training data
+    pub artifact: String,
+}
+
+/// Year → global emissions.
+#[post("/predict/emissions?<selector..>", data = "<data>")]
+pub fn predict_emissions(data: Result<Json<EmissionsRequest>, json::Error<'_>>, selector: Selector, models: &State<Models>) -> Result<Json<EmissionsResponse>, ApiError> {
+    let request = body(data)?;
+    if !YEARS.contains(&request.year) {
+        return Err(ApiError::field("year", format!("must be between {} and {}", YEARS.start(), YEARS.end())));
+    }
+
+    let entry = models.climate(&selector).map_err(ApiError::unknown_model)?;
+    let climate = &entry.model;
+    let emissions = climate.emissions.predict_with_uncertainty(request.year as f64);
+    models.count(entry.id());
+    Ok(Json(EmissionsResponse {
+        year: request.year,
+        emissions: Estimate::new(emissions, "GtCO₂"),
+        model: format!("polynomial (degree {})", climate.emissions.degree()),
+        extrapolated: !(climate.first_year..=climate.last_year).contains(&request.year),
+        artifact: entry.id(),
+    }))
+}
+
+//////////////////////////////////////// footprint ////////////////////////////////////////
+
+/// The answers of the footprint questionnaire (the columns of `Carbon Emission.csv`).
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct FootprintRequest {
+    pub body_type: String,
+    pub sex: String,
+    pub diet: String,
+    pub how_often_shower: String,
+    pub heating_energy_source: String,
+    pub transport: String,
+    #[serde(default)]
+    pub vehicle_type: String, // Empty without a vehicle
+    pub social_activity: String,
+    pub monthly_grocery_bill: f64,
+    pub frequency_of_traveling_by_air: String,
+    pub vehicle_monthly_distance_km: f64,
+    pub waste_bag_size: String,
+    pub waste_bag_weekly_count: i32,
+    pub how_long_tv_pc_daily_hour: i32,
+    pub how_many_new_clothes_monthly: i32,
+    pub how_long_internet_daily_hour: i32,
+    pub energy_efficiency: String,
+    #[serde(default)]
+    pub recycling: Vec<String>,
+    #[serde(default)]
+    pub cooking_with: Vec<String>,
+}
+
+impl FootprintRequest {
+    // Each multiple-choice answer with its field name and questionnaire header
+    fn answers(&self) -> Vec<(&'static str, &'static str, Vec<&str>)> {
+        vec![
+            ("body_type", "Body Type", vec![self.body_type.as_str()]),
+            ("sex", "Sex", vec![self.sex.as_str()]),
+            ("diet", "Diet", vec![self.diet.as_str()]),
+            ("how_often_shower", "How Often Shower", vec![self.how_often_shower.as_str()]),
+            ("heating_energy_source", "Heating Energy Source", vec![self.heating_energy_source.as_str()]),
+            ("transport", "Transport", vec![self.transport.as_str()]),
+            ("vehicle_type", "Vehicle Type", if self.vehicle_type.is_empty() { vec![] } else { vec![self.vehicle_type.as_str()] }),
+            ("social_activity", "Social Activity", vec![self.social_activity.as_str()]),
+            ("frequency_of_traveling_by_air", "Frequency of Traveling by Air", vec![self.frequency_of_traveling_by_air.as_str()]),
+            ("waste_bag_size", "Waste Bag Size", vec![self.waste_bag_size.as_str()]),
+            ("energy_efficiency", "Energy efficiency", vec![self.energy_efficiency.as_str()]),
+            ("recycling", "Recycling", self.recycling.iter().map(String::as_str).collect()),
+            ("cooking_with", "Cooking_With", self.cooking_with.iter().map(String::as_str).collect()),
+        ]
+    }
+
+    /// Every invalid answer, so a client can fix them all at once.
+    fn validate(&self) -> Vec<FieldError> {
+        let choices = footprint::choices();
+        let mut errors = Vec::new();
+        for (field, question, values) in self.answers() {
+            let allowed = choices.iter().find(|(name, _)| *name == question).map_or(&[][..], |(_, levels)| *levels);
+            for value in values.into_iter().filter(|value| !allowed.contains(value)) {
+                errors.push(FieldError { field: Some(field.to_string()), message: format!("unknown answer '{}' (expected one of: {})", value, allowed.join(", ")) });
+            }
+        }
+        let numbers = [
+            ("monthly_grocery_bill", self.monthly_grocery_bill),
+            ("vehicle_monthly_distance_km", self.vehicle_monthly_distance_km),
+            ("waste_bag_weekly_count", self.waste_bag_weekly_count as f64),
+            ("how_long_tv_pc_daily_hour", self.how_long_tv_pc_daily_hour as f64),
+            ("how_many_new_clothes_monthly", self.how_many_new_clothes_monthly as f64),
+            ("how_long_internet_daily_hour", self.how_long_internet_daily_hour as f64),
+        ];
+        for (field, value) in numbers {
+            if !(value.is_finite() && value >= 0.0) {
+                errors.push(FieldError { field: Some(field.to_string()), message: "must be zero or more".to_string() });
+            }
+        }
+        errors
+    }
+
+    fn into_record(self) -> Record {
+        Record {
+            body_type: self.body_type,
+            sex: self.sex,
+            diet: self.diet,
+            how_often_shower: self.how_often_shower,
+            heating_energy_source: self.heating_energy_source,
+            transport: self.transport,
+            vehicle_type: self.vehicle_type,
+            social_activity: self.social_activity,
+            monthly_grocery_bill: self.monthly_grocery_bill,
+            frequency_of_traveling_by_air: self.frequency_of_traveling_by_air,
+            vehicle_monthly_distance_km: self.vehicle_monthly_distance_km,
+            waste_bag_size: self.waste_bag_size,
+            waste_bag_weekly_count: self.waste_bag_weekly_count,
+            how_long_tv_pc_daily_hour: self.how_long_tv_pc_daily_hour,
+            how_many_new_clothes_monthly: self.how_many_new_clothes_monthly,
+            how_long_internet_daily_hour: self.how_long_internet_daily_hour,
+            energy_efficiency: self.energy_efficiency,
+            recycling: self.recycling,
+            cooking_with: self.cooking_with,
+            carbon_emission: 0.0, // The target; unknown for a new answer
+        }
+    }
+}
+
+/// How much one answer moved the prediction away from the average respondent.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Contribution {
+    pub question: String,
+    pub monthly_emissions: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct FootprintResponse {
+    pub monthly_emissions: f64,
+    pub annual_emissions: f64,
+    pub unit: &'static str,
+    pub baseline: f64,                    // Monthly emissions of the average respondent
+    pub contributions: Vec<Contribution>, // Largest first
+    pub model: &'static str,
+    pub artifact: String,
+}
+
+/// Questionnaire answers → a person's emissions.
+#[post("/predict/footprint?<selector..>", data = "<data>")]
+pub fn predict_footprint(data: Result<Json<FootprintRequest>, json::Error<'_>>, selector: Selector, models: &State<Models>) -> Result<Json<FootprintResponse>, ApiError> {
+    let request = body(data)?;
+    let errors = request.validate();
+    if !errors.is_empty() {
+        return Err(ApiError::validation(errors));
+    }
+
+    let footprint = models.footprint(&selector).map_err(ApiError::unknown_model)?;
+    let explanation = footprint.model.explain(&request.into_record())
+        .map_err(|error| ApiError::validation(vec![FieldError { field: None, message: error.to_string() }]))?;
+    models.count(footprint.id());
+    Ok(Json(FootprintResponse {
+        monthly_emissions: explanation.prediction,
+        annual_emissions: explanation.prediction * MONTHS,
+        unit: "kg CO₂",
+        baseline: explanation.baseline,
+        contributions: explanation.ranked().into_iter()
+            .map(|contribution| Contribution { question: contribution.feature.clone(), monthly_emissions: contribution.value })
+            .collect(),
+        model: footprint.model.name(),
+        artifact: footprint.id(),
+    }))
+}
+
+//////////////////////////////////////// behaviors ////////////////////////////////////////
+
+/// One choice of the duration list of a frequency.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct DurationOption {
+    pub value: u32,
+    pub label: String,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct FrequencyOptions {
+    pub name: &'static str,
+    pub label: &'static str,
+    pub durations: Vec<DurationOption>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct BehaviorsResponse {
+    pub behaviors: Vec<Behavior>,
+    pub frequencies: Vec<FrequencyOptions>,
+}
+
+/// The catalogue of behaviors with their emission factors, and the duration choices of every
+/// frequency; the form builds its lists from this.
+#[get("/behaviors")]
+pub fn behaviors(catalogue: &State<Catalogue>) -> Json<BehaviorsResponse> {
+    Json(BehaviorsResponse {
+        behaviors: catalogue.behaviors.clone(),
+        frequencies: frequency_options(),
+    })
+}
+
+/// Every frequency with the durations it accepts, as the form lists them.
+pub fn frequency_options() -> Vec<FrequencyOptions> {
+    Frequency::ALL.into_iter()
+        .map(|frequency| FrequencyOptions {
+            name: frequency.name(),
+            label: frequency.label(),
+            durations: frequency.durations().map(|value| DurationOption { value, label: frequency.describe(value) }).collect(),
+        })
+        .collect()
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct BehaviorRequest {
+    pub behavior: String,
+    pub frequency: String,
+    pub duration: u32, // In the unit of the frequency, e.g. hours per day for daily
+}
+
+/// A behavior's frequency × duration → its yearly emissions, with the steps in between.
+#[post("/predict/behavior", data = "<data>")]
+pub fn predict_behavior(data: Result<Json<BehaviorRequest>, json::Error<'_>>, catalogue: &State<Catalogue>) -> Result<Json<Breakdown>, ApiError> {
+    let request = body(data)?;
+    let behavior = catalogue.get(&request.behavior);
+    let frequency = request.frequency.parse::<Frequency>();
+
+    let mut errors = Vec::new();
+    if behavior.is_none() {
+        let message = format!("unknown behavior '{}' (expected one of: {})", request.behavior, catalogue.names().join(", "));
+        errors.push(FieldError { field: Some("behavior".to_string()), message });
+    }
+    if let Err(message) = &frequency {
+        errors.push(FieldError { field: Some("frequency".to_string()), message: message.clone() });
+    }
+    let (Some(behavior), Ok(frequency)) = (behavior, frequency) else {
+        return Err(ApiError::validation(errors));
+    };
+
+    let breakdown = behavior.annualize(frequency, request.duration).map_err(|error| ApiError::field("duration", error.to_string()))?;
+    Ok(Json(breakdown))
+}
+
+//////////////////////////////////////// scenarios ////////////////////////////////////////
+
+/// Emissions (GtCO₂ per year) in a year of a pathway.
+#[derive(Debug, Clone, Copy, Serialize, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct ControlPoint {
+    pub year: i32,
+    pub emissions: f64,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Preset {
+    pub name: &'static str,
+    pub points: Vec<ControlPoint>,
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ScenariosResponse {
+    pub start: i32,   // Last year of the training data, where every preset begins
+    pub end: i32,     // Where every preset ends
+    pub level: f64,   // Fitted emissions in `start`
+    pub unit: &'static str,
+    pub presets: Vec<Preset>,
+}
+
+/// The preset pathways (shaped like the IPCC SSPs) as control points, starting from the fitted
+/// emissions of the last year the models were trained on.
+#[get("/scenarios?<selector..>")]
+pub fn scenarios(selector: Selector, models: &State<Models>) -> Result<Json<ScenariosResponse>, ApiError> {
+    let climate = models.climate(&selector).map_err(ApiError::unknown_model)?;
+    let (start, level) = climate.model.start();
+    // Every preset or none: one the model's start year leaves without a pathway is reported, not left out
+    scenario::presets(start, level).map_err(|error| ApiError::field("model", format!("{} has no preset pathways: {}", climate.id(), error)))?;
+    Ok(Json(ScenariosResponse {
+        start,
+        end: PRESET_END_YEAR,
+        level,
+        unit: "GtCO₂",
+        presets: scenario::preset_points(start, level).into_iter()
+            .map(|(name, points)| Preset { name, points: points.into_iter().map(|(year, emissions)| ControlPoint { year, emissions }).collect() })
+            .collect(),
+    }))
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct ScenarioRequest {
+    #[serde(default)]
+    pub name: Option<String>,
+    pub points: Vec<ControlPoint>, // Increasing years; emissions are linear in between
+}
+
+impl ScenarioRequest {
+    /// The annual pathway through the points, or every problem with them.
+    pub(crate) fn pathway(&self) -> Result<Pathway, ApiError> {
+        let mut errors = Vec::new();
+        if self.points.len() < 2 {
+            errors.push(FieldError { field: Some("points".to_string()), message: "needs at least two points".to_string() });
+        }
+        for (i, point) in self.points.iter().enumerate() {
+            if !YEARS.contains(&point.year) {
+                errors.push(FieldError { field: Some(format!("points[{}].year", i)), message: format!("must be between {} and {}", YEARS.start(), YEARS.end()) });
+            } else if i > 0 && point.year <= self.points[i - 1].year {
+                errors.push(FieldError { field: Some(format!("points[{}].year", i)), message: "must be after the year of the previous point".to_string() });
+            }
+            if !(point.emissions.is_finite() && point.emissions.abs() <= MAX_EMISSIONS) {
+                errors.push(FieldError { field: Some(format!("points[{}].emissions", i)), message: format!("must be between -{0} and {0} GtCO₂", MAX_EMISSIONS) });
+            }
+        }
+        if !errors.is_empty() {
+            return Err(ApiError::validation(errors));
+        }
+
+        let points: Vec<(i32, f64)> = self.points.iter().map(|point| (point.year, point.emissions)).collect();
+        Pathway::piecewise(self.name.as_deref().unwrap_or("custom"), &points).map_err(|error| ApiError::field("points", error.to_string()))
+    }
+}
+
+/// Yearly values of a predicted series with their 95 % range.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct Band {
+    pub mean: Vec<f64>,
+    pub low: Vec<f64>,
+    pub high: Vec<f64>,
+    pub unit: &'static str,
+}
+
+impl Band {
+    fn new(predictions: &[Prediction], unit: &'static str) -> Self {
+        let (low, high) = predictions.iter().map(|prediction| prediction.interval(Z_95)).unzip();
+        Self { mean: predictions.iter().map(|prediction| prediction.mean).collect(), low, high, unit }
+    }
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ScenarioResponse {
+    pub name: String,
+    pub model: String,
+    pub years: Vec<i32>,
+    pub emissions: Vec<f64>, // GtCO₂ per year
+    pub temperature: Band,   // Anomaly relative to 1951–1980; the range is the scatter of the record around the model
+}
+
+/// An emissions pathway → the temperature anomaly of every year along it, projected by the
+/// energy-balance model after replaying the recorded emissions.
+///
+/// No registry model is involved, so there is no model selector and the projection is counted
+/// under PROJECTION.
+#[post("/scenario", data = "<data>")]
+pub fn predict_scenario(data: Result<Json<ScenarioRequest>, json::Error<'_>>, models: &State<Models>, projection: &State<EnergyBalanceModel>) -> Result<Json<ScenarioResponse>, ApiError> {
+    let pathway = body(data)?.pathway()?;
+    let temperatures = projection.project_with_uncertainty(&pathway);
+    models.count(PROJECTION.to_string());
+    Ok(Json(ScenarioResponse {
+        name: pathway.name,
+        model: projection.name(),
+        years: pathway.years,
+        emissions: pathway.emissions,
+        temperature: Band::new(&temperatures, "°C"),
+    }))
+}
+
+//////////////////////////////////////// models ////////////////////////////////////////
+
+/// A loaded model artifact.
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ModelInfo {
+    #[serde(flatten)]
+    pub metadata: Metadata,
+    pub task: &'static str, // `climate` or `footprint`
+    pub default: bool,      // Served when a request names no model
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct ModelsResponse {
+    pub models: Vec<ModelInfo>,
+}
+
+/// Every model the registry has loaded, with its metadata and training metrics; pass `model`
+/// and `version` as query parameters to a prediction to pick one.
+#[get("/models")]
+pub fn models(models: &State<Models>) -> Json<ModelsResponse> {
+    Json(ModelsResponse {
+        models: models.list().into_iter()
+            .map(|(task, metadata, default)| ModelInfo { metadata: (*metadata).clone(), task, default })
+            .collect(),
+    })
+}
+
+//////////////////////////////////////// openapi ////////////////////////////////////////
+
+/// OpenAPI 3 description of the endpoints under BASE.
+#[get("/openapi.json")]
+pub fn openapi(catalogue: &State<Catalogue>) -> Json<Value> {
+    Json(document(catalogue))
+}
+
+fn document(catalogue: &Catalogue) -> Value {
+    let choices = footprint::choices();
+    let levels = |question: &str| choices.iter().find(|(name, _)| *name == question).map_or(&[][..], |(_, levels)| *levels);
+    let choice = |question: &str| json!({ "type": "string", "enum": levels(question) });
+    let subset = |question: &str| json!({ "type": "array", "items": choice(question) });
+    let number = |description: &str| json!({ "type": "number", "minimum": 0, "description": description });
+    let integer = |description: &str| json!({ "type": "integer", "minimum": 0, "description": description });
+    let operation = |summary: &str, request: &str, response: &str| json!({
+        "summary": summary,
+        "requestBody": { "required": true, "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", request) } } } },
+        "responses": {
+            "200": { "description": "Prediction", "content": { "application/json": { "schema": { "$ref": format!("#/components/schemas/{}", response) } } } },
+            "422": { "description": "Invalid request", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+        },
+    });
+
+    // Operations answered by a registry model accept the model/version query of a Selector
+    let selected = |mut operation: Value| {
+        operation["parameters"] = json!([
+            { "name": "model", "in": "query", "schema": { "type": "string" }, "description": "Artifact name; the default climate or footprint model when absent" },
+            { "name": "version", "in": "query", "schema": { "type": "integer", "minimum": 1 }, "description": "Artifact version; the latest when absent" },
+        ]);
+        operation["responses"]["404"] = json!({ "description": "No such model", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } });
+        operation
+    };
+    let artifact = json!({ "type": "string", "description": "name@version of the model that answered" });
+    // The scenario presets start where the selected climate model's training data ends; the temperatures
+    // are always projected by the energy-balance model
+    let presets = |operation: Value| {
+        let mut operation = selected(operation);
+        operation["parameters"][0]["description"] = json!("Climate artifact whose last training year and fitted emissions the presets start from; the default climate model when absent. It does not change projected temperatures");
+        operation
+    };
+
+    let frequencies: Vec<&str> = Frequency::ALL.iter().map(Frequency::name).collect();
+    let units = json!({ "type": "string", "enum": ["hour", "day", "km"] });
+
+    let mut vehicle = choice("Vehicle Type");
+    vehicle["enum"].as_array_mut().expect("enum is an array").push(json!(""));
+    vehicle["description"] = json!("Empty without a vehicle");
+
+    json!({
+        "openapi": "3.0.3",
+        "info": { "title": "ClimatePredict", "version": "1" },
+        "servers": [{ "url": BASE }],
+        "paths": {
+            "/predict/temperature": { "post": selected(operation("Temperature anomaly (°C vs 1951–1980) for a level of global emissions", "TemperatureRequest", "TemperatureResponse")) },
+            "/predict/emissions": { "post": selected(operation("Global emissions (GtCO₂) forecast for a year", "EmissionsRequest", "EmissionsResponse")) },
+            "/predict/footprint": { "post": selected(operation("A person's emissions (kg CO₂) from their questionnaire answers", "FootprintRequest", "FootprintResponse")) },
+            "/predict/behavior": { "post": operation("Yearly emissions (kg CO₂) of a behavior done at a frequency for a duration", "BehaviorRequest", "Breakdown") },
+            "/scenario": { "post": operation("Temperature anomaly (°C vs 1951–1980) of every year along an emissions pathway, projected by the two-box energy-balance model", "ScenarioRequest", "ScenarioResponse") },
+            "/scenarios": {
+                "get": presets(json!({
+                    "summary": "Preset pathways shaped like the IPCC SSPs, as control points",
+                    "responses": {
+                        "200": { "description": "Presets", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ScenariosResponse" } } } },
+                    },
+                })),
+            },
+            "/models": {
+                "get": {
+                    "summary": "The loaded model artifacts with their metadata and training metrics",
+                    "responses": {
+                        "200": { "description": "Models", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ModelsResponse" } } } },
+                    },
+                },
+            },
+            "/train": {
+                "post": {
+                    "summary": "Start a background training job; the model is published as the next version of its artifact, beside the default models",
+                    "security": [{ "train_token": [] }],
+                    "requestBody": { "required": true, "content": { "application/json": { "schema": { "$ref": "#/components/schemas/TrainRequest" } } } },
+                    "responses": {
+                        "202": { "description": "Queued job", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/JobState" } } } },
+                        "401": { "description": "Missing or wrong train token", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                        "403": { "description": "Training is disabled: the server has no train token", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                        "422": { "description": "Invalid request", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                        "429": { "description": "Too many jobs are queued or running", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                    },
+                },
+            },
+            "/jobs": {
+                "get": {
+                    "summary": "Every training job since the server started, oldest first",
+                    "responses": {
+                        "200": {
+                            "description": "Jobs",
+                            "content": { "application/json": { "schema": { "type": "object", "required": ["jobs"], "properties": { "jobs": { "type": "array", "items": { "$ref": "#/components/schemas/JobState" } } } } } },
+                        },
+                    },
+                },
+            },
+            "/jobs/{id}": {
+                "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }],
+                "get": {
+                    "summary": "Status and latest progress of a training job",
+                    "responses": {
+                        "200": { "description": "Job", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/JobState" } } } },
+                        "404": { "description": "No such job", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                    },
+                },
+                "delete": {
+                    "summary": "Cancel a job: a queued job never starts, a running one stops after its current epoch",
+                    "security": [{ "train_token": [] }],
+                    "responses": {
+                        "202": { "description": "Cancellation accepted", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/JobState" } } } },
+                        "401": { "description": "Missing or wrong train token", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                        "403": { "description": "Training is disabled: the server has no train token", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                        "404": { "description": "No such job", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                        "409": { "description": "The job has already finished", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                    },
+                },
+            },
+            "/jobs/{id}/events": {
+                "parameters": [{ "name": "id", "in": "path", "required": true, "schema": { "type": "integer" } }],
+                "get": {
+                    "summary": "Server-sent events: a `progress` event (Progress) per epoch from the first (the last 1000 once the job has finished), then a `finished` event (JobState)",
+                    "responses": {
+                        "200": { "description": "Event stream", "content": { "text/event-stream": { "schema": { "type": "string" } } } },
+                        "404": { "description": "No such job", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/ErrorBody" } } } },
+                    },
+                },
+            },
+            "/behaviors": {
+                "get": {
+                    "summary": "The behaviors with their emission factors, and the durations each frequency accepts",
+                    "responses": {
+                        "200": { "description": "Catalogue", "content": { "application/json": { "schema": { "$ref": "#/components/schemas/BehaviorsResponse" } } } },
+                    },
+                },
+            },
+        },
+        "components": {
+            "securitySchemes": {
+                "train_token": { "type": "http", "scheme": "bearer", "description": "The server's train_token setting (ROCKET_TRAIN_TOKEN)" },
+            },
+            "schemas": {
+                "Estimate": {
+                    "type": "object",
+                    "required": ["mean", "std", "low", "high", "unit"],
+                    "properties": {
+                        "mean": { "type": "number" },
+                        "std": { "type": "number", "description": "Standard deviation of a new observation" },
+                        "low": { "type": "number", "description": "Lower end of the 95 % range" },
+                        "high": { "type": "number", "description": "Upper end of the 95 % range" },
+                        "unit": { "type": "string" },
+                    },
+                },
+                "TemperatureRequest": {
+                    "type": "object",
+                    "required": ["emissions"],
+                    "properties": { "emissions": { "type": "number", "minimum": 0, "maximum": MAX_EMISSIONS, "description": "GtCO₂ per year" } },
+                },
+                "TemperatureResponse": {
+                    "type": "object",
+                    "required": ["emissions", "temperature", "model", "artifact"],
+                    "properties": {
+                        "emissions": { "type": "number" },
+                        "temperature": { "$ref": "#/components/schemas/Estimate" },
+                        "model": { "type": "string" },
+                        "artifact": artifact,
+                    },
+                },
+                "EmissionsRequest": {
+                    "type": "object",
+                    "required": ["year"],
+                    "properties": { "year": { "type": "integer", "minimum": YEARS.start(), "maximum": YEARS.end() } },
+                },
+                "EmissionsResponse": {
+                    "type": "object",
+                    "required": ["year", "emissions", "model", "extrapolated", "artifact"],
+                    "properties": {
+                        "year": { "type": "integer" },
+                        "emissions": { "$ref": "#/components/schemas/Estimate" },
+                        "model": { "type": "string" },
+                        "extrapolated": { "type": "boolean", "description": "The year lies outside the training data" },
+                        "artifact": artifact,
+                    },
+                },
+                "FootprintRequest": {
+                    "type": "object",
+                    "required": [
+                        "body_type", "sex", "diet", "how_often_shower", "heating_energy_source", "transport", "social_activity",
+                        "monthly_grocery_bill", "frequency_of_traveling_by_air", "vehicle_monthly_distance_km", "waste_bag_size",
+                        "waste_bag_weekly_count", "how_long_tv_pc_daily_hour", "how_many_new_clothes_monthly",
+                        "how_long_internet_daily_hour", "energy_efficiency",
+                    ],
+                    "properties": {
+                        "body_type": choice("Body Type"),
+                        "sex": choice("Sex"),
+                        "diet": choice("Diet"),
+                        "how_often_shower": choice("How Often Shower"),
+                        "heating_energy_source": choice("Heating Energy Source"),
+                        "transport": choice("Transport"),
+                        "vehicle_type": vehicle,
+                        "social_activity": choice("Social Activity"),
+                        "monthly_grocery_bill": number("Dollars per month"),
+                        "frequency_of_traveling_by_air": choice("Frequency of Traveling by Air"),
+                        "vehicle_monthly_distance_km": number("Kilometres driven per month"),
+                        "waste_bag_size": choice("Waste Bag Size"),
+                        "waste_bag_weekly_count": integer("Bags of waste per week"),
+                        "how_long_tv_pc_daily_hour": integer("Hours per day"),
+                        "how_many_new_clothes_monthly": integer("Items per month"),
+                        "how_long_internet_daily_hour": integer("Hours per day"),
+                        "energy_efficiency": choice("Energy efficiency"),
+                        "recycling": subset("Recycling"),
+                        "cooking_with": subset("Cooking_With"),
+                    },
+                },
+                "FootprintResponse": {
+                    "type": "object",
+                    "required": ["monthly_emissions", "annual_emissions", "unit", "baseline", "contributions", "model", "artifact"],
+                    "properties": {
+                        "monthly_emissions": { "type": "number" },
+                        "annual_emissions": { "type": "number" },
+                        "unit": { "type": "string" },
+                        "baseline": { "type": "number", "description": "Monthly emissions of the average respondent" },
+                        "contributions": {
+                            "type": "array",
+                            "description": "Per-question change from the baseline, largest first",
+                            "items": {
+                                "type": "object",
+                                "required": ["question", "monthly_emissions"],
+                                "properties": { "question": { "type": "string" }, "monthly_emissions": { "type": "number" } },
+                            },
+                        },
+                        "model": { "type": "string" },
+                        "artifact": artifact,
+                    },
+                },
+                "ControlPoint": {
+                    "type": "object",
+                    "required": ["year", "emissions"],
+                    "properties": {
+                        "year": { "type": "integer", "minimum": YEARS.start(), "maximum": YEARS.end() },
+                        "emissions": { "type": "number", "minimum": -MAX_EMISSIONS, "maximum": MAX_EMISSIONS, "description": "GtCO₂ per year" },
+                    },
+                },
+                "ScenariosResponse": {
+                    "type": "object",
+                    "required": ["start", "end", "level", "unit", "presets"],
+                    "properties": {
+                        "start": { "type": "integer", "description": "Last year of the training data, where every preset begins" },
+                        "end": { "type": "integer" },
+                        "level": { "type": "number", "description": "Fitted emissions in the start year" },
+                        "unit": { "type": "string" },
+                        "presets": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name", "points"],
+                                "properties": {
+                                    "name": { "type": "string" },
+                                    "points": { "type": "array", "items": { "$ref": "#/components/schemas/ControlPoint" } },
+                                },
+                            },
+                        },
+                    },
+                },
+                "ScenarioRequest": {
+                    "type": "object",
+                    "required": ["points"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "points": { "type": "array", "minItems": 2, "items": { "$ref": "#/components/schemas/ControlPoint" }, "description": "Increasing years; emissions are linear in between" },
+                    },
+                },
+                "ScenarioResponse": {
+                    "type": "object",
+                    "required": ["name", "model", "years", "emissions", "temperature"],
+                    "properties": {
+                        "name": { "type": "string" },
+                        "model": { "type": "string", "description": "Model that projected the temperatures: energy balance" },
+                        "years": { "type": "array", "items": { "type": "integer" } },
+                        "emissions": { "type": "array", "items": { "type": "number" } },
+                        "temperature": {
+                            "type": "object",
+                            "required": ["mean", "low", "high", "unit"],
+                            "description": "Anomaly of every year with a 95 % range from the scatter of the observed record around the model",
+                            "properties": {
+                                "mean": { "type": "array", "items": { "type": "number" } },
+                                "low": { "type": "array", "items": { "type": "number" } },
+                                "high": { "type": "array", "items": { "type": "number" } },
+                                "unit": { "type": "string" },
+                            },
+                        },
+                    },
+                },
+                "ModelsResponse": {
+                    "type": "object",
+                    "required": ["models"],
+                    "properties": {
+                        "models": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name", "version", "algorithm", "source", "trained_at", "metrics", "task", "default"],
+                                "properties": {
+                                    "name": { "type": "string" },
+                                    "version": { "type": "integer" },
+                                    "algorithm": { "type": "string" },
+                                    "source": { "type": "string", "description": "Data the model was trained on" },
+                                    "trained_at": { "type": "integer", "description": "Unix time in seconds" },
+                                    "metrics": { "type": "object", "additionalProperties": { "type": "number" } },
+                                    "task": { "type": "string", "enum": ["climate", "footprint"] },
+                                    "default": { "type": "boolean", "description": "Served when a request names no model" },
+                                },
+                            },
+                        },
+                    },
+                },
+                "TrainRequest": {
+                    "type": "object",
+                    "required": ["task"],
+                    "properties": {
+                        "task": { "type": "string", "enum": ["climate", "footprint"] },
+                        "name": { "type": "string", "pattern": "^[a-z0-9_-]{0,56}-trained$", "description": "Artifact to publish, ending in -trained so it never replaces a shipped model; climate-trained, climate-polynomial-trained, climate-neural-trained, footprint-trained or footprint-forest-trained by default" },
+                        "degree": { "type": "integer", "minimum": 1, "maximum": 10, "description": "Climate: degree of the year → emissions polynomial" },
+                        "epochs": { "type": "integer", "minimum": 1, "maximum": 100000, "description": "Climate: gradient descent epochs of a linear or neural temperature model" },
+                        "learning_rate": { "type": "number", "exclusiveMinimum": 0, "maximum": 1, "description": "Climate: gradient descent step on standardized data" },
+                        "algorithm": { "type": "string", "enum": ["linear", "polynomial", "forest", "neural"], "description": "Estimator: linear, polynomial or neural temperature models for climate, linear or forest for footprint" },
+                        "trees": { "type": "integer", "minimum": 1, "maximum": 500, "description": "Footprint: trees of a forest" },
+                        "max_depth": { "type": "integer", "minimum": 1, "description": "Footprint: depth limit of a forest's trees" },
+                    },
+                },
+                "Progress": {
+                    "type": "object",
+                    "required": ["epoch", "epochs", "loss"],
+                    "properties": {
+                        "epoch": { "type": "integer" },
+                        "epochs": { "type": "integer" },
+                        "loss": { "type": "number", "description": "Training mean squared error" },
+                    },
+                },
+                "JobState": {
+                    "type": "object",
+                    "required": ["id", "name", "hyperparameters", "status", "created_at"],
+                    "properties": {
+                        "id": { "type": "integer" },
+                        "name": { "type": "string" },
+                        "hyperparameters": { "type": "object", "description": "The task with its hyperparameters, defaults filled in" },
+                        "status": { "type": "string", "enum": ["queued", "running", "succeeded", "failed", "cancelled"] },
+                        "progress": { "allOf": [{ "$ref": "#/components/schemas/Progress" }], "nullable": true },
+                        "artifact": { "type": "string", "nullable": true, "description": "name@version of the published model" },
+                        "error": { "type": "string", "nullable": true },
+                        "created_at": { "type": "integer", "description": "Unix time in seconds" },
+                        "finished_at": { "type": "integer", "nullable": true },
+                    },
+                },
+                "BehaviorRequest": {
+                    "type": "object",
+                    "required": ["behavior", "frequency", "duration"],
+                    "properties": {
+                        "behavior": { "type": "string", "enum": catalogue.names() },
+                        "frequency": { "type": "string", "enum": frequencies },
+                        "duration": { "type": "integer", "minimum": 1, "description": "Hours per day (daily), days per week, two weeks or month, or months per year (annually)" },
+                    },
+                },
+                "Breakdown": {
+                    "type": "object",
+                    "required": ["behavior", "label", "frequency", "duration", "schedule", "hours", "quantity", "unit", "factor", "annual_emissions", "source"],
+                    "properties": {
+                        "behavior": { "type": "string" },
+                        "label": { "type": "string" },
+                        "frequency": { "type": "string", "enum": frequencies },
+                        "duration": { "type": "integer" },
+                        "schedule": { "type": "string", "description": "The answer as the form reads it" },
+                        "hours": { "type": "number", "description": "Hours a year spent on the behavior" },
+                        "quantity": { "type": "number", "description": "The hours converted to the unit of the factor" },
+                        "unit": units,
+                        "factor": { "type": "number", "description": "kg CO₂ per unit" },
+                        "annual_emissions": { "type": "number", "description": "kg CO₂ a year" },
+                        "source": { "type": "string" },
+                    },
+                },
+                "BehaviorsResponse": {
+                    "type": "object",
+                    "required": ["behaviors", "frequencies"],
+                    "properties": {
+                        "behaviors": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name", "label", "factor", "unit", "hours_per_day", "source"],
+                                "properties": {
+                                    "name": { "type": "string" },
+                                    "label": { "type": "string" },
+                                    "factor": { "type": "number", "description": "kg CO₂ per unit" },
+                                    "unit": units,
+                                    "hours_per_day": { "type": "number", "description": "Hours of the behavior on a day it happens" },
+                                    "km_per_hour": { "type": "number", "nullable": true, "description": "Average speed, for per-km factors" },
+                                    "source": { "type": "string" },
+                                },
+                            },
+                        },
+                        "frequencies": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["name", "label", "durations"],
+                                "properties": {
+                                    "name": { "type": "string" },
+                                    "label": { "type": "string" },
+                                    "durations": {
+                                        "type": "array",
+                                        "items": {
+                                            "type": "object",
+                                            "required": ["value", "label"],
+                                            "properties": { "value": { "type": "integer" }, "label": { "type": "string" } },
+                                        },
+                                    },
+                                },
+                            },
+                        },
+                    },
+                },
+                "ErrorBody": {
+                    "type": "object",
+                    "required": ["error", "errors"],
+                    "properties": {
+                        "error": { "type": "string" },
+                        "errors": {
+                            "type": "array",
+                            "items": {
+                                "type": "object",
+                                "required": ["message"],
+                                "properties": { "field": { "type": "string" }, "message": { "type": "string" } },
+                            },
+                        },
+                    },
+                },
+            },
+        },
+    })
+}
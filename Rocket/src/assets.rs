@@ -0,0 +1,73 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use rocket::fairing::AdHoc;
+use rocket::http::Header;
+use std::path::{Path, PathBuf};
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const ASSETS: &str = "ui"; // The page, its script and styles, relative to the Rocket crate
+pub const INDEX: &str = "index.html"; // Template of the page, rendered by `handle::index`
+pub const ASSET_CACHE: &str = "public, max-age=3600"; // Scripts, styles and images may be reused for an hour
+pub const PAGE_CACHE: &str = "no-cache"; // The page lists the loaded models, so it is rendered on every request
+
+//////////////////////////////////////// state ////////////////////////////////////////
+
+/// Where the UI files are read from.
+pub struct Assets {
+    pub root: PathBuf,
+}
+
+impl Assets {
+    pub fn path(&self, file: impl AsRef<Path>) -> PathBuf {
+        self.root.join(file)
+    }
+}
+
+/// Finds the UI directory when the server ignites, from the `asset_dir` setting (`Rocket.toml`
+/// or `ROCKET_ASSET_DIR`) or ASSETS; the server refuses to start without its INDEX template.
+pub fn stage() -> AdHoc {
+    AdHoc::try_on_ignite("Assets", |rocket| async {
+        let root: String = rocket.figment().extract_inner("asset_dir").unwrap_or_else(|_| ASSETS.to_string());
+        let assets = Assets { root: PathBuf::from(&root) };
+        if assets.path(INDEX).is_file() {
+            info!("Serving the UI from {}", root);
+            Ok(rocket.manage(assets))
+        } else {
+            error!("{} has no {}", root, INDEX);
+            Err(rocket)
+        }
+    })
+}
+
+//////////////////////////////////////// responses ////////////////////////////////////////
+
+/// A response with a `Cache-Control` header.
+#[derive(Responder)]
+pub struct Cached<R> {
+    pub inner: R,
+    pub cache: Header<'static>,
+}
+
+impl<R> Cached<R> {
+    pub fn new(inner: R, policy: &'static str) -> Self {
+        Self { inner, cache: Header::new("Cache-Control", policy) }
+    }
+}
+
+//////////////////////////////////////// templates ////////////////////////////////////////
+
+/// Fills the `{{ name }}` placeholders of a template; values are inserted as given, so
+/// text from outside must go through `escape` first. Unknown placeholders are left in place.
+pub fn render(template: &str, values: &[(&str, String)]) -> String {
+    let mut page = template.to_string();
+    for (name, value) in values {
+        page = page.replace(&format!("{{{{ {} }}}}", name), value);
+    }
+    page
+}
+
+/// Escapes text for HTML content and attribute values.
+pub fn escape(text: &str) -> String {
+    text.replace('&', "&amp;").replace('<', "&lt;").replace('>', "&gt;").replace('"', "&quot;").replace('\'', "&#39;")
+}
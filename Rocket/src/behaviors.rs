@@ -0,0 +1,28 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use climate_predict::behavior::Catalogue;
+use rocket::fairing::AdHoc;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const CATALOGUE: &str = "../Project/data/emission-factors.csv"; // Behaviors and their emission factors
+
+//////////////////////////////////////// state ////////////////////////////////////////
+
+/// Loads the behavior catalogue when the server ignites, from the `catalogue` setting
+/// (`Rocket.toml` or `ROCKET_CATALOGUE`) or CATALOGUE; the server refuses to start without it.
+pub fn stage() -> AdHoc {
+    AdHoc::try_on_ignite("Catalogue", |rocket| async {
+        let path: String = rocket.figment().extract_inner("catalogue").unwrap_or_else(|_| CATALOGUE.to_string());
+        match Catalogue::load(&path) {
+            Ok(catalogue) => {
+                info!("Loaded {} behaviors from {}", catalogue.behaviors.len(), path);
+                Ok(rocket.manage(catalogue))
+            }
+            Err(error) => {
+                error!("{}", error);
+                Err(rocket)
+            }
+        }
+    })
+}
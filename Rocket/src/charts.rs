@@ -0,0 +1,304 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::api::{ControlPoint, ScenarioRequest};
+use crate::models::{Models, Selector};
+use crate::projection::PROJECTION;
+use climate_predict::energy_balance::EnergyBalanceModel;
+use climate_predict::persist;
+use climate_predict::plot::{self, Format, ScenarioChart, Z_95};
+use climate_predict::scenario;
+use rocket::fairing::AdHoc;
+use rocket::http::{ContentType, Header, Status};
+use rocket::tokio::task::spawn_blocking;
+use rocket::{Route, State};
+use std::collections::{HashMap, VecDeque};
+use std::error::Error;
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const BASE: &str = "/charts";
+pub const DATASET: &str = "../Project/data/emission_temp_data.csv"; // Data the fit charts are trained and drawn on
+pub const CAPACITY: usize = 64; // Rendered charts kept in memory
+const CACHE_CONTROL: &str = "public, max-age=3600"; // Browsers and dashboards may reuse a chart for an hour
+const TEST_RATIO: f64 = 0.2; // Most recent share of rows held out for testing, as in the plot binary
+const COLUMNS: [&str; 3] = ["Year", "Emissions", "Lowess"]; // Columns a fit chart can use
+const MAX_DEGREE: usize = 10;
+
+pub fn routes() -> Vec<Route> {
+    routes![fit_svg, fit_png, scenario_svg, scenario_png]
+}
+
+//////////////////////////////////////// cache ////////////////////////////////////////
+
+#[derive(Default)]
+struct Entries {
+    charts: HashMap<String, Vec<u8>>,
+    order: VecDeque<String>, // Keys of `charts`, oldest first
+}
+
+/// Rendered charts keyed by their normalized parameters; beyond `capacity` the oldest are dropped.
+pub struct ChartCache {
+    capacity: usize,
+    entries: Mutex<Entries>,
+}
+
+impl ChartCache {
+    pub fn new(capacity: usize) -> Self {
+        Self { capacity, entries: Mutex::new(Entries::default()) }
+    }
+
+    /// The chart stored under `key`, or the output of `render`, which is then stored.
+    ///
+    /// Returns:
+    ///     The chart, and whether it came from the cache.
+    pub fn get_or_render<F>(&self, key: &str, render: F) -> Result<(Vec<u8>, bool), Box<dyn Error>>
+    where
+        F: FnOnce() -> Result<Vec<u8>, Box<dyn Error>>,
+    {
+        if let Some(chart) = self.lock().charts.get(key) {
+            return Ok((chart.clone(), true));
+        }
+        // Rendered without holding the lock, so a slow chart does not hold up cached ones
+        let chart = render()?;
+
+        let mut entries = self.lock();
+        if entries.charts.insert(key.to_string(), chart.clone()).is_none() {
+            entries.order.push_back(key.to_string());
+        }
+        while entries.order.len() > self.capacity {
+            if let Some(oldest) = entries.order.pop_front() {
+                entries.charts.remove(&oldest);
+            }
+        }
+        Ok((chart, false))
+    }
+
+    /// Drops every chart, e.g. after the models they were drawn from change.
+    pub fn clear(&self) {
+        let mut entries = self.lock();
+        entries.charts.clear();
+        entries.order.clear();
+    }
+
+    pub fn len(&self) -> usize {
+        self.lock().charts.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    // A panic while the lock was held cannot leave the map half-updated, so poisoning is ignored
+    fn lock(&self) -> MutexGuard<'_, Entries> {
+        self.entries.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+//////////////////////////////////////// state ////////////////////////////////////////
+
+/// The chart cache and the data fit charts are drawn from.
+pub struct Charts {
+    pub cache: Arc<ChartCache>, // Shared with the model reloader, which clears it
+    pub dataset: String,
+}
+
+/// Sets up chart rendering from the `chart_data` (data of the fit charts) and `chart_cache`
+/// (charts kept in memory) settings, or DATASET and CAPACITY.
+pub fn stage() -> AdHoc {
+    AdHoc::on_ignite("Charts", |rocket| async {
+        let figment = rocket.figment();
+        let dataset: String = figment.extract_inner("chart_data").unwrap_or_else(|_| DATASET.to_string());
+        let capacity: usize = figment.extract_inner("chart_cache").unwrap_or(CAPACITY);
+        rocket.manage(Charts { cache: Arc::new(ChartCache::new(capacity)), dataset })
+    })
+}
+
+//////////////////////////////////////// responses ////////////////////////////////////////
+
+/// A rendered chart, cacheable by clients; `X-Cache` tells whether the server had it already.
+#[derive(Responder)]
+pub struct Chart {
+    inner: (ContentType, Vec<u8>),
+    cache_control: Header<'static>,
+    x_cache: Header<'static>,
+}
+
+impl Chart {
+    fn new(format: Format, image: Vec<u8>, cached: bool) -> Self {
+        let content_type = match format {
+            Format::Svg => ContentType::SVG,
+            Format::Png => ContentType::PNG,
+        };
+        Self {
+            inner: (content_type, image),
+            cache_control: Header::new("Cache-Control", CACHE_CONTROL),
+            x_cache: Header::new("X-Cache", if cached { "HIT" } else { "MISS" }),
+        }
+    }
+}
+
+type ChartResult = Result<Chart, (Status, String)>;
+
+fn invalid(message: String) -> (Status, String) {
+    (Status::UnprocessableEntity, message)
+}
+
+fn render_failed(error: Box<dyn Error>) -> (Status, String) {
+    error!("cannot render chart: {}", error);
+    (Status::InternalServerError, format!("cannot render chart: {}", error))
+}
+
+/// The chart cached under `key`, or the output of `render`; both run on the blocking pool, since
+/// loading data, fitting and rasterizing would otherwise hold up an async worker.
+async fn get_or_render<F>(cache: &Arc<ChartCache>, key: String, render: F) -> Result<(Vec<u8>, bool), (Status, String)>
+where
+    F: FnOnce() -> Result<Vec<u8>, Box<dyn Error>> + Send + 'static,
+{
+    let cache = cache.clone();
+    match spawn_blocking(move || cache.get_or_render(&key, render).map_err(|error| error.to_string())).await {
+        Ok(Ok(chart)) => Ok(chart),
+        Ok(Err(message)) => Err(render_failed(message.into())),
+        Err(error) => Err(render_failed(Box::new(error))),
+    }
+}
+
+//////////////////////////////////////// fit charts ////////////////////////////////////////
+
+/// Query of a fit chart, as in `/charts/fit.svg?model=poly&degree=3&x=Year&y=Emissions`.
+#[derive(Debug, FromForm)]
+pub struct FitQuery {
+    model: Option<String>,   // linear (default) or poly
+    degree: Option<usize>,   // Of a poly model; persist::DEGREE by default
+    x: Option<String>,       // Input column, Emissions by default
+    y: Option<String>,       // Target column, Lowess by default
+    test_ratio: Option<f64>, // Most recent share of rows held out, TEST_RATIO by default
+}
+
+impl FitQuery {
+    /// The degree, columns and test ratio, checked and with defaults filled in.
+    fn normalize(&self) -> Result<(usize, &'static str, &'static str, f64), String> {
+        let degree = match (self.model.as_deref().unwrap_or("linear"), self.degree) {
+            ("linear", None | Some(1)) => 1,
+            ("linear", Some(_)) => return Err("a linear model has degree 1; use model=poly for higher degrees".to_string()),
+            ("poly", degree) => degree.unwrap_or(persist::DEGREE),
+            (model, _) => return Err(format!("unknown model '{}' (expected linear or poly)", model)),
+        };
+        if degree == 0 || degree > MAX_DEGREE {
+            return Err(format!("degree must be between 1 and {}", MAX_DEGREE));
+        }
+        let column = |name: &str| COLUMNS.into_iter().find(|column| *column == name)
+            .ok_or_else(|| format!("unknown column '{}' (expected one of: {})", name, COLUMNS.join(", ")));
+        let x = column(self.x.as_deref().unwrap_or("Emissions"))?;
+        let y = column(self.y.as_deref().unwrap_or("Lowess"))?;
+        let test_ratio = self.test_ratio.unwrap_or(TEST_RATIO);
+        if !(0.0..1.0).contains(&test_ratio) {
+            return Err("test_ratio must be at least 0 and below 1".to_string());
+        }
+        Ok((degree, x, y, test_ratio))
+    }
+}
+
+async fn fit(format: Format, query: FitQuery, charts: &Charts) -> ChartResult {
+    let (degree, x, y, test_ratio) = query.normalize().map_err(invalid)?;
+    let key = format!("fit.{}?degree={}&x={}&y={}&test_ratio={}", format.extension(), degree, x, y, test_ratio);
+    let dataset = charts.dataset.clone();
+    let (image, cached) = get_or_render(&charts.cache, key, move || Ok(plot::fit_chart(&dataset, degree, x, y, test_ratio, format)?.image)).await?;
+    Ok(Chart::new(format, image, cached))
+}
+
+/// A model fit with its 95 % prediction interval, train/test split and residuals.
+#[get("/fit.svg?<query..>")]
+pub async fn fit_svg(query: FitQuery, charts: &State<Charts>) -> ChartResult {
+    fit(Format::Svg, query, charts).await
+}
+
+#[get("/fit.png?<query..>")]
+pub async fn fit_png(query: FitQuery, charts: &State<Charts>) -> ChartResult {
+    fit(Format::Png, query, charts).await
+}
+
+//////////////////////////////////////// scenario charts ////////////////////////////////////////
+
+/// Query of a scenario chart: a preset, as in `/charts/scenario.svg?preset=SSP2-4.5`, or control
+/// points, as in `/charts/scenario.svg?points=2023:40,2050:0,2100:0&name=net%20zero`. With a preset,
+/// `model` and `version` pick the climate model it starts from, as in the JSON API; they cannot be
+/// given with points, which start where they say.
+#[derive(Debug, FromForm)]
+pub struct ScenarioQuery {
+    preset: Option<String>,
+    points: Option<String>, // year:emissions pairs separated by commas
+    name: Option<String>,
+    model: Option<String>,
+    version: Option<u32>,
+}
+
+impl ScenarioQuery {
+    /// The pathway as a scenario request of the JSON API, so both are validated alike, with the
+    /// `name@version` of the climate model a preset starts from.
+    fn request(&self, models: &Models) -> Result<(ScenarioRequest, Option<String>), (Status, String)> {
+        let (points, start) = match (&self.preset, &self.points) {
+            (Some(preset), None) => {
+                let selector = Selector { model: self.model.clone(), version: self.version };
+                let climate = models.climate(&selector).map_err(|message| (Status::NotFound, message))?;
+                let (start, level) = climate.model.start();
+                let presets = scenario::preset_points(start, level);
+                let Some((_, points)) = presets.iter().find(|(name, _)| name == preset) else {
+                    let names: Vec<&str> = presets.iter().map(|(name, _)| *name).collect();
+                    return Err(invalid(format!("unknown preset '{}' (expected one of: {})", preset, names.join(", "))));
+                };
+                (points.iter().map(|&(year, emissions)| ControlPoint { year, emissions }).collect(), Some(climate.id()))
+            }
+            (None, Some(_)) if self.model.is_some() || self.version.is_some() => {
+                return Err(invalid("model and version choose where a preset starts; they do not apply to points".to_string()));
+            }
+            (None, Some(points)) => (points.split(',')
+                .map(|point| {
+                    let parsed = point.split_once(':').and_then(|(year, emissions)| Some(ControlPoint { year: year.trim().parse().ok()?, emissions: emissions.trim().parse().ok()? }));
+                    parsed.ok_or_else(|| invalid(format!("cannot read point '{}' (expected year:emissions)", point)))
+                })
+                .collect::<Result<Vec<_>, _>>()?, None),
+            _ => return Err(invalid("give either a preset or points".to_string())),
+        };
+        Ok((ScenarioRequest { name: self.name.clone().or_else(|| self.preset.clone()), points }, start))
+    }
+}
+
+async fn scenario(format: Format, query: ScenarioQuery, models: &Models, projection: &EnergyBalanceModel, charts: &Charts) -> ChartResult {
+    let (request, start) = query.request(models)?;
+    let pathway = request.pathway().map_err(|error| {
+        let messages: Vec<String> = error.body.errors.iter()
+            .map(|field| match &field.field {
+                Some(name) => format!("{}: {}", name, field.message),
+                None => field.message.clone(),
+            })
+            .collect();
+        invalid(messages.join("; "))
+    })?;
+
+    let points: Vec<String> = request.points.iter().map(|point| format!("{}:{}", point.year, point.emissions)).collect();
+    let key = format!("scenario.{}?model={}&name={}&points={}", format.extension(), start.unwrap_or_default(), pathway.name, points.join(","));
+    let projection = projection.clone();
+    let (image, cached) = get_or_render(&charts.cache, key, move || {
+        let temperatures = projection.project_with_uncertainty(&pathway);
+        let title = format!("{} scenario", pathway.name);
+        let chart = ScenarioChart { title: &title, years: &pathway.years, emissions: &pathway.emissions, temperatures: &temperatures, z: Z_95 };
+        plot::render_scenario(format, &chart)
+    }).await?;
+    // A cached chart is not a new prediction; the temperatures are the projection's, whichever model a preset starts from
+    if !cached {
+        models.count(PROJECTION.to_string());
+    }
+    Ok(Chart::new(format, image, cached))
+}
+
+/// An emissions pathway and the temperature anomaly the energy-balance model projects for it, with its 95 % range.
+#[get("/scenario.svg?<query..>")]
+pub async fn scenario_svg(query: ScenarioQuery, models: &State<Models>, projection: &State<EnergyBalanceModel>, charts: &State<Charts>) -> ChartResult {
+    scenario(Format::Svg, query, models, projection, charts).await
+}
+
+#[get("/scenario.png?<query..>")]
+pub async fn scenario_png(query: ScenarioQuery, models: &State<Models>, projection: &State<EnergyBalanceModel>, charts: &State<Charts>) -> ChartResult {
+    scenario(Format::Png, query, models, projection, charts).await
+}
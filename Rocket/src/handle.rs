@@ -1,45 +1,114 @@
-#![feature(proc_macro_hygiene, decl_macro)]
+//////////////////////////////////////// dependencies ////////////////////////////////////////
 
-#[macro_use] extern crate rocket;
+use crate::api;
+use crate::assets::{self, Assets, Cached, ASSET_CACHE, INDEX, PAGE_CACHE};
+use crate::models::{Models, Selector};
+use climate_predict::behavior::{Catalogue, Frequency};
+use rocket::form::Form;
+use rocket::fs::NamedFile;
+use rocket::http::Status;
+use rocket::response::content::RawHtml;
+use rocket::serde::json;
+use rocket::tokio::fs;
+use rocket::{Request, State};
+use std::path::{Path, PathBuf};
 
-use rocket::Request;
-use rocket::response::content;
+//////////////////////////////////////// global variables ////////////////////////////////////////
 
-#[get("/")]
-fn index() -> content::Html<&'static str> {
-    content::Html(include_str!("index.html"))
+const Z_95: f64 = 1.96; // Standard deviations covering ~95 % of a normal distribution
+
+//////////////////////////////////////// forms ////////////////////////////////////////
+
+#[derive(FromForm)]
+pub struct FormInput {
+    behavior: String,
+    frequency: String,
+    duration: u32, // In the unit of the frequency, e.g. hours per day for daily
+    #[field(validate = range(1800..=2200))]
+    year: i32, // Year to forecast global emissions and temperature for
 }
 
-#[post("/predict", data = "<form_data>")]
-fn predict(form_data: rocket::request::Form<FormInput>) -> String {
-    let behavior = &form_data.behavior;
-    let frequency = &form_data.frequency;
-    let duration = &form_data.duration;
+//////////////////////////////////////// routes ////////////////////////////////////////
 
-    // Here you can process the form data and return the result
-    let result = format!(
-        "Predicting the climate impact of {}, with a frequency of {} and a duration of {}.",
-        behavior, frequency, duration
-    );
+/// The form, rendered from the INDEX template with the loaded models and the behavior catalogue.
+/// The template is read on every request, so the page can be edited without a rebuild.
+#[get("/")]
+pub async fn index(assets: &State<Assets>, models: &State<Models>, catalogue: &State<Catalogue>) -> Result<Cached<RawHtml<String>>, Status> {
+    let template = fs::read_to_string(assets.path(INDEX)).await.map_err(|error| {
+        error!("cannot read {}: {}", assets.path(INDEX).display(), error);
+        Status::InternalServerError
+    })?;
 
-    result
+    let option = |value: &str, label: &str| format!("<option value=\"{}\">{}</option>", assets::escape(value), assets::escape(label));
+    let behaviors: Vec<String> = catalogue.behaviors.iter().map(|behavior| option(&behavior.name, &behavior.label)).collect();
+    let frequencies: Vec<String> = Frequency::ALL.iter().map(|frequency| option(frequency.name(), frequency.label())).collect();
+    let models: Vec<String> = models.list().into_iter()
+        .map(|(task, metadata, default)| {
+            let model = format!(
+                "{}@{} ({}{}): {}, trained on {}",
+                metadata.name, metadata.version, task, if default { ", default" } else { "" }, metadata.algorithm, metadata.source
+            );
+            format!("<li>{}</li>", assets::escape(&model))
+        })
+        .collect();
+    // Inside <script>, only a closing tag could end the JSON early
+    let durations = json::to_string(&api::frequency_options()).map_err(|_| Status::InternalServerError)?.replace("</", "<\\/");
+
+    let page = assets::render(&template, &[
+        ("behaviors", behaviors.join("\n")),
+        ("frequencies", frequencies.join("\n")),
+        ("models", models.join("\n")),
+        ("durations", durations),
+    ]);
+    Ok(Cached::new(RawHtml(page), PAGE_CACHE))
 }
 
-#[derive(FromForm)]
-struct FormInput {
-    behavior: String,
-    frequency: String,
-    duration: String,
+/// Any other file of the asset directory (script, styles), typed by its extension.
+#[get("/<file..>", rank = 10)]
+pub async fn asset(file: PathBuf, assets: &State<Assets>) -> Option<Cached<NamedFile>> {
+    // The template is only served rendered, at /
+    if file == Path::new(INDEX) || !assets.path(&file).is_file() {
+        return None;
+    }
+    NamedFile::open(assets.path(&file)).await.ok().map(|named| Cached::new(named, ASSET_CACHE))
 }
 
-#[catch(404)]
-fn not_found(req: &Request) -> String {
-    format!("404: Page '{}' not found", req.uri())
+/// Annualizes the behavior with its emission factor from the catalogue, then forecasts global
+/// emissions and the temperature anomaly (vs 1951–1980) for the requested year with the saved
+/// year → emissions → temperature models (the default climate model, or the one named by the
+/// `model`/`version` query), with 95 % ranges.
+#[post("/predict?<selector..>", data = "<form_data>")]
+pub fn predict(form_data: Form<FormInput>, selector: Selector, models: &State<Models>, catalogue: &State<Catalogue>) -> Result<String, (Status, String)> {
+    let invalid = |message: String| (Status::UnprocessableEntity, message);
+    let behavior = catalogue.get(&form_data.behavior)
+        .ok_or_else(|| invalid(format!("unknown behavior '{}' (expected one of: {})", form_data.behavior, catalogue.names().join(", "))))?;
+    let frequency: Frequency = form_data.frequency.parse().map_err(invalid)?;
+    let breakdown = behavior.annualize(frequency, form_data.duration).map_err(|error| invalid(error.to_string()))?;
+    let year = form_data.year;
+    let entry = models.climate(&selector).map_err(|message| (Status::NotFound, message))?;
+    let climate = &entry.model;
+
+    let outputs = climate.pipeline().predict(year as f64);
+    models.count(entry.id());
+    let (emissions, temperature) = (outputs[0].prediction, outputs[1].prediction);
+    let (emissions_low, emissions_high) = emissions.interval(Z_95);
+    let (temperature_low, temperature_high) = temperature.interval(Z_95);
+
+    let mut result = format!(
+        "{}.\n\
+         In {}: global emissions of {:.1} GtCO₂ (95 %: {:.1} to {:.1}) and a temperature anomaly of {:+.2} °C (95 %: {:+.2} to {:+.2}) relative to 1951–1980.",
+        breakdown,
+        year, emissions.mean, emissions_low, emissions_high, temperature.mean, temperature_low, temperature_high
+    );
+    if !(climate.first_year..=climate.last_year).contains(&year) {
+        result += &format!("\nThe models were trained on {}–{}, so this is an extrapolation.", climate.first_year, climate.last_year);
+    }
+    Ok(result)
 }
 
-fn main() {
-    rocket::ignite()
-        .mount("/", routes![index, predict])
-        .register(catchers![not_found])
-        .launch();
+//////////////////////////////////////// catchers ////////////////////////////////////////
+
+#[catch(404)]
+pub fn not_found(req: &Request<'_>) -> String {
+    format!("404: Page '{}' not found", req.uri())
 }
@@ -0,0 +1,28 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::models::Models;
+use rocket::http::Status;
+use rocket::serde::json::{json, Json, Value};
+use rocket::{Route, State};
+
+//////////////////////////////////////// routes ////////////////////////////////////////
+
+/// Liveness: the server is up and answering, whatever state its models are in.
+#[get("/healthz")]
+pub fn healthz() -> Json<Value> {
+    Json(json!({ "status": "ok" }))
+}
+
+/// Readiness: 200 once a default climate and footprint model are loaded, 503 with the reason until then.
+#[get("/readyz")]
+pub fn readyz(models: Option<&State<Models>>) -> (Status, Json<Value>) {
+    match models {
+        Some(models) if models.is_ready() => (Status::Ok, Json(json!({ "status": "ready" }))),
+        Some(_) => (Status::ServiceUnavailable, Json(json!({ "status": "unavailable", "reason": "the default models are not loaded" }))),
+        None => (Status::ServiceUnavailable, Json(json!({ "status": "unavailable", "reason": "the model registry is not set up" }))),
+    }
+}
+
+pub fn routes() -> Vec<Route> {
+    routes![healthz, readyz]
+}
@@ -0,0 +1,523 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::api::{self, ApiError, ErrorBody, FieldError};
+use crate::charts::Charts;
+use crate::models::Models;
+use climate_predict::persist::{self, Artifact};
+use climate_predict::regression::GradientDescent;
+use climate_predict::training::{self, Algorithm, Hyperparameters, Progress};
+use rocket::fairing::AdHoc;
+use rocket::http::Status;
+use rocket::request::{self, FromRequest, Request};
+use rocket::response::stream::{Event, EventStream};
+use rocket::serde::json::{self, Json};
+use rocket::serde::{Deserialize, Serialize};
+use rocket::tokio::sync::{watch, Semaphore};
+use rocket::tokio::{self, task};
+use rocket::{Route, Shutdown, State};
+use std::collections::BTreeMap;
+use std::ops::{ControlFlow, RangeInclusive};
+use std::sync::atomic::{AtomicBool, AtomicU64, Ordering};
+use std::sync::{Arc, Mutex, MutexGuard, PoisonError};
+use std::time::{SystemTime, UNIX_EPOCH};
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const CLIMATE_DATA: &str = "../Project/data/emission_temp_data.csv"; // Data climate jobs train on
+pub const FOOTPRINT_DATA: &str = "../Project/data/Carbon Emission.csv"; // Data footprint jobs train on
+pub const CONCURRENCY: usize = 1; // Jobs trained at once; the others wait queued
+pub const QUEUED: usize = 16; // Unfinished jobs at once; further requests are refused until some finish
+const TRAINED: &str = "-trained"; // Suffix every name jobs publish under ends in, so they never replace a shipped artifact
+const KEPT: usize = 100; // Finished jobs remembered before the oldest are forgotten
+const REPLAYED: usize = 1000; // Latest epochs a finished job keeps for late event streams
+const DEGREES: RangeInclusive<usize> = 1..=10;
+const EPOCHS: RangeInclusive<usize> = 1..=100_000;
+const TREES: RangeInclusive<u16> = 1..=500;
+const MAX_LEARNING_RATE: f64 = 1.0; // Beyond this gradient descent on standardized data diverges
+
+pub fn routes() -> Vec<Route> {
+    routes![train, jobs, job, events, cancel]
+}
+
+//////////////////////////////////////// jobs ////////////////////////////////////////
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize)]
+#[serde(crate = "rocket::serde", rename_all = "lowercase")]
+pub enum JobStatus {
+    Queued,
+    Running,
+    Succeeded,
+    Failed,
+    Cancelled,
+}
+
+impl JobStatus {
+    pub fn name(self) -> &'static str {
+        match self {
+            JobStatus::Queued => "queued",
+            JobStatus::Running => "running",
+            JobStatus::Succeeded => "succeeded",
+            JobStatus::Failed => "failed",
+            JobStatus::Cancelled => "cancelled",
+        }
+    }
+
+    pub fn is_finished(self) -> bool {
+        matches!(self, JobStatus::Succeeded | JobStatus::Failed | JobStatus::Cancelled)
+    }
+}
+
+/// What GET /jobs/<id> reports about a training job.
+#[derive(Debug, Clone, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct JobState {
+    pub id: u64,
+    pub name: String, // Artifact the result is published as
+    pub hyperparameters: Hyperparameters,
+    pub status: JobStatus,
+    pub progress: Option<Progress>, // Latest epoch
+    pub artifact: Option<String>,   // `name@version` once published to the registry
+    pub error: Option<String>,
+    pub created_at: u64, // Unix time in seconds
+    pub finished_at: Option<u64>,
+}
+
+/// Progress an event stream has not sent yet, see Job::since.
+pub struct Unsent {
+    pub progress: Vec<Progress>,
+    pub sent: usize, // Epochs reported so far, to pass to the next call
+    pub status: JobStatus,
+}
+
+// A job's state with the epochs replayed to event streams
+struct Tracked {
+    state: JobState,
+    history: Vec<Progress>,
+    dropped: usize, // Epochs removed from the front of `history` once the job finished
+}
+
+/// A training job; its state changes are announced on `updates`.
+pub struct Job {
+    tracked: Mutex<Tracked>,
+    cancel: AtomicBool,
+    updates: watch::Sender<()>,
+}
+
+impl Job {
+    fn new(state: JobState) -> Self {
+        Self { tracked: Mutex::new(Tracked { state, history: Vec::new(), dropped: 0 }), cancel: AtomicBool::new(false), updates: watch::channel(()).0 }
+    }
+
+    pub fn state(&self) -> JobState {
+        self.lock().state.clone()
+    }
+
+    /// The epochs reported after the first `sent`, and the job's status.
+    ///
+    /// A finished job only keeps its last REPLAYED epochs, so a stream that fell further behind skips
+    /// the ones in between.
+    pub fn since(&self, sent: usize) -> Unsent {
+        let tracked = self.lock();
+        let start = sent.saturating_sub(tracked.dropped).min(tracked.history.len());
+        Unsent { progress: tracked.history[start..].to_vec(), sent: tracked.dropped + tracked.history.len(), status: tracked.state.status }
+    }
+
+    fn lock(&self) -> MutexGuard<'_, Tracked> {
+        self.tracked.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    fn update(&self, change: impl FnOnce(&mut JobState)) {
+        change(&mut self.lock().state);
+        self.updates.send_replace(());
+    }
+
+    fn record(&self, progress: Progress) {
+        let mut tracked = self.lock();
+        tracked.state.progress = Some(progress);
+        tracked.history.push(progress);
+        drop(tracked);
+        self.updates.send_replace(());
+    }
+
+    fn finish(&self, status: JobStatus, artifact: Option<String>, error: Option<String>) {
+        let mut tracked = self.lock();
+        tracked.state.status = status;
+        tracked.state.artifact = artifact;
+        tracked.state.error = error;
+        tracked.state.finished_at = Some(now());
+        let excess = tracked.history.len().saturating_sub(REPLAYED);
+        tracked.history.drain(..excess);
+        tracked.history.shrink_to_fit();
+        tracked.dropped += excess;
+        drop(tracked);
+        self.updates.send_replace(());
+    }
+
+    // Moves a queued job to running, unless it was cancelled while it waited
+    fn try_start(&self) -> bool {
+        let mut started = false;
+        self.update(|state| {
+            if state.status == JobStatus::Queued {
+                state.status = JobStatus::Running;
+                started = true;
+            }
+        });
+        started
+    }
+
+    /// Cancels a queued job at once and asks a running one to stop after its current epoch.
+    ///
+    /// Returns:
+    ///     The status of a job that had already finished, as an error.
+    pub fn cancel(&self) -> Result<(), JobStatus> {
+        let mut result = Ok(());
+        self.update(|state| match state.status {
+            JobStatus::Queued => {
+                state.status = JobStatus::Cancelled;
+                state.finished_at = Some(now());
+            }
+            JobStatus::Running => self.cancel.store(true, Ordering::Relaxed),
+            finished => result = Err(finished),
+        });
+        result
+    }
+}
+
+fn now() -> u64 {
+    SystemTime::now().duration_since(UNIX_EPOCH).map_or(0, |elapsed| elapsed.as_secs())
+}
+
+/// Every training job of this run of the server, with the data they train on.
+pub struct Jobs {
+    climate_data: String,
+    footprint_data: String,
+    token: Option<String>, // Bearer token that starts and cancels jobs; without one training is disabled
+    slots: Arc<Semaphore>,
+    publishing: Arc<Mutex<()>>, // Picking the next version and saving it happen together
+    next_id: AtomicU64,
+    jobs: Mutex<BTreeMap<u64, Arc<Job>>>,
+}
+
+impl Jobs {
+    pub fn new(climate_data: String, footprint_data: String, token: Option<String>, concurrency: usize) -> Self {
+        Self {
+            climate_data,
+            footprint_data,
+            token,
+            slots: Arc::new(Semaphore::new(concurrency)),
+            publishing: Arc::new(Mutex::new(())),
+            next_id: AtomicU64::new(1),
+            jobs: Mutex::new(BTreeMap::new()),
+        }
+    }
+
+    pub fn get(&self, id: u64) -> Option<Arc<Job>> {
+        self.lock().get(&id).cloned()
+    }
+
+    fn lock(&self) -> MutexGuard<'_, BTreeMap<u64, Arc<Job>>> {
+        self.jobs.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    /// Queues a job and trains it in the background once a slot is free; a successful model is
+    /// saved as the next version of `name` and loaded into the registry straight away.
+    ///
+    /// Returns:
+    ///     The job, or an error if QUEUED jobs are already waiting or running.
+    fn start(&self, name: String, hyperparameters: Hyperparameters, models: Models, charts: &Charts) -> Result<Arc<Job>, ApiError> {
+        let mut jobs = self.lock();
+        if jobs.values().filter(|job| !job.lock().state.status.is_finished()).count() >= QUEUED {
+            return Err(ApiError {
+                status: Status::TooManyRequests,
+                body: ErrorBody { error: "busy".to_string(), errors: vec![FieldError { field: None, message: format!("{} training jobs are already queued or running", QUEUED) }] },
+            });
+        }
+
+        let id = self.next_id.fetch_add(1, Ordering::Relaxed);
+        let state = JobState {
+            id,
+            name,
+            hyperparameters,
+            status: JobStatus::Queued,
+            progress: None,
+            artifact: None,
+            error: None,
+            created_at: now(),
+            finished_at: None,
+        };
+        let job = Arc::new(Job::new(state));
+
+        jobs.insert(id, job.clone());
+        let finished: Vec<u64> = jobs.iter().filter(|(_, job)| job.lock().state.status.is_finished()).map(|(id, _)| *id).collect();
+        for id in finished.iter().take(jobs.len().saturating_sub(KEPT)) {
+            jobs.remove(id);
+        }
+        drop(jobs);
+
+        let data = match job.lock().state.hyperparameters {
+            Hyperparameters::Climate { .. } => self.climate_data.clone(),
+            Hyperparameters::Footprint { .. } => self.footprint_data.clone(),
+        };
+        let (slots, publishing, cache, running) = (self.slots.clone(), self.publishing.clone(), charts.cache.clone(), job.clone());
+        tokio::spawn(async move {
+            let Ok(_slot) = slots.acquire_owned().await else {
+                return;
+            };
+            if !running.try_start() {
+                return;
+            }
+
+            // Ok(None) when the job was stopped on request, which is not a failure
+            let job = running.clone();
+            let result = task::spawn_blocking(move || {
+                let state = job.state();
+                let mut stopped = false;
+                let trained = state.hyperparameters.train(&data, |progress| {
+                    job.record(progress);
+                    stopped = job.cancel.load(Ordering::Relaxed);
+                    if stopped { ControlFlow::Break(()) } else { ControlFlow::Continue(()) }
+                });
+                let (model, metrics) = match trained {
+                    Ok(trained) => trained,
+                    Err(_) if stopped => return Ok(None),
+                    Err(error) => return Err(error.to_string()),
+                };
+
+                let _publishing = publishing.lock().unwrap_or_else(PoisonError::into_inner);
+                let directory = models.directory().to_string_lossy().into_owned();
+                let version = persist::next_version(&directory, &state.name).map_err(|error| error.to_string())?;
+                let path = Artifact::new(&state.name, version, &data, metrics, model).save(&directory).map_err(|error| error.to_string())?;
+                info!("Training job {} saved {} version {} to {}", state.id, state.name, version, path);
+                if models.refresh().map_err(|error| error.to_string())? {
+                    cache.clear();
+                }
+                Ok::<_, String>(Some(format!("{}@{}", state.name, version)))
+            })
+            .await;
+
+            match result {
+                Ok(Ok(Some(artifact))) => running.finish(JobStatus::Succeeded, Some(artifact), None),
+                Ok(Ok(None)) => running.finish(JobStatus::Cancelled, None, None),
+                Ok(Err(error)) => running.finish(JobStatus::Failed, None, Some(error)),
+                Err(error) => running.finish(JobStatus::Failed, None, Some(error.to_string())),
+            }
+        });
+        Ok(job)
+    }
+}
+
+/// Sets up training jobs from the `climate_data` and `footprint_data` settings (the datasets jobs
+/// train on), `train_token` (the bearer token POST /train and DELETE /jobs/<id> require; training
+/// is disabled without it) and `train_concurrency` (jobs trained at once), or CLIMATE_DATA,
+/// FOOTPRINT_DATA and CONCURRENCY.
+pub fn stage() -> AdHoc {
+    AdHoc::on_ignite("Jobs", |rocket| async {
+        let figment = rocket.figment();
+        let climate_data: String = figment.extract_inner("climate_data").unwrap_or_else(|_| CLIMATE_DATA.to_string());
+        let footprint_data: String = figment.extract_inner("footprint_data").unwrap_or_else(|_| FOOTPRINT_DATA.to_string());
+        let token: Option<String> = figment.extract_inner("train_token").ok().filter(|token: &String| !token.is_empty());
+        let concurrency: usize = figment.extract_inner("train_concurrency").unwrap_or(CONCURRENCY);
+        rocket.manage(Jobs::new(climate_data, footprint_data, token, concurrency))
+    })
+}
+
+//////////////////////////////////////// requests ////////////////////////////////////////
+
+/// A training request, e.g. `{"task": "climate", "algorithm": "neural", "epochs": 5000}` or
+/// `{"task": "footprint", "algorithm": "forest", "trees": 50}`; missing hyperparameters take
+/// the defaults of the `train` binary.
+#[derive(Debug, Deserialize)]
+#[serde(crate = "rocket::serde", deny_unknown_fields)]
+pub struct TrainRequest {
+    pub task: String,
+    pub name: Option<String>, // Artifact to publish a new version of, ending in TRAINED
+    pub degree: Option<usize>,
+    pub epochs: Option<usize>,
+    pub learning_rate: Option<f64>,
+    pub algorithm: Option<String>,
+    pub trees: Option<u16>,
+    pub max_depth: Option<u16>,
+}
+
+impl TrainRequest {
+    /// The artifact name and hyperparameters, or every problem with them.
+    fn hyperparameters(&self) -> Result<(String, Hyperparameters), ApiError> {
+        let mut errors = Vec::new();
+        let mut error = |field: &str, message: String| errors.push(FieldError { field: Some(field.to_string()), message });
+
+        let given = [
+            ("degree", self.degree.is_some()), ("epochs", self.epochs.is_some()), ("learning_rate", self.learning_rate.is_some()),
+            ("trees", self.trees.is_some()), ("max_depth", self.max_depth.is_some()),
+        ];
+        let algorithm = match self.algorithm.as_deref().unwrap_or("linear").parse::<Algorithm>() {
+            Ok(algorithm) if algorithm.fits(&self.task) || !["climate", "footprint"].contains(&self.task.as_str()) => Some(algorithm),
+            Ok(algorithm) => {
+                error("algorithm", format!("{} models cannot be {}", self.task, algorithm));
+                None
+            }
+            Err(message) => {
+                error("algorithm", message);
+                None
+            }
+        };
+        let (hyperparameters, foreign) = match self.task.as_str() {
+            "climate" => {
+                let degree = self.degree.unwrap_or(persist::DEGREE);
+                let default_epochs = if algorithm == Some(Algorithm::Neural) { training::NEURAL_EPOCHS } else { training::EPOCHS };
+                let epochs = self.epochs.unwrap_or(default_epochs);
+                let learning_rate = self.learning_rate.unwrap_or(training::LEARNING_RATE);
+                if !DEGREES.contains(&degree) {
+                    error("degree", format!("must be between {} and {}", DEGREES.start(), DEGREES.end()));
+                }
+                if !EPOCHS.contains(&epochs) {
+                    error("epochs", format!("must be between {} and {}", EPOCHS.start(), EPOCHS.end()));
+                }
+                if !(learning_rate.is_finite() && learning_rate > 0.0 && learning_rate <= MAX_LEARNING_RATE) {
+                    error("learning_rate", format!("must be above 0 and at most {}", MAX_LEARNING_RATE));
+                }
+                // A polynomial temperature model is solved in one step, not by gradient descent
+                let foreign: &[&str] = if algorithm == Some(Algorithm::Polynomial) { &["epochs", "learning_rate", "trees", "max_depth"] } else { &["trees", "max_depth"] };
+                let gradient_descent = GradientDescent { epochs, learning_rate };
+                (algorithm.map(|algorithm| Hyperparameters::Climate { degree, algorithm, gradient_descent }), foreign)
+            }
+            "footprint" => {
+                let trees = self.trees.unwrap_or(training::TREES);
+                if !TREES.contains(&trees) {
+                    error("trees", format!("must be between {} and {}", TREES.start(), TREES.end()));
+                }
+                if self.max_depth == Some(0) {
+                    error("max_depth", "must be at least 1".to_string());
+                }
+                let foreign: &[&str] = &["degree", "epochs", "learning_rate"];
+                (algorithm.map(|algorithm| Hyperparameters::Footprint { algorithm, trees, max_depth: self.max_depth }), foreign)
+            }
+            task => {
+                error("task", format!("unknown task '{}' (expected climate or footprint)", task));
+                (None, &[][..])
+            }
+        };
+        let kind = match algorithm {
+            Some(Algorithm::Polynomial) => format!("polynomial {}", self.task),
+            _ => self.task.clone(),
+        };
+        for (field, _) in given.iter().filter(|(field, given)| *given && foreign.contains(field)) {
+            error(field, format!("does not apply to {} models", kind));
+        }
+        // Jobs publish beside the served defaults, never over them; requests select the result by name
+        let default_name = hyperparameters.as_ref().map_or(String::new(), |hyperparameters| format!("{}{}", hyperparameters.default_name(), TRAINED));
+
+        let name = self.name.clone().unwrap_or(default_name);
+        let valid_name = !name.is_empty() && name.len() <= 64 && name.chars().all(|c| c.is_ascii_lowercase() || c.is_ascii_digit() || c == '-' || c == '_');
+        if !valid_name && self.name.is_some() {
+            error("name", "must be 1 to 64 lowercase letters, digits, '-' or '_'".to_string());
+        } else if !name.ends_with(TRAINED) || name == TRAINED {
+            // A shipped name such as climate-polynomial would make the job's model the latest for every client selecting it
+            error("name", format!("must end in '{}' after at least one character, so it cannot replace a shipped model", TRAINED));
+        }
+
+        match hyperparameters {
+            Some(hyperparameters) if errors.is_empty() => Ok((name, hyperparameters)),
+            _ => Err(ApiError::validation(errors)),
+        }
+    }
+}
+
+/// A request allowed to start and cancel jobs: it carries `Authorization: Bearer <train_token>`.
+pub struct Trainer;
+
+#[rocket::async_trait]
+impl<'r> FromRequest<'r> for Trainer {
+    type Error = ApiError;
+
+    async fn from_request(request: &'r Request<'_>) -> request::Outcome<Self, ApiError> {
+        let refuse = |status: Status, error: &str, message: &str| {
+            request::Outcome::Error((status, ApiError { status, body: ErrorBody { error: error.to_string(), errors: vec![FieldError { field: None, message: message.to_string() }] } }))
+        };
+        let Some(token) = request.rocket().state::<Jobs>().and_then(|jobs| jobs.token.as_deref()) else {
+            return refuse(Status::Forbidden, "forbidden", "training is disabled; set train_token to enable it");
+        };
+        match request.headers().get_one("Authorization").and_then(|value| value.strip_prefix("Bearer ")) {
+            Some(given) if same(given.as_bytes(), token.as_bytes()) => request::Outcome::Success(Trainer),
+            _ => refuse(Status::Unauthorized, "unauthorized", "send the train_token as 'Authorization: Bearer <token>'"),
+        }
+    }
+}
+
+// Compares without stopping at the first difference, so timing does not reveal the token
+fn same(given: &[u8], token: &[u8]) -> bool {
+    given.len() == token.len() && given.iter().zip(token).fold(0, |difference, (a, b)| difference | (a ^ b)) == 0
+}
+
+fn unknown_job(id: u64) -> ApiError {
+    ApiError {
+        status: Status::NotFound,
+        body: ErrorBody { error: "unknown job".to_string(), errors: vec![FieldError { field: None, message: format!("no training job {}", id) }] },
+    }
+}
+
+//////////////////////////////////////// routes ////////////////////////////////////////
+
+/// Starts a training job; poll GET /jobs/<id> or follow GET /jobs/<id>/events for its progress.
+#[post("/train", data = "<data>")]
+pub fn train(trainer: Result<Trainer, ApiError>, data: Result<Json<TrainRequest>, json::Error<'_>>, jobs: &State<Jobs>, models: &State<Models>, charts: &State<Charts>) -> Result<(Status, Json<JobState>), ApiError> {
+    trainer?;
+    let (name, hyperparameters) = api::body(data)?.hyperparameters()?;
+    let job = jobs.start(name, hyperparameters, models.inner().clone(), charts)?;
+    Ok((Status::Accepted, Json(job.state())))
+}
+
+#[derive(Debug, Serialize)]
+#[serde(crate = "rocket::serde")]
+pub struct JobsResponse {
+    pub jobs: Vec<JobState>,
+}
+
+/// Every job of this run of the server, oldest first.
+#[get("/jobs")]
+pub fn jobs(jobs: &State<Jobs>) -> Json<JobsResponse> {
+    Json(JobsResponse { jobs: jobs.lock().values().map(|job| job.state()).collect() })
+}
+
+#[get("/jobs/<id>")]
+pub fn job(id: u64, jobs: &State<Jobs>) -> Result<Json<JobState>, ApiError> {
+    jobs.get(id).map(|job| Json(job.state())).ok_or_else(|| unknown_job(id))
+}
+
+/// Server-sent events of a job: a `progress` event per epoch, replayed from the first for late
+/// subscribers (the last REPLAYED once the job has finished), then one `finished` event with the
+/// final state, after which the stream ends.
+#[get("/jobs/<id>/events")]
+pub fn events(id: u64, jobs: &State<Jobs>, mut shutdown: Shutdown) -> Result<EventStream![], ApiError> {
+    let job = jobs.get(id).ok_or_else(|| unknown_job(id))?;
+    let mut updates = job.updates.subscribe();
+    Ok(EventStream! {
+        let mut sent = 0;
+        loop {
+            let unsent = job.since(sent);
+            for progress in &unsent.progress {
+                yield Event::json(progress).event("progress");
+            }
+            sent = unsent.sent;
+            if unsent.status.is_finished() {
+                yield Event::json(&job.state()).event("finished");
+                break;
+            }
+            tokio::select! {
+                changed = updates.changed() => if changed.is_err() { break },
+                _ = &mut shutdown => break,
+            }
+        }
+    })
+}
+
+/// Cancels a job: a queued job never starts, a running one stops after its current epoch.
+#[delete("/jobs/<id>")]
+pub fn cancel(trainer: Result<Trainer, ApiError>, id: u64, jobs: &State<Jobs>) -> Result<(Status, Json<JobState>), ApiError> {
+    trainer?;
+    let job = jobs.get(id).ok_or_else(|| unknown_job(id))?;
+    job.cancel().map_err(|status| ApiError {
+        status: Status::Conflict,
+        body: ErrorBody { error: "finished".to_string(), errors: vec![FieldError { field: None, message: format!("job {} has already {}", id, status.name()) }] },
+    })?;
+    Ok((Status::Accepted, Json(job.state())))
+}
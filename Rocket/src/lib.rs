@@ -0,0 +1,39 @@
+// Web half of ClimatePredict: the Rocket server, built here so the binary and the integration tests share it
+
+#![recursion_limit = "256"] // The OpenAPI document in api.rs is one large json! literal
+
+#[macro_use] extern crate rocket;
+
+pub mod api;
+pub mod assets;
+pub mod behaviors;
+pub mod charts;
+pub mod handle;
+pub mod health;
+pub mod jobs;
+pub mod metrics;
+pub mod models;
+pub mod projection;
+
+use rocket::{Build, Rocket};
+
+/// The server with every route and catcher mounted, ready to launch or to test with a local client.
+pub fn build() -> Rocket<Build> {
+    rocket::build()
+        .attach(metrics::Instrument)
+        .attach(models::stage())
+        .attach(behaviors::stage())
+        .attach(assets::stage())
+        .attach(charts::stage())
+        .attach(projection::stage())
+        .attach(jobs::stage())
+        .attach(models::watch())
+        .mount("/", routes![handle::index, handle::asset, handle::predict])
+        .mount("/", health::routes())
+        .mount("/", routes![metrics::metrics])
+        .mount(api::BASE, api::routes())
+        .mount(api::BASE, jobs::routes())
+        .mount(charts::BASE, charts::routes())
+        .register("/", catchers![handle::not_found])
+        .register(api::BASE, catchers![api::error])
+}
@@ -0,0 +1,146 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::models::Models;
+use rocket::fairing::{self, Fairing, Info, Kind};
+use rocket::http::ContentType;
+use rocket::{Build, Data, Request, Response, Rocket, State};
+use std::collections::BTreeMap;
+use std::fmt::Write;
+use std::sync::{Mutex, MutexGuard, PoisonError};
+use std::time::Instant;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+const BUCKETS: [f64; 12] = [0.001, 0.005, 0.01, 0.025, 0.05, 0.1, 0.25, 0.5, 1.0, 2.5, 5.0, 10.0]; // Upper bounds of the latency histogram, in seconds
+const UNMATCHED: &str = "unmatched"; // Route label of requests no route was tried for; labels are route paths, never request paths
+
+//////////////////////////////////////// recording ////////////////////////////////////////
+
+/// Request latencies of a route, bucketed as a Prometheus histogram.
+#[derive(Default)]
+struct Histogram {
+    counts: [u64; BUCKETS.len()], // Requests at or under each bound
+    count: u64,
+    sum: f64,
+}
+
+impl Histogram {
+    fn observe(&mut self, seconds: f64) {
+        for (count, bound) in self.counts.iter_mut().zip(BUCKETS) {
+            if seconds <= bound {
+                *count += 1;
+            }
+        }
+        self.count += 1;
+        self.sum += seconds;
+    }
+}
+
+#[derive(Default)]
+struct Recorded {
+    requests: BTreeMap<(String, String, u16), u64>, // By method, route and status
+    latencies: BTreeMap<String, Histogram>,         // By route
+}
+
+/// Request counts and latencies since the server started.
+#[derive(Default)]
+pub struct Metrics {
+    recorded: Mutex<Recorded>,
+}
+
+impl Metrics {
+    fn lock(&self) -> MutexGuard<'_, Recorded> {
+        self.recorded.lock().unwrap_or_else(PoisonError::into_inner)
+    }
+
+    pub fn record(&self, method: &str, route: &str, status: u16, seconds: f64) {
+        let mut recorded = self.lock();
+        *recorded.requests.entry((method.to_string(), route.to_string(), status)).or_default() += 1;
+        recorded.latencies.entry(route.to_string()).or_default().observe(seconds);
+    }
+
+    /// Everything in the Prometheus text exposition format, with the registry's models.
+    pub fn render(&self, models: &Models) -> String {
+        let recorded = self.lock();
+        let mut text = String::new();
+
+        header(&mut text, "http_requests_total", "counter", "Requests handled, by method, route and status.");
+        for ((method, route, status), count) in &recorded.requests {
+            let _ = writeln!(text, "http_requests_total{{method=\"{}\",route=\"{}\",status=\"{}\"}} {}", method, escape(route), status, count);
+        }
+
+        header(&mut text, "http_request_duration_seconds", "histogram", "Time from receiving a request to sending its response head, by route.");
+        for (route, histogram) in &recorded.latencies {
+            let route = escape(route);
+            for (bound, count) in BUCKETS.iter().zip(histogram.counts) {
+                let _ = writeln!(text, "http_request_duration_seconds_bucket{{route=\"{}\",le=\"{}\"}} {}", route, bound, count);
+            }
+            let _ = writeln!(text, "http_request_duration_seconds_bucket{{route=\"{}\",le=\"+Inf\"}} {}", route, histogram.count);
+            let _ = writeln!(text, "http_request_duration_seconds_sum{{route=\"{}\"}} {}", route, histogram.sum);
+            let _ = writeln!(text, "http_request_duration_seconds_count{{route=\"{}\"}} {}", route, histogram.count);
+        }
+
+        header(&mut text, "model_predictions_total", "counter", "Predictions computed by each model, by artifact name and version; scenario projections count under the unversioned energy-balance model, and cached charts are not counted.");
+        for (id, count) in models.predictions() {
+            let (name, version) = id.rsplit_once('@').unwrap_or((&id, ""));
+            let _ = writeln!(text, "model_predictions_total{{model=\"{}\",version=\"{}\"}} {}", escape(name), version, count);
+        }
+
+        header(&mut text, "model_load_seconds", "gauge", "Time taken to read and parse each loaded model artifact.");
+        for (metadata, load_time) in models.load_times() {
+            let _ = writeln!(text, "model_load_seconds{{model=\"{}\",version=\"{}\"}} {}", escape(&metadata.name), metadata.version, load_time.as_secs_f64());
+        }
+        text
+    }
+}
+
+fn header(text: &mut String, name: &str, kind: &str, help: &str) {
+    let _ = writeln!(text, "# HELP {} {}", name, help);
+    let _ = writeln!(text, "# TYPE {} {}", name, kind);
+}
+
+// Label values escape backslashes, quotes and newlines
+fn escape(value: &str) -> String {
+    value.replace('\\', "\\\\").replace('"', "\\\"").replace('\n', "\\n")
+}
+
+//////////////////////////////////////// fairing ////////////////////////////////////////
+
+// When the request arrived, kept in the request's local cache
+struct Started(Instant);
+
+/// Times every request and counts it under its route's path (without the query).
+pub struct Instrument;
+
+#[rocket::async_trait]
+impl Fairing for Instrument {
+    fn info(&self) -> Info {
+        Info { name: "Metrics", kind: Kind::Ignite | Kind::Request | Kind::Response }
+    }
+
+    async fn on_ignite(&self, rocket: Rocket<Build>) -> fairing::Result {
+        Ok(rocket.manage(Metrics::default()))
+    }
+
+    async fn on_request(&self, request: &mut Request<'_>, _: &mut Data<'_>) {
+        request.local_cache(|| Started(Instant::now()));
+    }
+
+    async fn on_response<'r>(&self, request: &'r Request<'_>, response: &mut Response<'r>) {
+        let Some(metrics) = request.rocket().state::<Metrics>() else {
+            return;
+        };
+        let seconds = request.local_cache(|| Started(Instant::now())).0.elapsed().as_secs_f64();
+        let route = request.route().map_or(UNMATCHED, |route| route.uri.as_str().split('?').next().unwrap_or_default());
+        metrics.record(request.method().as_str(), route, response.status().code, seconds);
+    }
+}
+
+//////////////////////////////////////// routes ////////////////////////////////////////
+
+/// Request, prediction and model metrics for Prometheus to scrape.
+#[get("/metrics")]
+pub fn metrics(metrics: &State<Metrics>, models: &State<Models>) -> (ContentType, String) {
+    let content_type = ContentType::new("text", "plain").with_params([("version", "0.0.4"), ("charset", "utf-8")]);
+    (content_type, metrics.render(models))
+}
@@ -0,0 +1,285 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::charts::Charts;
+use climate_predict::footprint::FootprintModel;
+use climate_predict::persist::{self, Artifact, ClimateModel, Metadata, SavedModel};
+use rocket::fairing::AdHoc;
+use rocket::tokio::task::spawn_blocking;
+use rocket::tokio::time;
+use std::collections::BTreeMap;
+use std::error::Error;
+use std::fs;
+use std::path::{Path, PathBuf};
+use std::sync::{Arc, Mutex, PoisonError, RwLock, RwLockReadGuard};
+use std::time::{Duration, Instant, SystemTime};
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const MODELS: &str = "../Project/models"; // Written by `cargo run --bin train` in Project
+pub const POLL_MS: u64 = 2000; // How often the directory is checked for new or updated artifacts
+
+//////////////////////////////////////// selection ////////////////////////////////////////
+
+/// Which model a request wants, as in `?model=footprint-forest&version=2`; by default the
+/// latest version of persist::CLIMATE or persist::FOOTPRINT.
+#[derive(Debug, Default, FromForm)]
+pub struct Selector {
+    pub model: Option<String>,
+    pub version: Option<u32>,
+}
+
+/// A loaded model with its metadata.
+pub struct Entry<T> {
+    pub metadata: Arc<Metadata>,
+    pub model: Arc<T>,
+    pub file: PathBuf,
+    pub load_time: Duration, // Reading and parsing the artifact
+}
+
+impl<T> Clone for Entry<T> {
+    fn clone(&self) -> Self {
+        Self { metadata: self.metadata.clone(), model: self.model.clone(), file: self.file.clone(), load_time: self.load_time }
+    }
+}
+
+impl<T> Entry<T> {
+    /// `name@version`, as reported in responses.
+    pub fn id(&self) -> String {
+        format!("{}@{}", self.metadata.name, self.metadata.version)
+    }
+}
+
+// Latest version of the selected name, or the exact version asked for
+fn select<T>(entries: &[Entry<T>], task: &str, default: &str, selector: &Selector) -> Result<Entry<T>, String> {
+    let name = selector.model.as_deref().unwrap_or(default);
+    let candidates = entries.iter().filter(|entry| entry.metadata.name == name);
+    let found = match selector.version {
+        Some(version) => candidates.into_iter().find(|entry| entry.metadata.version == version),
+        None => candidates.max_by_key(|entry| entry.metadata.version),
+    };
+    found.cloned().ok_or_else(|| match selector.version {
+        Some(version) => format!("no {} model '{}' with version {}", task, name, version),
+        None => format!("no {} model '{}'", task, name),
+    })
+}
+
+//////////////////////////////////////// registry ////////////////////////////////////////
+
+// Modification time and size of an artifact file; a change in either means it was rewritten
+type Stamp = (SystemTime, u64);
+
+#[derive(Default, Clone)]
+struct Loaded {
+    climate: Vec<Entry<ClimateModel>>,
+    footprint: Vec<Entry<FootprintModel>>,
+    stamps: BTreeMap<PathBuf, Stamp>,
+}
+
+impl Loaded {
+    fn add(&mut self, file: &Path, artifact: Artifact, load_time: Duration) {
+        let metadata = Arc::new(artifact.metadata);
+        let file = file.to_path_buf();
+        match artifact.model {
+            SavedModel::Climate(model) => self.climate.push(Entry { metadata, model: Arc::new(model), file, load_time }),
+            SavedModel::Footprint(model) => self.footprint.push(Entry { metadata, model: Arc::new(model), file, load_time }),
+        }
+    }
+
+    // Keeps what was loaded from `file` before
+    fn carry_over(&mut self, previous: &Loaded, file: &Path) {
+        self.climate.extend(previous.climate.iter().filter(|entry| entry.file == file).cloned());
+        self.footprint.extend(previous.footprint.iter().filter(|entry| entry.file == file).cloned());
+    }
+}
+
+/// Every model artifact in a directory, reloaded when files are added, rewritten or removed.
+/// Clones share the same models.
+#[derive(Clone)]
+pub struct Models {
+    directory: PathBuf,
+    loaded: Arc<RwLock<Loaded>>,
+    refreshing: Arc<Mutex<()>>, // Held for a whole refresh, so a slow one cannot overwrite a newer result
+    predictions: Arc<Mutex<BTreeMap<String, u64>>>, // Predictions computed, by `name@version`
+}
+
+impl Models {
+    /// Reads every artifact in `directory`; it needs at least one climate and one footprint model.
+    pub fn load(directory: &str) -> Result<Self, Box<dyn Error>> {
+        let models = Self {
+            directory: PathBuf::from(directory),
+            loaded: Arc::new(RwLock::new(Loaded::default())),
+            refreshing: Arc::new(Mutex::new(())),
+            predictions: Arc::new(Mutex::new(BTreeMap::new())),
+        };
+        models.refresh()?;
+
+        let loaded = models.read();
+        if loaded.climate.is_empty() || loaded.footprint.is_empty() {
+            return Err(format!("{} needs a climate and a footprint model", directory).into());
+        }
+        drop(loaded);
+        Ok(models)
+    }
+
+    /// Reloads the artifacts whose files changed since the last call.
+    ///
+    /// An artifact that cannot be read keeps its previous version, so a half-written file
+    /// does not take a model away; removed files drop their models. The watcher and training jobs
+    /// both refresh, one at a time: once a call returns, every file saved before it is loaded.
+    ///
+    /// Returns:
+    ///     Whether anything changed.
+    pub fn refresh(&self) -> Result<bool, Box<dyn Error>> {
+        let _refreshing = self.refreshing.lock().unwrap_or_else(PoisonError::into_inner);
+        let stamps = stamps(&self.directory)?;
+        let previous = self.read().clone();
+        if stamps == previous.stamps {
+            return Ok(false);
+        }
+
+        let mut loaded = Loaded { stamps: stamps.clone(), ..Loaded::default() };
+        for (file, stamp) in &stamps {
+            if previous.stamps.get(file) == Some(stamp) {
+                loaded.carry_over(&previous, file);
+                continue;
+            }
+            let started = Instant::now();
+            match persist::load::<Artifact>(&file.to_string_lossy()) {
+                Ok(artifact) => {
+                    info!("Loaded model {} version {} from {}", artifact.metadata.name, artifact.metadata.version, file.display());
+                    loaded.add(file, artifact, started.elapsed());
+                }
+                Err(error) => {
+                    warn!("{}; keeping what was loaded from it before", error);
+                    loaded.carry_over(&previous, file);
+                }
+            }
+        }
+        *self.loaded.write().unwrap_or_else(PoisonError::into_inner) = loaded;
+        Ok(true)
+    }
+
+    /// Where the artifacts are read from, and where trained models are published.
+    pub fn directory(&self) -> &Path {
+        &self.directory
+    }
+
+    /// The selected climate model, by default the latest persist::CLIMATE.
+    pub fn climate(&self, selector: &Selector) -> Result<Entry<ClimateModel>, String> {
+        select(&self.read().climate, "climate", persist::CLIMATE, selector)
+    }
+
+    /// The selected footprint model, by default the latest persist::FOOTPRINT.
+    pub fn footprint(&self, selector: &Selector) -> Result<Entry<FootprintModel>, String> {
+        select(&self.read().footprint, "footprint", persist::FOOTPRINT, selector)
+    }
+
+    /// Counts a prediction answered by the model `name@version`; handlers call it once the
+    /// prediction is computed, so lookups, failures and cached answers are not counted.
+    pub fn count(&self, id: String) {
+        *self.predictions.lock().unwrap_or_else(PoisonError::into_inner).entry(id).or_default() += 1;
+    }
+
+    /// Whether a request naming no model can be answered, i.e. both default models are loaded.
+    pub fn is_ready(&self) -> bool {
+        let loaded = self.read();
+        let default = Selector::default();
+        select(&loaded.climate, "climate", persist::CLIMATE, &default).is_ok() && select(&loaded.footprint, "footprint", persist::FOOTPRINT, &default).is_ok()
+    }
+
+    /// Predictions computed by each model since the server started, by `name@version`.
+    pub fn predictions(&self) -> BTreeMap<String, u64> {
+        self.predictions.lock().unwrap_or_else(PoisonError::into_inner).clone()
+    }
+
+    /// How long each loaded model took to read, with its metadata.
+    pub fn load_times(&self) -> Vec<(Arc<Metadata>, Duration)> {
+        let loaded = self.read();
+        loaded.climate.iter().map(|entry| (entry.metadata.clone(), entry.load_time))
+            .chain(loaded.footprint.iter().map(|entry| (entry.metadata.clone(), entry.load_time)))
+            .collect()
+    }
+
+    /// Metadata of every loaded model with its task and whether requests get it by default,
+    /// sorted by name and version.
+    pub fn list(&self) -> Vec<(&'static str, Arc<Metadata>, bool)> {
+        let loaded = self.read();
+        let defaults = [
+            select(&loaded.climate, "climate", persist::CLIMATE, &Selector::default()).ok().map(|entry| entry.id()),
+            select(&loaded.footprint, "footprint", persist::FOOTPRINT, &Selector::default()).ok().map(|entry| entry.id()),
+        ];
+        let mut list: Vec<_> = loaded.climate.iter().map(|entry| ("climate", entry.metadata.clone(), entry.id()))
+            .chain(loaded.footprint.iter().map(|entry| ("footprint", entry.metadata.clone(), entry.id())))
+            .map(|(task, metadata, id)| (task, metadata, defaults.contains(&Some(id))))
+            .collect();
+        list.sort_by(|(_, a, _), (_, b, _)| (&a.name, a.version).cmp(&(&b.name, b.version)));
+        list
+    }
+
+    fn read(&self) -> RwLockReadGuard<'_, Loaded> {
+        self.loaded.read().unwrap_or_else(PoisonError::into_inner)
+    }
+}
+
+// The JSON files of a directory with their stamps
+fn stamps(directory: &Path) -> Result<BTreeMap<PathBuf, Stamp>, Box<dyn Error>> {
+    let entries = fs::read_dir(directory).map_err(|error| format!("cannot read models directory {}: {}", directory.display(), error))?;
+    let mut stamps = BTreeMap::new();
+    for entry in entries {
+        let path = entry?.path();
+        if path.extension().is_some_and(|extension| extension == "json") {
+            let metadata = fs::metadata(&path)?;
+            stamps.insert(path, (metadata.modified()?, metadata.len()));
+        }
+    }
+    Ok(stamps)
+}
+
+//////////////////////////////////////// fairings ////////////////////////////////////////
+
+/// Loads the saved models when the server ignites, from the `model_dir` setting (`Rocket.toml`
+/// or `ROCKET_MODEL_DIR`) or MODELS; the server refuses to start without them.
+pub fn stage() -> AdHoc {
+    AdHoc::try_on_ignite("Models", |rocket| async {
+        let directory: String = rocket.figment().extract_inner("model_dir").unwrap_or_else(|_| MODELS.to_string());
+        match Models::load(&directory) {
+            Ok(models) => {
+                info!("Loaded {} models from {}", models.list().len(), directory);
+                Ok(rocket.manage(models))
+            }
+            Err(error) => {
+                error!("{} (run `cargo run --bin train` in Project)", error);
+                Err(rocket)
+            }
+        }
+    })
+}
+
+/// Once the server is up, checks the models directory every `model_poll_ms` milliseconds
+/// (POLL_MS by default) and reloads changed artifacts, clearing the chart cache when it does.
+pub fn watch() -> AdHoc {
+    AdHoc::on_liftoff("Model reload", |rocket| Box::pin(async move {
+        let poll: u64 = rocket.figment().extract_inner("model_poll_ms").unwrap_or(POLL_MS);
+        let (Some(models), Some(charts)) = (rocket.state::<Models>(), rocket.state::<Charts>()) else {
+            return;
+        };
+        let (models, cache, mut shutdown) = (models.clone(), charts.cache.clone(), rocket.shutdown());
+        rocket::tokio::spawn(async move {
+            let mut interval = time::interval(Duration::from_millis(poll.max(1)));
+            loop {
+                rocket::tokio::select! {
+                    _ = &mut shutdown => break,
+                    _ = interval.tick() => {}
+                }
+                // Reading and parsing artifacts is blocking file I/O, kept off the async workers
+                let reloading = models.clone();
+                match spawn_blocking(move || reloading.refresh().map_err(|error| error.to_string())).await {
+                    Ok(Ok(true)) => cache.clear(),
+                    Ok(Ok(false)) => {}
+                    Ok(Err(error)) => warn!("{}", error),
+                    Err(error) => warn!("model reload panicked: {}", error),
+                }
+            }
+        });
+    }))
+}
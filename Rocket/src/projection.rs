@@ -0,0 +1,33 @@
+//////////////////////////////////////// dependencies ////////////////////////////////////////
+
+use crate::jobs::CLIMATE_DATA;
+use climate_predict::energy_balance::EnergyBalanceModel;
+use rocket::fairing::AdHoc;
+
+//////////////////////////////////////// global variables ////////////////////////////////////////
+
+pub const PROJECTION: &str = "energy-balance"; // What scenario projections are counted under in model_predictions_total
+
+//////////////////////////////////////// state ////////////////////////////////////////
+
+/// Calibrates the energy-balance model that projects scenario temperatures when the server ignites,
+/// on the `climate_data` setting (`ROCKET_CLIMATE_DATA`) or CLIMATE_DATA; the server refuses to start without it,
+/// or if the file does not run over the years of the model's built-in CO2 concentrations (1880–2023).
+///
+/// Scenarios need a model with memory: the regressions map each year's emissions to that year's
+/// temperature, so a pathway reaching net zero would fall back to the preindustrial anomaly.
+pub fn stage() -> AdHoc {
+    AdHoc::try_on_ignite("Projection", |rocket| async {
+        let path: String = rocket.figment().extract_inner("climate_data").unwrap_or_else(|_| CLIMATE_DATA.to_string());
+        match EnergyBalanceModel::calibrate_on(&path) {
+            Ok(model) => {
+                info!("Calibrated the energy balance model on {}: {:.2} °C per doubling of CO2", path, model.parameters.climate_sensitivity());
+                Ok(rocket.manage(model))
+            }
+            Err(error) => {
+                error!("{}", error);
+                Err(rocket)
+            }
+        }
+    })
+}
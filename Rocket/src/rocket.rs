@@ -1,3 +1,8 @@
-fn main() {
-    print!("Ok");
-}
\ No newline at end of file
+#[macro_use] extern crate rocket;
+
+#[launch]
+fn rocket() -> _ {
+    rocket_nn::build()
+}
+
+// cargo run --bin rock
@@ -0,0 +1,307 @@
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::{Client, LocalResponse};
+use rocket::serde::json::{json, Value};
+
+fn client() -> Client {
+    Client::tracked(rocket_nn::build()).expect("valid rocket instance")
+}
+
+fn post<'c>(client: &'c Client, path: &str, body: Value) -> LocalResponse<'c> {
+    client.post(format!("/api/v1{}", path)).header(ContentType::JSON).body(body.to_string()).dispatch()
+}
+
+fn footprint() -> Value {
+    json!({
+        "body_type": "overweight", "sex": "female", "diet": "pescatarian", "how_often_shower": "daily",
+        "heating_energy_source": "coal", "transport": "public", "vehicle_type": "", "social_activity": "often",
+        "monthly_grocery_bill": 230, "frequency_of_traveling_by_air": "frequently", "vehicle_monthly_distance_km": 210,
+        "waste_bag_size": "large", "waste_bag_weekly_count": 4, "how_long_tv_pc_daily_hour": 7,
+        "how_many_new_clothes_monthly": 26, "how_long_internet_daily_hour": 1, "energy_efficiency": "No",
+        "recycling": ["Metal"], "cooking_with": ["Stove", "Oven"]
+    })
+}
+
+#[test]
+fn temperature_for_emissions() {
+    let client = client();
+    let response = post(&client, "/predict/temperature", json!({ "emissions": 40.0 }));
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JSON));
+    let body: Value = response.into_json().unwrap();
+    let temperature = &body["temperature"];
+    assert_eq!(temperature["unit"], "°C");
+    assert!(temperature["low"].as_f64() < temperature["mean"].as_f64());
+    assert!(temperature["mean"].as_f64() < temperature["high"].as_f64());
+}
+
+#[test]
+fn emissions_for_a_year() {
+    let client = client();
+    let body: Value = post(&client, "/predict/emissions", json!({ "year": 2000 })).into_json().unwrap();
+
+    assert_eq!(body["year"], 2000);
+    assert_eq!(body["emissions"]["unit"], "GtCO₂");
+    assert_eq!(body["extrapolated"], false);
+
+    let body: Value = post(&client, "/predict/emissions", json!({ "year": 2100 })).into_json().unwrap();
+    assert_eq!(body["extrapolated"], true);
+}
+
+#[test]
+fn footprint_for_answers() {
+    let client = client();
+    let response = post(&client, "/predict/footprint", footprint());
+
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().unwrap();
+    let monthly = body["monthly_emissions"].as_f64().unwrap();
+    assert!(monthly > 0.0);
+    assert!((body["annual_emissions"].as_f64().unwrap() - 12.0 * monthly).abs() < 1e-6);
+    assert_eq!(body["unit"], "kg CO₂");
+
+    // Contributions explain the whole difference from the average respondent
+    let total: f64 = body["contributions"].as_array().unwrap().iter().map(|c| c["monthly_emissions"].as_f64().unwrap()).sum();
+    assert!((body["baseline"].as_f64().unwrap() + total - monthly).abs() < 1e-6);
+}
+
+#[test]
+fn models_list_the_loaded_artifacts() {
+    let client = client();
+    let response = client.get("/api/v1/models").dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().unwrap();
+    let models = body["models"].as_array().unwrap();
+    let climate = models.iter().find(|model| model["name"] == "climate").expect("the climate model is listed");
+    assert_eq!(climate["task"], "climate");
+    assert_eq!(climate["default"], true);
+    assert!(climate["metrics"]["emissions_train_rmse"].as_f64().unwrap() > 0.0);
+    assert!(models.iter().any(|model| model["name"] == "footprint" && model["task"] == "footprint"));
+}
+
+#[test]
+fn predictions_name_the_model_that_answered() {
+    let client = client();
+    let body: Value = post(&client, "/predict/temperature", json!({ "emissions": 40.0 })).into_json().unwrap();
+    assert_eq!(body["artifact"], "climate@1");
+    assert_eq!(body["model"], "linear");
+    let body: Value = post(&client, "/predict/temperature?model=climate-polynomial", json!({ "emissions": 40.0 })).into_json().unwrap();
+    assert_eq!(body["model"], "polynomial (degree 2)");
+    let body: Value = post(&client, "/predict/temperature?model=climate-neural", json!({ "emissions": 40.0 })).into_json().unwrap();
+    assert_eq!(body["artifact"], "climate-neural@1");
+    assert_eq!(body["model"], "neural network (8 hidden units)");
+    assert!((body["temperature"]["mean"].as_f64().unwrap() - 1.3).abs() < 0.5, "{}", body);
+
+    let body: Value = post(&client, "/predict/emissions?model=climate&version=1", json!({ "year": 2000 })).into_json().unwrap();
+    assert_eq!(body["artifact"], "climate@1");
+    let body: Value = post(&client, "/predict/footprint?model=footprint", footprint()).into_json().unwrap();
+    assert_eq!(body["artifact"], "footprint@1");
+    assert_eq!(body["model"], "linear");
+}
+
+#[test]
+fn unknown_models_are_not_found() {
+    let client = client();
+    let response = post(&client, "/predict/temperature?model=nonexistent", json!({ "emissions": 40.0 }));
+
+    assert_eq!(response.status(), Status::NotFound);
+    let body: Value = response.into_json().unwrap();
+    assert_eq!(body["errors"][0]["field"], "model");
+
+    // The footprint model is not a climate model
+    let response = client.get("/api/v1/scenarios?model=footprint").dispatch();
+    assert_eq!(response.status(), Status::NotFound);
+    let response = post(&client, "/predict/emissions?version=99", json!({ "year": 2000 }));
+    assert_eq!(response.status(), Status::NotFound);
+}
+
+#[test]
+fn behaviors_list_the_catalogue_and_durations() {
+    let client = client();
+    let response = client.get("/api/v1/behaviors").dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().unwrap();
+    let driving = &body["behaviors"][0];
+    assert_eq!(driving["name"], "driving");
+    assert_eq!(driving["unit"], "km");
+    let frequencies: Vec<&str> = body["frequencies"].as_array().unwrap().iter().map(|f| f["name"].as_str().unwrap()).collect();
+    assert_eq!(frequencies, ["daily", "weekly", "biweekly", "monthly", "annually"]);
+    let weekly = &body["frequencies"][1]["durations"];
+    assert_eq!(weekly.as_array().unwrap().len(), 7);
+    assert_eq!(weekly[2], json!({ "value": 3, "label": "3 day(s) per week" }));
+}
+
+#[test]
+fn behavior_is_annualized() {
+    let client = client();
+    let response = post(&client, "/predict/behavior", json!({ "behavior": "driving", "frequency": "weekly", "duration": 2 }));
+
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().unwrap();
+    assert_eq!(body["schedule"], "2 day(s) per week");
+    // Two one-hour days a week at 40 km/h and 0.17 kg CO₂/km
+    let hours = 2.0 * 365.25 / 7.0;
+    assert!((body["hours"].as_f64().unwrap() - hours).abs() < 1e-9);
+    assert!((body["quantity"].as_f64().unwrap() - 40.0 * hours).abs() < 1e-9);
+    assert!((body["annual_emissions"].as_f64().unwrap() - 0.17 * 40.0 * hours).abs() < 1e-9);
+
+    let body: Value = post(&client, "/predict/behavior", json!({ "behavior": "heating-gas", "frequency": "annually", "duration": 6 })).into_json().unwrap();
+    assert_eq!(body["unit"], "day");
+    assert!((body["quantity"].as_f64().unwrap() - 6.0 * 365.25 / 12.0).abs() < 1e-9);
+
+    // A daily behavior counted per day happens on at most every day of the year, however many hours it runs
+    let body: Value = post(&client, "/predict/behavior", json!({ "behavior": "heating-gas", "frequency": "daily", "duration": 24 })).into_json().unwrap();
+    assert!((body["quantity"].as_f64().unwrap() - 365.25).abs() < 1e-9);
+    assert!((body["annual_emissions"].as_f64().unwrap() - 6.0 * 365.25).abs() < 1e-9);
+    let body: Value = post(&client, "/predict/behavior", json!({ "behavior": "heating-gas", "frequency": "daily", "duration": 4 })).into_json().unwrap();
+    assert!((body["quantity"].as_f64().unwrap() - 0.5 * 365.25).abs() < 1e-9);
+}
+
+#[test]
+fn unknown_behaviors_and_frequencies_are_field_errors() {
+    let client = client();
+    let response = post(&client, "/predict/behavior", json!({ "behavior": "teleporting", "frequency": "hourly", "duration": 1 }));
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().unwrap();
+    let fields: Vec<&str> = body["errors"].as_array().unwrap().iter().map(|e| e["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, ["behavior", "frequency"]);
+
+    let response = post(&client, "/predict/behavior", json!({ "behavior": "driving", "frequency": "daily", "duration": 25 }));
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.into_json::<Value>().unwrap()["errors"][0]["field"], "duration");
+}
+
+fn pathway() -> Value {
+    json!({ "name": "net zero", "points": [{ "year": 2023, "emissions": 40.0 }, { "year": 2050, "emissions": 0.0 }, { "year": 2100, "emissions": 0.0 }] })
+}
+
+#[test]
+fn scenarios_list_the_presets() {
+    let client = client();
+    let body: Value = client.get("/api/v1/scenarios").dispatch().into_json().unwrap();
+
+    assert_eq!(body["start"], 2023);
+    assert_eq!(body["end"], 2100);
+    let names: Vec<&str> = body["presets"].as_array().unwrap().iter().map(|p| p["name"].as_str().unwrap()).collect();
+    assert_eq!(names, ["SSP1-1.9", "SSP1-2.6", "SSP2-4.5", "SSP3-7.0", "SSP5-8.5"]);
+    assert_eq!(body["presets"][0]["points"][0]["emissions"], body["level"]);
+}
+
+#[test]
+fn scenario_projects_every_year() {
+    let client = client();
+    let response = post(&client, "/scenario", pathway());
+
+    assert_eq!(response.status(), Status::Ok);
+    let body: Value = response.into_json().unwrap();
+    assert_eq!(body["name"], "net zero");
+    assert_eq!(body["years"].as_array().unwrap().len(), 2100 - 2023 + 1);
+    assert_eq!(body["emissions"][27], 0.0);
+    let temperature = &body["temperature"];
+    assert_eq!(temperature["unit"], "°C");
+    assert_eq!(temperature["mean"].as_array().unwrap().len(), 78);
+    assert_eq!(body["model"], "energy balance");
+    // The warming is set by cumulative emissions, so it stays near its peak after net zero instead
+    // of falling back to the preindustrial anomaly
+    let mean: Vec<f64> = temperature["mean"].as_array().unwrap().iter().map(|t| t.as_f64().unwrap()).collect();
+    let peak = mean.iter().copied().fold(f64::NEG_INFINITY, f64::max);
+    assert!(peak > mean[0]);
+    assert!(mean[77] > mean[0] - 0.2 && mean[77] > peak - 0.5, "{:?}", mean);
+    assert!(temperature["low"][77].as_f64() < temperature["mean"][77].as_f64());
+    assert!(temperature["mean"][77].as_f64() < temperature["high"][77].as_f64());
+}
+
+#[test]
+fn invalid_pathways_are_field_errors() {
+    let client = client();
+    let response = post(&client, "/scenario", json!({ "points": [{ "year": 2050, "emissions": 10.0 }, { "year": 2040, "emissions": 5000.0 }] }));
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().unwrap();
+    let fields: Vec<&str> = body["errors"].as_array().unwrap().iter().map(|e| e["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, ["points[1].year", "points[1].emissions"]);
+
+    let response = post(&client, "/scenario", json!({ "points": [{ "year": 2050, "emissions": 10.0 }] }));
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.into_json::<Value>().unwrap()["errors"][0]["field"], "points");
+}
+
+#[test]
+fn out_of_range_values_are_field_errors() {
+    let client = client();
+    let response = post(&client, "/predict/emissions", json!({ "year": 3000 }));
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().unwrap();
+    assert_eq!(body["error"], "validation");
+    assert_eq!(body["errors"][0]["field"], "year");
+
+    let response = post(&client, "/predict/temperature", json!({ "emissions": -1.0 }));
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.into_json::<Value>().unwrap()["errors"][0]["field"], "emissions");
+}
+
+#[test]
+fn every_invalid_answer_is_reported() {
+    let client = client();
+    let mut answers = footprint();
+    answers["diet"] = json!("carnivore");
+    answers["recycling"] = json!(["Metal", "Wood"]);
+    answers["waste_bag_weekly_count"] = json!(-2);
+    let response = post(&client, "/predict/footprint", answers);
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().unwrap();
+    let fields: Vec<&str> = body["errors"].as_array().unwrap().iter().map(|e| e["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, ["diet", "recycling", "waste_bag_weekly_count"]);
+}
+
+#[test]
+fn malformed_bodies_are_structured_errors() {
+    let client = client();
+    let response = client.post("/api/v1/predict/emissions").header(ContentType::JSON).body("{\"year\": \"soon\"}").dispatch();
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().unwrap();
+    assert_eq!(body["error"], "validation");
+    assert!(body["errors"][0]["field"].is_null());
+    assert!(body["errors"][0]["message"].as_str().unwrap().contains("invalid type"));
+}
+
+#[test]
+fn unknown_api_paths_answer_in_json() {
+    let client = client();
+    let response = client.get("/api/v1/nowhere").dispatch();
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.into_json::<Value>().unwrap()["error"], "not found");
+}
+
+#[test]
+fn openapi_document_lists_the_endpoints() {
+    let client = client();
+    let response = client.get("/api/v1/openapi.json").dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    let document: Value = response.into_json().unwrap();
+    assert_eq!(document["openapi"], "3.0.3");
+    for path in ["/predict/temperature", "/predict/emissions", "/predict/footprint", "/predict/behavior", "/scenario"] {
+        assert!(document["paths"][path]["post"].is_object(), "{} is documented", path);
+    }
+    // Scenario charts are drawn, and cached, under /charts
+    assert!(document["paths"]["/scenario/chart"].is_null());
+    let diets = &document["components"]["schemas"]["FootprintRequest"]["properties"]["diet"]["enum"];
+    assert_eq!(diets, &json!(["omnivore", "pescatarian", "vegetarian", "vegan"]));
+    assert!(document["paths"]["/behaviors"]["get"].is_object());
+    assert!(document["paths"]["/models"]["get"].is_object());
+    assert!(document["paths"]["/train"]["post"].is_object());
+    assert!(document["paths"]["/jobs/{id}"]["delete"].is_object());
+    assert_eq!(document["paths"]["/predict/temperature"]["post"]["parameters"][0]["name"], "model");
+    // Scenario temperatures come from the energy-balance model: only the presets depend on a selected model
+    assert!(document["paths"]["/scenario"]["post"]["parameters"].is_null());
+    assert!(document["paths"]["/scenarios"]["get"]["parameters"][0]["description"].as_str().unwrap().contains("presets start from"));
+    assert_eq!(document["components"]["schemas"]["BehaviorRequest"]["properties"]["behavior"]["enum"][0], "driving");
+}
@@ -0,0 +1,69 @@
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::Client;
+
+fn client() -> Client {
+    Client::tracked(rocket_nn::build()).expect("valid rocket instance")
+}
+
+#[test]
+fn fit_charts_render_as_svg_and_png() {
+    let client = client();
+    let response = client.get("/charts/fit.svg?model=poly&degree=3&x=Year&y=Emissions").dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::SVG));
+    assert_eq!(response.headers().get_one("Cache-Control"), Some("public, max-age=3600"));
+    assert!(response.into_string().unwrap().contains("Emissions vs Year, polynomial (degree 3)"));
+
+    let response = client.get("/charts/fit.png").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::PNG));
+    assert!(response.into_bytes().unwrap().starts_with(b"\x89PNG"));
+}
+
+#[test]
+fn charts_are_cached_by_their_parameters() {
+    let client = client();
+    let first = client.get("/charts/fit.svg?model=poly&degree=3").dispatch();
+    assert_eq!(first.headers().get_one("X-Cache"), Some("MISS"));
+    let first = first.into_string().unwrap();
+
+    // The same chart, with the defaults spelled out in another order
+    let second = client.get("/charts/fit.svg?x=Emissions&degree=3&model=poly&y=Lowess&test_ratio=0.2").dispatch();
+    assert_eq!(second.headers().get_one("X-Cache"), Some("HIT"));
+    assert_eq!(second.into_string().unwrap(), first);
+
+    let other = client.get("/charts/fit.svg?model=poly&degree=2").dispatch();
+    assert_eq!(other.headers().get_one("X-Cache"), Some("MISS"));
+}
+
+#[test]
+fn scenario_charts_take_a_preset_or_points() {
+    let client = client();
+    let response = client.get("/charts/scenario.svg?preset=SSP2-4.5").dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_string().unwrap().contains("SSP2-4.5 scenario"));
+
+    let response = client.get("/charts/scenario.png?points=2023:40,2050:0,2100:0&name=net%20zero").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::PNG));
+}
+
+#[test]
+fn invalid_chart_parameters_are_rejected() {
+    let client = client();
+    let rejected = |uri: &str| {
+        let response = client.get(uri.to_string()).dispatch();
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{}", uri);
+        response.into_string().unwrap()
+    };
+
+    assert_eq!(rejected("/charts/fit.svg?model=forest"), "unknown model 'forest' (expected linear or poly)");
+    assert_eq!(rejected("/charts/fit.svg?model=poly&degree=11"), "degree must be between 1 and 10");
+    assert_eq!(rejected("/charts/fit.svg?x=Month"), "unknown column 'Month' (expected one of: Year, Emissions, Lowess)");
+    assert!(rejected("/charts/scenario.svg?preset=SSP9").starts_with("unknown preset 'SSP9'"));
+    assert_eq!(rejected("/charts/scenario.svg?points=2050:0,2040:1"), "points[1].year: must be after the year of the previous point");
+    assert_eq!(rejected("/charts/scenario.svg"), "give either a preset or points");
+    assert!(rejected("/charts/scenario.svg?points=2023:40,2100:0&model=climate").starts_with("model and version choose where a preset starts"));
+}
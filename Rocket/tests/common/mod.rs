@@ -0,0 +1,17 @@
+use rocket_nn::models::MODELS;
+use std::fs;
+use std::path::PathBuf;
+
+// A copy of the saved models the test can add to and overwrite, so published versions do not
+// reach the real directory; `prefix` keeps the copies of different test files apart
+pub fn model_directory(prefix: &str, test: &str) -> PathBuf {
+    let source: String = rocket::Config::figment().extract_inner("model_dir").unwrap_or_else(|_| MODELS.to_string());
+    let directory = std::env::temp_dir().join(format!("rocket-{}-{}-{}", prefix, test, std::process::id()));
+    let _ = fs::remove_dir_all(&directory);
+    fs::create_dir_all(&directory).unwrap();
+    for entry in fs::read_dir(source).unwrap() {
+        let path = entry.unwrap().path();
+        fs::copy(&path, directory.join(path.file_name().unwrap())).unwrap();
+    }
+    directory
+}
@@ -0,0 +1,49 @@
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::Client;
+use rocket::serde::json::{json, Value};
+
+fn client() -> Client {
+    Client::tracked(rocket_nn::build()).expect("valid rocket instance")
+}
+
+#[test]
+fn health_and_readiness_report_ok_once_models_are_loaded() {
+    let client = client();
+    let response = client.get("/healthz").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<Value>().unwrap()["status"], "ok");
+
+    let response = client.get("/readyz").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.into_json::<Value>().unwrap()["status"], "ready");
+}
+
+#[test]
+fn metrics_count_requests_and_predictions() {
+    let client = client();
+    let response = client.post("/api/v1/predict/temperature").header(ContentType::JSON).body(json!({ "emissions": 40.0 }).to_string()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    client.get("/nowhere/at/all").dispatch();
+
+    // Listing presets predicts nothing, a cached chart is drawn once, and scenario temperatures
+    // are the energy-balance model's, whichever climate model a preset starts from
+    assert_eq!(client.get("/api/v1/scenarios").dispatch().status(), Status::Ok);
+    for _ in 0..2 {
+        assert_eq!(client.get("/charts/scenario.svg?preset=SSP2-4.5").dispatch().status(), Status::Ok);
+    }
+    let pathway = json!({ "points": [{ "year": 2023, "emissions": 40.0 }, { "year": 2100, "emissions": 0.0 }] });
+    assert_eq!(client.post("/api/v1/scenario").header(ContentType::JSON).body(pathway.to_string()).dispatch().status(), Status::Ok);
+
+    let response = client.get("/metrics").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.content_type().unwrap().to_string().starts_with("text/plain"));
+    let text = response.into_string().unwrap();
+    assert!(text.contains("http_requests_total{method=\"POST\",route=\"/api/v1/predict/temperature\",status=\"200\"} 1"), "{}", text);
+    assert!(text.contains("http_request_duration_seconds_bucket{route=\"/api/v1/predict/temperature\",le=\"+Inf\"} 1"));
+    assert!(text.contains("http_request_duration_seconds_count{route=\"/api/v1/predict/temperature\"} 1"));
+    assert!(!text.contains("/nowhere"), "request paths never become labels");
+    assert!(text.contains("model_predictions_total{model=\"climate\",version=\"1\"} 1"), "{}", text);
+    assert!(text.contains("model_predictions_total{model=\"energy-balance\",version=\"\"} 2"), "{}", text);
+    assert!(text.contains("# TYPE model_load_seconds gauge"));
+    assert!(text.contains("model_load_seconds{model=\"footprint\",version=\"1\"}"));
+}
@@ -0,0 +1,219 @@
+mod common;
+
+use rocket::http::{ContentType, Header, Status};
+use rocket::local::blocking::{Client, LocalResponse};
+use rocket::serde::json::{json, Value};
+use std::fs;
+use std::path::Path;
+use std::thread;
+use std::time::{Duration, Instant};
+
+const TOKEN: &str = "test-token";
+
+fn client(directory: &Path, concurrency: usize) -> Client {
+    let figment = rocket::Config::figment()
+        .merge(("model_dir", directory.to_string_lossy().into_owned()))
+        .merge(("train_token", TOKEN))
+        .merge(("train_concurrency", concurrency));
+    Client::tracked(rocket_nn::build().configure(figment)).expect("valid rocket instance")
+}
+
+fn authorization(token: &str) -> Header<'static> {
+    Header::new("Authorization", format!("Bearer {}", token))
+}
+
+fn train<'c>(client: &'c Client, body: Value) -> LocalResponse<'c> {
+    client.post("/api/v1/train").header(ContentType::JSON).header(authorization(TOKEN)).body(body.to_string()).dispatch()
+}
+
+fn cancel<'c>(client: &'c Client, id: u64) -> LocalResponse<'c> {
+    client.delete(format!("/api/v1/jobs/{}", id)).header(authorization(TOKEN)).dispatch()
+}
+
+// Polls the job until it finishes, or fails after a while
+fn wait(client: &Client, id: u64) -> Value {
+    let start = Instant::now();
+    loop {
+        let job: Value = client.get(format!("/api/v1/jobs/{}", id)).dispatch().into_json().unwrap();
+        if !["queued", "running"].contains(&job["status"].as_str().unwrap()) {
+            return job;
+        }
+        assert!(start.elapsed() < Duration::from_secs(60), "job {} is still {}", id, job["status"]);
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn trained_models_are_published_to_the_registry() {
+    let directory = common::model_directory("jobs", "publish");
+    let client = client(&directory, 1);
+    let response = train(&client, json!({ "task": "climate", "epochs": 50, "learning_rate": 0.1 }));
+
+    assert_eq!(response.status(), Status::Accepted);
+    let job: Value = response.into_json().unwrap();
+    assert_eq!(job["hyperparameters"]["task"], "climate");
+    let job = wait(&client, job["id"].as_u64().unwrap());
+    assert_eq!(job["status"], "succeeded", "{}", job);
+    assert_eq!(job["artifact"], "climate-trained@1");
+    assert_eq!(job["progress"]["epoch"], 50);
+    assert!(directory.join("climate-trained-v1.json").is_file());
+
+    // The default model keeps answering; the new one answers at once when selected, without
+    // waiting for the directory to be polled
+    let predict = |query: &str| {
+        let response = client.post(format!("/api/v1/predict/temperature{}", query)).header(ContentType::JSON).body(json!({ "emissions": 40.0 }).to_string()).dispatch();
+        response.into_json::<Value>().unwrap()["artifact"].clone()
+    };
+    assert_eq!(predict(""), "climate@1");
+    assert_eq!(predict("?model=climate-trained"), "climate-trained@1");
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn progress_is_streamed_as_server_sent_events() {
+    let directory = common::model_directory("jobs", "events");
+    let client = client(&directory, 1);
+    let job: Value = train(&client, json!({ "task": "climate", "epochs": 20 })).into_json().unwrap();
+    let id = job["id"].as_u64().unwrap();
+
+    let response = client.get(format!("/api/v1/jobs/{}/events", id)).dispatch();
+    assert_eq!(response.content_type(), Some(ContentType::EventStream));
+    let stream = response.into_string().unwrap();
+    assert_eq!(stream.matches("event:progress").count(), 20, "{}", stream);
+    assert!(stream.contains("\"epoch\":20"));
+    assert!(stream.trim_end().rsplit("\n\n").next().unwrap().contains("event:finished"));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn finished_jobs_replay_only_their_latest_epochs() {
+    let directory = common::model_directory("jobs", "replay");
+    let client = client(&directory, 1);
+    let job: Value = train(&client, json!({ "task": "climate", "epochs": 1500 })).into_json().unwrap();
+    let id = job["id"].as_u64().unwrap();
+    assert_eq!(wait(&client, id)["status"], "succeeded");
+
+    // A late subscriber gets the last 1000 epochs, in order, then the final state
+    let stream = client.get(format!("/api/v1/jobs/{}/events", id)).dispatch().into_string().unwrap();
+    assert_eq!(stream.matches("event:progress").count(), 1000);
+    let first = stream.find("\"epoch\":501,").expect("epoch 501 is replayed");
+    assert!(!stream.contains("\"epoch\":500,"));
+    assert!(first < stream.find("\"epoch\":1500,").unwrap());
+    assert!(stream.trim_end().rsplit("\n\n").next().unwrap().contains("event:finished"));
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn queued_jobs_can_be_cancelled() {
+    // Without a training slot, jobs wait queued
+    let directory = common::model_directory("jobs", "cancel");
+    let client = client(&directory, 0);
+    let job: Value = train(&client, json!({ "task": "footprint", "algorithm": "forest", "trees": 5 })).into_json().unwrap();
+    assert_eq!(job["status"], "queued");
+    assert_eq!(job["name"], "footprint-forest-trained");
+    let id = job["id"].as_u64().unwrap();
+
+    let response = cancel(&client, id);
+    assert_eq!(response.status(), Status::Accepted);
+    assert_eq!(response.into_json::<Value>().unwrap()["status"], "cancelled");
+
+    let response = cancel(&client, id);
+    assert_eq!(response.status(), Status::Conflict);
+    let jobs: Value = client.get("/api/v1/jobs").dispatch().into_json().unwrap();
+    assert_eq!(jobs["jobs"][0]["status"], "cancelled");
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn invalid_hyperparameters_are_field_errors() {
+    let directory = common::model_directory("jobs", "invalid");
+    let client = client(&directory, 0);
+    let response = train(&client, json!({ "task": "climate", "degree": 20, "learning_rate": -1, "trees": 5, "name": "Bad Name" }));
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    let body: Value = response.into_json().unwrap();
+    let fields: Vec<&str> = body["errors"].as_array().unwrap().iter().map(|error| error["field"].as_str().unwrap()).collect();
+    assert_eq!(fields, ["degree", "learning_rate", "trees", "name"]);
+
+    // Temperature models are linear, polynomial or neural, and a polynomial is not trained by epochs
+    let response = train(&client, json!({ "task": "climate", "algorithm": "forest" }));
+    assert_eq!(response.into_json::<Value>().unwrap()["errors"][0]["message"], "climate models cannot be forest");
+    let response = train(&client, json!({ "task": "climate", "algorithm": "polynomial", "epochs": 10 }));
+    assert_eq!(response.into_json::<Value>().unwrap()["errors"][0]["message"], "does not apply to polynomial climate models");
+    let job: Value = train(&client, json!({ "task": "climate", "algorithm": "neural" })).into_json().unwrap();
+    assert_eq!(job["name"], "climate-neural-trained");
+    assert_eq!(job["hyperparameters"]["gradient_descent"]["epochs"], 5000);
+
+    // No job may publish a new version of a shipped artifact, default or not
+    for name in ["climate", "climate-polynomial", "climate-neural", "footprint", "footprint-forest", "-trained"] {
+        let response = train(&client, json!({ "task": "climate", "name": name }));
+        assert_eq!(response.status(), Status::UnprocessableEntity, "{}", name);
+        let message = response.into_json::<Value>().unwrap()["errors"][0]["message"].as_str().unwrap().to_string();
+        assert!(message.starts_with("must end in '-trained'"), "{}: {}", name, message);
+    }
+    let job: Value = train(&client, json!({ "task": "climate", "name": "climate-experiment-trained" })).into_json().unwrap();
+    assert_eq!(job["name"], "climate-experiment-trained");
+
+    let response = train(&client, json!({ "task": "weather" }));
+    assert_eq!(response.into_json::<Value>().unwrap()["errors"][0]["field"], "task");
+    assert_eq!(client.get("/api/v1/jobs/99").dispatch().status(), Status::NotFound);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn running_jobs_stop_as_cancelled() {
+    let directory = common::model_directory("jobs", "stop");
+    let client = client(&directory, 1);
+    let job: Value = train(&client, json!({ "task": "climate", "algorithm": "neural", "epochs": 100_000 })).into_json().unwrap();
+    let id = job["id"].as_u64().unwrap();
+
+    let start = Instant::now();
+    while client.get(format!("/api/v1/jobs/{}", id)).dispatch().into_json::<Value>().unwrap()["progress"].is_null() {
+        assert!(start.elapsed() < Duration::from_secs(10), "job {} never started", id);
+        thread::sleep(Duration::from_millis(5));
+    }
+    assert_eq!(cancel(&client, id).status(), Status::Accepted);
+    let job = wait(&client, id);
+    assert_eq!(job["status"], "cancelled", "{}", job);
+    assert!(job["error"].is_null());
+    assert!(!directory.join("climate-neural-trained-v1.json").exists());
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn training_needs_the_token() {
+    let directory = common::model_directory("jobs", "token");
+    let client = client(&directory, 0);
+    let body = json!({ "task": "climate" }).to_string();
+    let response = client.post("/api/v1/train").header(ContentType::JSON).body(body.clone()).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+    let response = client.post("/api/v1/train").header(ContentType::JSON).header(authorization("guess")).body(body.clone()).dispatch();
+    assert_eq!(response.status(), Status::Unauthorized);
+
+    let id = train(&client, json!({ "task": "climate" })).into_json::<Value>().unwrap()["id"].as_u64().unwrap();
+    assert_eq!(client.delete(format!("/api/v1/jobs/{}", id)).dispatch().status(), Status::Unauthorized);
+    assert_eq!(client.get(format!("/api/v1/jobs/{}", id)).dispatch().status(), Status::Ok);
+
+    // Without a configured token nobody can train
+    let figment = rocket::Config::figment().merge(("model_dir", directory.to_string_lossy().into_owned())).merge(("train_token", ""));
+    let closed = Client::tracked(rocket_nn::build().configure(figment)).unwrap();
+    let response = closed.post("/api/v1/train").header(ContentType::JSON).header(authorization("")).body(body).dispatch();
+    assert_eq!(response.status(), Status::Forbidden);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn full_queues_refuse_new_jobs() {
+    let directory = common::model_directory("jobs", "full");
+    let client = client(&directory, 0);
+    for _ in 0..rocket_nn::jobs::QUEUED {
+        assert_eq!(train(&client, json!({ "task": "footprint" })).status(), Status::Accepted);
+    }
+    let response = train(&client, json!({ "task": "footprint" }));
+    assert_eq!(response.status(), Status::TooManyRequests);
+
+    // Cancelled jobs no longer count
+    cancel(&client, 1);
+    assert_eq!(train(&client, json!({ "task": "footprint" })).status(), Status::Accepted);
+    fs::remove_dir_all(&directory).unwrap();
+}
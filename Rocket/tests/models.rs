@@ -0,0 +1,92 @@
+mod common;
+
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::Client;
+use rocket::serde::json::{self, json, Value};
+use std::fs;
+use std::path::Path;
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::sync::Arc;
+use std::thread;
+use std::time::{Duration, Instant};
+
+fn client(directory: &Path) -> Client {
+    let figment = rocket::Config::figment().merge(("model_dir", directory.to_string_lossy().into_owned())).merge(("model_poll_ms", 20));
+    Client::tracked(rocket_nn::build().configure(figment)).expect("valid rocket instance")
+}
+
+fn artifact(client: &Client) -> String {
+    let response = client.post("/api/v1/predict/temperature").header(ContentType::JSON).body(json!({ "emissions": 40.0 }).to_string()).dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    response.into_json::<Value>().unwrap()["artifact"].as_str().unwrap().to_string()
+}
+
+// Polls until the server answers with `expected`, or fails after a few seconds
+fn wait_for(client: &Client, expected: &str) {
+    let start = Instant::now();
+    while artifact(client) != expected {
+        assert!(start.elapsed() < Duration::from_secs(5), "the server still answers with {}", artifact(client));
+        thread::sleep(Duration::from_millis(20));
+    }
+}
+
+#[test]
+fn new_versions_are_picked_up_without_a_restart() {
+    let directory = common::model_directory("models", "reload");
+    let client = client(&directory);
+    assert_eq!(artifact(&client), "climate@1");
+
+    let mut saved: Value = json::from_str(&fs::read_to_string(directory.join("climate-v1.json")).unwrap()).unwrap();
+    saved["version"] = json!(2);
+    fs::write(directory.join("climate-v2.json"), saved.to_string()).unwrap();
+    wait_for(&client, "climate@2");
+
+    let models: Value = client.get("/api/v1/models").dispatch().into_json().unwrap();
+    let climate: Vec<&Value> = models["models"].as_array().unwrap().iter().filter(|model| model["name"] == "climate").collect();
+    assert_eq!(climate.len(), 2);
+    assert_eq!(climate[1]["default"], true);
+
+    // The previous version stays available by number
+    let response = client.post("/api/v1/predict/temperature?version=1").header(ContentType::JSON).body(json!({ "emissions": 40.0 }).to_string()).dispatch();
+    assert_eq!(response.into_json::<Value>().unwrap()["artifact"], "climate@1");
+
+    fs::remove_file(directory.join("climate-v2.json")).unwrap();
+    wait_for(&client, "climate@1");
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn unreadable_artifacts_keep_the_loaded_model() {
+    let directory = common::model_directory("models", "broken");
+    let client = client(&directory);
+
+    fs::write(directory.join("climate-v1.json"), "{ half written").unwrap();
+    thread::sleep(Duration::from_millis(200));
+    assert_eq!(artifact(&client), "climate@1");
+    assert_eq!(client.get("/api/v1/models").dispatch().status(), Status::Ok);
+    fs::remove_dir_all(&directory).unwrap();
+}
+
+#[test]
+fn a_concurrent_refresh_does_not_drop_a_published_model() {
+    let directory = common::model_directory("models", "race");
+    let models = rocket_nn::models::Models::load(&directory.to_string_lossy()).unwrap();
+    let saved: Value = json::from_str(&fs::read_to_string(directory.join("climate-v1.json")).unwrap()).unwrap();
+
+    // Like the reload poll running nonstop while a training job publishes and refreshes
+    let stop = Arc::new(AtomicBool::new(false));
+    let polls: Vec<_> = (0..4).map(|_| {
+        let (models, stop) = (models.clone(), stop.clone());
+        thread::spawn(move || while !stop.load(Ordering::Relaxed) { models.refresh().unwrap(); })
+    }).collect();
+    for version in 2..100 {
+        let mut artifact = saved.clone();
+        artifact["version"] = json!(version);
+        fs::write(directory.join(format!("climate-v{}.json", version)), artifact.to_string()).unwrap();
+        models.refresh().unwrap();
+        assert_eq!(models.climate(&Default::default()).unwrap().metadata.version, version as u32);
+    }
+    stop.store(true, Ordering::Relaxed);
+    polls.into_iter().for_each(|poll| poll.join().unwrap());
+    fs::remove_dir_all(&directory).unwrap();
+}
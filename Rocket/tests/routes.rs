@@ -0,0 +1,169 @@
+use rocket::error::ErrorKind;
+use rocket::http::{ContentType, Status};
+use rocket::local::blocking::Client;
+
+fn client() -> Client {
+    Client::tracked(rocket_nn::build()).expect("valid rocket instance")
+}
+
+#[test]
+fn index_serves_the_form() {
+    let client = client();
+    let response = client.get("/").dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::HTML));
+    assert_eq!(response.headers().get_one("Cache-Control"), Some("no-cache"));
+    let page = response.into_string().unwrap();
+    assert!(page.contains("<form id=\"impactForm\">"));
+    assert!(!page.contains("{{"), "every placeholder is filled");
+}
+
+#[test]
+fn index_lists_the_behaviors_and_models() {
+    let client = client();
+    let page = client.get("/").dispatch().into_string().unwrap();
+
+    assert!(page.contains("<option value=\"driving\">Driving a petrol car</option>"));
+    assert!(page.contains("<option value=\"biweekly\">Bi-Weekly</option>"));
+    assert!(page.contains("<li>climate@1 (climate, default): polynomial (degree 3) emissions, linear temperature"));
+    assert!(page.contains("<li>footprint@1 (footprint, default): linear, trained on"));
+    assert!(page.contains("{\"value\":3,\"label\":\"3 day(s) per week\"}"));
+}
+
+#[test]
+fn assets_are_served_with_their_type_and_cache_policy() {
+    let client = client();
+    let response = client.get("/script.js").dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::JavaScript));
+    assert_eq!(response.headers().get_one("Cache-Control"), Some("public, max-age=3600"));
+    assert!(response.into_string().unwrap().contains("function predictImpact()"));
+
+    let response = client.get("/style.css").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::CSS));
+
+    let response = client.get("/scenario.html").dispatch();
+    assert_eq!(response.status(), Status::Ok);
+    assert_eq!(response.content_type(), Some(ContentType::HTML));
+    assert!(response.into_string().unwrap().contains("<svg id=\"editor\""));
+}
+
+#[test]
+fn only_files_of_the_asset_directory_are_served() {
+    let client = client();
+
+    assert_eq!(client.get("/index.html").dispatch().status(), Status::NotFound);
+    assert_eq!(client.get("/../Cargo.toml").dispatch().status(), Status::NotFound);
+    assert_eq!(client.get("/missing.js").dispatch().status(), Status::NotFound);
+}
+
+#[test]
+fn predict_forecasts_emissions_and_temperature() {
+    let client = client();
+    let response = client.post("/predict")
+        .header(ContentType::Form)
+        .body("behavior=driving&frequency=daily&duration=2&year=2000")
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    let body = response.into_string().unwrap();
+    assert!(body.starts_with("Driving a petrol car, 2 hour(s) per day: 730 hours a year, or about 29220 km at 0.17 kg CO₂/km, emitting 4967 kg CO₂ a year.\n"));
+    assert!(body.contains("In 2000: global emissions of "));
+    assert!(body.contains("GtCO₂ (95 %: "));
+    assert!(body.contains("°C (95 %: "));
+    assert!(!body.contains("extrapolation"));
+}
+
+#[test]
+fn predict_flags_years_beyond_the_training_data() {
+    let client = client();
+    let response = client.post("/predict")
+        .header(ContentType::Form)
+        .body("behavior=driving&frequency=daily&duration=2&year=2100")
+        .dispatch();
+
+    assert_eq!(response.status(), Status::Ok);
+    assert!(response.into_string().unwrap().contains("so this is an extrapolation"));
+}
+
+#[test]
+fn predict_rejects_an_incomplete_form() {
+    let client = client();
+    let response = client.post("/predict")
+        .header(ContentType::Form)
+        .body("behavior=driving")
+        .dispatch();
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+#[test]
+fn predict_rejects_behaviors_missing_from_the_catalogue() {
+    let client = client();
+    let response = client.post("/predict")
+        .header(ContentType::Form)
+        .body("behavior=teleporting&frequency=daily&duration=2&year=2000")
+        .dispatch();
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert!(response.into_string().unwrap().starts_with("unknown behavior 'teleporting' (expected one of: driving, "));
+}
+
+#[test]
+fn predict_rejects_durations_beyond_the_frequency() {
+    let client = client();
+    let response = client.post("/predict")
+        .header(ContentType::Form)
+        .body("behavior=driving&frequency=weekly&duration=8&year=2000")
+        .dispatch();
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+    assert_eq!(response.into_string().unwrap(), "a weekly duration must be between 1 and 7");
+}
+
+#[test]
+fn predict_rejects_years_out_of_range() {
+    let client = client();
+    let response = client.post("/predict")
+        .header(ContentType::Form)
+        .body("behavior=driving&frequency=daily&duration=2&year=3000")
+        .dispatch();
+
+    assert_eq!(response.status(), Status::UnprocessableEntity);
+}
+
+#[test]
+fn unknown_pages_are_caught() {
+    let client = client();
+    let response = client.get("/nowhere").dispatch();
+
+    assert_eq!(response.status(), Status::NotFound);
+    assert_eq!(response.into_string().unwrap(), "404: Page '/nowhere' not found");
+}
+
+#[test]
+fn ignition_fails_without_saved_models() {
+    let rocket = rocket_nn::build().configure(rocket::Config::figment().merge(("model_dir", "missing")));
+
+    let error = Client::tracked(rocket).expect_err("ignition should fail");
+    assert!(matches!(error.kind(), ErrorKind::FailedFairings(fairings) if fairings[0].name == "Models"));
+}
+
+#[test]
+fn ignition_fails_without_the_catalogue() {
+    let rocket = rocket_nn::build().configure(rocket::Config::figment().merge(("catalogue", "missing.csv")));
+
+    let error = Client::tracked(rocket).expect_err("ignition should fail");
+    assert!(matches!(error.kind(), ErrorKind::FailedFairings(fairings) if fairings[0].name == "Catalogue"));
+}
+
+#[test]
+fn ignition_fails_without_the_asset_directory() {
+    let rocket = rocket_nn::build().configure(rocket::Config::figment().merge(("asset_dir", "missing")));
+
+    let error = Client::tracked(rocket).expect_err("ignition should fail");
+    assert!(matches!(error.kind(), ErrorKind::FailedFairings(fairings) if fairings[0].name == "Assets"));
+}
@@ -0,0 +1,142 @@
+const WIDTH = 640;
+const HEIGHT = 300;
+const PAD = 40;
+const SVG = 'http://www.w3.org/2000/svg';
+
+let scenario = { name: 'custom', years: [], emissions: [], min: 0, max: 1 };
+let dragging = null;
+
+function interpolate(points, year) {
+    for (let i = 1; i < points.length; i++) {
+        if (year <= points[i].year) {
+            const [a, b] = [points[i - 1], points[i]];
+            return a.emissions + (b.emissions - a.emissions) * (year - a.year) / (b.year - a.year);
+        }
+    }
+    return points[points.length - 1].emissions;
+}
+
+function x(year) {
+    const [first, last] = [scenario.years[0], scenario.years[scenario.years.length - 1]];
+    return PAD + (WIDTH - 2 * PAD) * (year - first) / (last - first);
+}
+
+function y(emissions) {
+    return HEIGHT - PAD - (HEIGHT - 2 * PAD) * (emissions - scenario.min) / (scenario.max - scenario.min);
+}
+
+function element(name, attributes, text) {
+    const node = document.createElementNS(SVG, name);
+    for (const [key, value] of Object.entries(attributes)) {
+        node.setAttribute(key, value);
+    }
+    if (text !== undefined) {
+        node.textContent = text;
+    }
+    return node;
+}
+
+function drawEditor() {
+    const editor = document.getElementById('editor');
+    while (editor.firstChild) {
+        editor.removeChild(editor.firstChild);
+    }
+
+    const [first, last] = [scenario.years[0], scenario.years[scenario.years.length - 1]];
+    editor.appendChild(element('line', { x1: PAD, x2: WIDTH - PAD, y1: y(0), y2: y(0), class: 'zero' }));
+    editor.appendChild(element('text', { x: 4, y: y(scenario.max) + 4 }, scenario.max.toFixed(0)));
+    editor.appendChild(element('text', { x: 4, y: y(0) + 4 }, '0'));
+    editor.appendChild(element('text', { x: 4, y: y(scenario.min) + 4 }, scenario.min.toFixed(0)));
+    editor.appendChild(element('text', { x: PAD, y: HEIGHT - 10 }, first));
+    editor.appendChild(element('text', { x: WIDTH - PAD - 30, y: HEIGHT - 10 }, last));
+    editor.appendChild(element('text', { x: WIDTH / 2 - 60, y: 20 }, 'Emissions (GtCO₂ per year)'));
+
+    const points = scenario.years.map((year, i) => `${x(year)},${y(scenario.emissions[i])}`).join(' ');
+    editor.appendChild(element('polyline', { points, class: 'pathway' }));
+    scenario.years.forEach((year, i) => {
+        const handle = element('circle', { cx: x(year), cy: y(scenario.emissions[i]), r: 7, class: 'handle' });
+        handle.appendChild(element('title', {}, `${year}: ${scenario.emissions[i].toFixed(1)} GtCO₂`));
+        handle.addEventListener('pointerdown', event => {
+            dragging = i;
+            editor.setPointerCapture(event.pointerId);
+        });
+        editor.appendChild(handle);
+    });
+}
+
+function applyPreset(preset) {
+    scenario.name = preset.name;
+    scenario.emissions = scenario.years.map(year => interpolate(preset.points, year));
+    drawEditor();
+    update();
+}
+
+function update() {
+    const request = {
+        name: scenario.name,
+        points: scenario.years.map((year, i) => ({ year, emissions: scenario.emissions[i] })),
+    };
+    const summary = document.getElementById('summary');
+    const chart = document.getElementById('chart');
+
+    fetch('/api/v1/scenario', { method: 'POST', headers: { 'Content-Type': 'application/json' }, body: JSON.stringify(request) })
+        .then(response => response.ok ? response.json() : Promise.reject(`Request failed (${response.status})`))
+        .then(body => {
+            const last = body.years.length - 1;
+            const temperature = body.temperature;
+            summary.innerText = `${body.name}: a temperature anomaly of ${temperature.mean[last].toFixed(2)} °C in ${body.years[last]} ` +
+                `(95 %: ${temperature.low[last].toFixed(2)} to ${temperature.high[last].toFixed(2)}) relative to 1951–1980.`;
+        })
+        .catch(error => { summary.innerText = error; });
+    // The same pathway as an image URL, so the chart can be cached and embedded elsewhere
+    const points = request.points.map(point => `${point.year}:${point.emissions.toFixed(2)}`).join(',');
+    chart.src = `/charts/scenario.svg?name=${encodeURIComponent(scenario.name)}&points=${points}`;
+}
+
+function loadPresets() {
+    fetch('/api/v1/scenarios')
+        .then(response => response.ok ? response.json() : Promise.reject(`Cannot load the presets (${response.status})`))
+        .then(body => {
+            // A handle at the start and then every decade
+            scenario.years = [body.start];
+            for (let year = Math.floor(body.start / 10) * 10 + 10; year <= body.end; year += 10) {
+                scenario.years.push(year);
+            }
+            const values = body.presets.flatMap(preset => preset.points.map(point => point.emissions));
+            scenario.min = Math.min(0, ...values) * 1.2;
+            scenario.max = Math.max(...values) * 1.2;
+
+            const buttons = document.getElementById('presets');
+            for (const preset of body.presets) {
+                const button = document.createElement('button');
+                button.type = 'button';
+                button.innerText = preset.name;
+                button.addEventListener('click', () => applyPreset(preset));
+                buttons.appendChild(button);
+            }
+            applyPreset(body.presets[0]);
+        })
+        .catch(error => { document.getElementById('summary').innerText = error; });
+}
+
+document.addEventListener('DOMContentLoaded', () => {
+    const editor = document.getElementById('editor');
+    editor.addEventListener('pointermove', event => {
+        if (dragging === null) {
+            return;
+        }
+        const box = editor.getBoundingClientRect();
+        const top = (event.clientY - box.top) * HEIGHT / box.height;
+        const value = scenario.min + (HEIGHT - PAD - top) * (scenario.max - scenario.min) / (HEIGHT - 2 * PAD);
+        scenario.emissions[dragging] = Math.min(scenario.max, Math.max(scenario.min, value));
+        scenario.name = 'custom';
+        drawEditor();
+    });
+    editor.addEventListener('pointerup', () => {
+        if (dragging !== null) {
+            dragging = null;
+            update();
+        }
+    });
+    loadPresets();
+});
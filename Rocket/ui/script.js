@@ -1,3 +1,5 @@
+const frequencies = JSON.parse(document.getElementById('frequencies').textContent);
+
 function updateDurationOptions() {
     const frequency = document.getElementById('frequency').value;
     const duration = document.getElementById('duration');
@@ -6,37 +8,20 @@ function updateDurationOptions() {
         duration.removeChild(duration.firstChild);
     }
 
-    if (frequency === 'daily') {
-        for (let i = 1; i <= 24; i++) {
-            duration.add(new Option(`${i} hour(s) per day`, i));
-        }
-    } else if (frequency === 'weekly') {
-        for (let i = 1; i <= 7; i++) {
-            duration.add(new Option(`${i} day(s) per week`, i));
-        }
-    } else if (frequency === 'biweekly') {
-        for (let i = 1; i <= 14; i++) {
-            duration.add(new Option(`${i} day(s) per two weeks`, i));
-        }
-    } else if (frequency === 'monthly') {
-        for (let i = 1; i <= 31; i++) {
-            duration.add(new Option(`${i} day(s) per month`, i));
-        }
-    } else if (frequency === 'annually') {
-        for (let i = 1; i <= 12; i++) {
-            duration.add(new Option(`${i} month(s) per year`, i));
-        }
+    const selected = frequencies.find(item => item.name === frequency);
+    for (const option of selected ? selected.durations : []) {
+        duration.add(new Option(option.label, option.value));
     }
 }
 
 function predictImpact() {
-    const behavior = document.getElementById('behavior').value;
-    const frequency = document.getElementById('frequency').value;
-    const duration = document.getElementById('duration').value;
-
-    const resultText = `Predicting the climate impact of ${behavior}, with a frequency of ${frequency} and a duration of ${duration}.`;
+    const form = document.getElementById('impactForm');
+    const result = document.getElementById('result');
 
-    document.getElementById('result').innerText = resultText;
+    fetch('/predict', { method: 'POST', body: new URLSearchParams(new FormData(form)) })
+        .then(response => response.ok ? response.text() : response.text().then(text => Promise.reject(text || `Request failed (${response.status})`)))
+        .then(text => { result.innerText = text; })
+        .catch(error => { result.innerText = error; });
 }
 
 document.addEventListener('DOMContentLoaded', () => {